@@ -1,5 +1,7 @@
-use sdl3::keyboard::Scancode;
+use std::time::Duration;
+use sdl3::keyboard::{Keycode, Scancode};
 use specs::{Join, ReadExpect, System, WriteStorage};
+use fennel_2d::plugin::Plugin2D;
 use fennel_2d::sprite::{SpriteFactory, SpriteRenderingSystem};
 use fennel_graphics::graphics::Sprite;
 use fennel_graphics::plugin::event_handler::PluginEvent;
@@ -12,21 +14,23 @@ impl<'a> System<'a> for MySystem {
     type SystemData = (ReadExpect<'a, Vec<PluginEvent>>, WriteStorage<'a, Sprite>);
 
     fn run(&mut self, (events, mut sprites): Self::SystemData) {
+        // key presses and their repeats, releases come as `PluginEvent::KeyUpEvent`
         events
             .iter()
-            .filter(|event| matches!(event, PluginEvent::KeyboardEvent(_)))
-            .for_each(|event| {
+            .filter_map(|event| match event {
+                PluginEvent::KeyboardEvent(event) => event.scancode,
+                _ => None,
+            })
+            .for_each(|scancode| {
                for sprite in (&mut sprites).join() {
-                   if let PluginEvent::KeyboardEvent(event) = event {
-                       match event.scancode.unwrap() {
-                           Scancode::D => {
-                               sprite.transform.position.0 += 4.0;
-                           },
-                           Scancode::A => {
-                               sprite.transform.position.0 -= 4.0;
-                           }
-                           _ => {},
+                   match scancode {
+                       Scancode::D => {
+                           sprite.transform.position.0 += 4.0;
+                       },
+                       Scancode::A => {
+                           sprite.transform.position.0 -= 4.0;
                        }
+                       _ => {},
                    }
                }
             });
@@ -35,10 +39,17 @@ impl<'a> System<'a> for MySystem {
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
+    let mut graphics = GraphicsPlugin::new("game", (800, 600), "assets://")
+        .with_debug_overlay("Terminus", 14.0)
+        .with_screenshot_key(Keycode::F12, "screenshots");
+    if cfg!(debug_assertions) {
+        graphics = graphics.with_hot_reload(Duration::from_millis(500));
+    }
     let app = AppBuilder::new()
         .config("examples/game/game.toml")
         .with_component::<Sprite, SpriteFactory>("sprite", SpriteFactory)
-        .with_plugin(GraphicsPlugin::new("game", (800, 600), "assets"))
+        .with_plugin(graphics)
+        .with_plugin(Plugin2D::new().with_animation("assets://tiles.anim.ron"))
         .register_system(MySystem, "my_system", &["event_gather_system"])
         .register_system(SpriteRenderingSystem, "sprite_rendering_system", &[])
         .build()?;
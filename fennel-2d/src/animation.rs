@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use anyhow::Context;
+use log::error;
+use ron::Value;
+use serde::Deserialize;
+use specs::{Entities, Entity, Join, LazyUpdate, ReadExpect, System, World, WorldExt, WriteExpect, WriteStorage};
+use fennel_graphics::graphics::Sprite;
+use fennel_registry::{impl_component_factory, ComponentFactory};
+use fennel_resources::vfs;
+
+impl_component_factory!(AnimatorFactory, Animator);
+
+/// Frames shorter than this are treated as this long so that zero-length frames can't stall
+/// the system
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+/// How a clip behaves once it reaches its last frame
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackMode {
+    /// Start over from the first frame
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forwards again
+    PingPong,
+    /// Stop on the last frame and emit [`AnimationFinished`]
+    Once,
+}
+
+/// A single frame of a clip
+#[derive(Deserialize, Debug, Clone)]
+pub struct Frame {
+    /// Image to show, usually a `"sheet:frame"` reference
+    pub image: String,
+    /// How long the frame is shown, in seconds
+    pub duration: f32,
+}
+
+/// A named sequence of frames, e.g. "idle" or "run"
+#[derive(Deserialize, Debug, Clone)]
+pub struct Clip {
+    /// Frames in playback order
+    pub frames: Vec<Frame>,
+    /// Playback mode
+    #[serde(default)]
+    pub mode: PlaybackMode,
+}
+
+/// Animation asset: a set of named clips, usually of one character
+///
+/// # Example
+/// ```ron
+/// (
+///     name: "player",
+///     clips: {
+///         "idle": (
+///             mode: Loop,
+///             frames: [(image: "tiles:0", duration: 0.25), (image: "tiles:1", duration: 0.25)],
+///         ),
+///     },
+/// )
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct Animation {
+    /// Unique name of the animation
+    pub name: String,
+    /// Clip name - clip
+    pub clips: HashMap<String, Clip>,
+}
+
+/// ECS resource holding all loaded [`Animation`]s
+#[derive(Default, Debug)]
+pub struct Animations {
+    map: HashMap<String, Animation>,
+}
+
+/// Component that plays a clip of an [`Animation`] on the entity's [`Sprite`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct Animator {
+    /// Name of the [`Animation`]
+    pub animation: String,
+    /// Name of the currently playing clip
+    pub clip: String,
+    /// Playback speed multiplier
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    /// Is the clip playing? Paused animators keep their current frame
+    #[serde(default = "default_playing")]
+    pub playing: bool,
+    #[serde(skip)]
+    frame: usize,
+    #[serde(skip)]
+    elapsed: f32,
+    #[serde(skip)]
+    reversed: bool,
+    #[serde(skip)]
+    finished: bool,
+}
+
+/// Event emitted when a [`PlaybackMode::Once`] clip reaches its last frame
+#[derive(Debug, Clone)]
+pub struct AnimationFinished {
+    /// The entity owning the [`Animator`]
+    pub entity: Entity,
+    /// Name of the [`Animation`]
+    pub animation: String,
+    /// Name of the finished clip
+    pub clip: String,
+}
+
+/// ECS system advancing [`Animator`]s by real elapsed time and updating their [`Sprite`]s
+///
+/// [`AnimationFinished`] events are collected in the `Vec<AnimationFinished>` resource, which
+/// is cleared every time this system runs.
+#[derive(Default)]
+pub struct AnimationSystem {
+    last_run: Option<Instant>,
+}
+
+impl specs::Component for Animator {
+    type Storage = specs::VecStorage<Self>;
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_playing() -> bool {
+    true
+}
+
+impl Animations {
+    /// Creates a new empty instance of [`Animations`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert an animation, replacing the one with the same name
+    pub fn insert(&mut self, animation: Animation) {
+        self.map.insert(animation.name.clone(), animation);
+    }
+
+    /// Fetches an animation by name
+    pub fn get(&self, name: &str) -> Option<&Animation> {
+        self.map.get(name)
+    }
+
+    /// Load an animation from a RON file and insert it
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file = vfs::read(path)
+            .with_context(|| format!("failed to read animation {}", path.display()))?;
+        let animation: Animation = ron::de::from_bytes(&file)
+            .with_context(|| format!("failed to parse animation {}", path.display()))?;
+        self.insert(animation);
+        Ok(())
+    }
+}
+
+impl Animator {
+    /// Creates a new instance of [`Animator`] playing `clip` of `animation`
+    pub fn new(animation: String, clip: String) -> Self {
+        Self {
+            animation,
+            clip,
+            speed: default_speed(),
+            playing: default_playing(),
+            frame: 0,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    /// Switch to another clip and play it from the start. Does nothing if the clip is already
+    /// playing
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip || self.finished {
+            self.clip = clip.to_string();
+            self.frame = 0;
+            self.elapsed = 0.0;
+            self.reversed = false;
+            self.finished = false;
+        }
+        self.playing = true;
+    }
+
+    /// Index of the current frame in the clip
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Has a [`PlaybackMode::Once`] clip reached its end?
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advance the animator by `delta` seconds
+    ///
+    /// # Returns
+    /// `true` if a [`PlaybackMode::Once`] clip has just finished
+    pub fn advance(&mut self, clip: &Clip, delta: f32) -> bool {
+        if !self.playing || self.finished || clip.frames.is_empty() {
+            return false;
+        }
+        let last = clip.frames.len() - 1;
+        self.frame = self.frame.min(last);
+        self.elapsed += delta * self.speed;
+
+        loop {
+            let duration = clip.frames[self.frame].duration.max(MIN_FRAME_DURATION);
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+
+            match clip.mode {
+                PlaybackMode::Loop => {
+                    self.frame = if self.frame == last { 0 } else { self.frame + 1 };
+                }
+                PlaybackMode::PingPong => {
+                    if last == 0 {
+                        continue;
+                    }
+                    if self.reversed && self.frame == 0 {
+                        self.reversed = false;
+                    } else if !self.reversed && self.frame == last {
+                        self.reversed = true;
+                    }
+                    self.frame = if self.reversed { self.frame - 1 } else { self.frame + 1 };
+                }
+                PlaybackMode::Once => {
+                    if self.frame == last {
+                        self.elapsed = 0.0;
+                        self.finished = true;
+                        return true;
+                    }
+                    self.frame += 1;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl<'a> System<'a> for AnimationSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Animations>,
+        WriteStorage<'a, Animator>,
+        WriteStorage<'a, Sprite>,
+        WriteExpect<'a, Vec<AnimationFinished>>,
+    );
+
+    fn run(&mut self, (entities, animations, mut animators, mut sprites, mut finished): Self::SystemData) {
+        let now = Instant::now();
+        let delta = self.last_run
+            .map(|last_run| now.duration_since(last_run).as_secs_f32())
+            .unwrap_or(0.0);
+        self.last_run = Some(now);
+        finished.clear();
+
+        for (entity, animator, sprite) in (&entities, &mut animators, &mut sprites).join() {
+            let clip = match animations.get(&animator.animation).and_then(|a| a.clips.get(&animator.clip)) {
+                Some(clip) => clip,
+                None => {
+                    error!("animation clip {}:{} not found", animator.animation, animator.clip);
+                    continue;
+                }
+            };
+
+            if animator.advance(clip, delta) {
+                finished.push(AnimationFinished {
+                    entity,
+                    animation: animator.animation.clone(),
+                    clip: animator.clip.clone(),
+                });
+            }
+            if let Some(frame) = clip.frames.get(animator.frame) {
+                sprite.set_image(&frame.image);
+            }
+        }
+    }
+}
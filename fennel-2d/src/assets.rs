@@ -0,0 +1,40 @@
+use std::collections::BTreeSet;
+use log::error;
+use ron::Value;
+use specs::{Entity, Join, LazyUpdate, ReadExpect, ReadStorage, System, World, WorldExt};
+use fennel_graphics::plugin::system::Assets;
+use fennel_registry::{impl_component_factory, ComponentFactory};
+pub use fennel_registry::AssetGroups;
+
+impl_component_factory!(AssetGroupsFactory, AssetGroups);
+
+/// ECS system loading and unloading the groups referenced by [`AssetGroups`] components
+#[derive(Default)]
+pub struct AssetGroupSystem {
+    /// Groups requested so far
+    loaded: BTreeSet<String>,
+}
+
+impl<'a> System<'a> for AssetGroupSystem {
+    type SystemData = (ReadStorage<'a, AssetGroups>, ReadExpect<'a, Assets>);
+
+    fn run(&mut self, (asset_groups, assets): Self::SystemData) {
+        let wanted: BTreeSet<String> = asset_groups.join().flat_map(|groups| groups.groups.iter().cloned()).collect();
+        let (load, unload) = group_changes(&self.loaded, &wanted);
+        for group in &load {
+            assets.load_group(group);
+        }
+        for group in &unload {
+            assets.unload_group(group);
+        }
+        self.loaded = wanted;
+    }
+}
+
+/// Groups to load and groups to unload to go from `loaded` to `wanted`
+pub(crate) fn group_changes(loaded: &BTreeSet<String>, wanted: &BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+    (
+        wanted.difference(loaded).cloned().collect(),
+        loaded.difference(wanted).cloned().collect(),
+    )
+}
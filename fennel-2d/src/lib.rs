@@ -1,4 +1,14 @@
 /// Module providing basic sprite systems
 pub mod sprite;
 /// Provides the plugin interface
-pub mod plugin;
\ No newline at end of file
+pub mod plugin;
+/// Module providing frame-based sprite animations
+pub mod animation;
+/// Module providing tilemaps and Tiled map import
+pub mod tilemap;
+/// Module providing retained-mode game UI widgets
+pub mod ui;
+/// Module loading manifest asset groups per scene
+pub mod assets;
+#[cfg(test)]
+mod tests;
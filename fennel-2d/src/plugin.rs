@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::path::PathBuf;
+use specs::{DispatcherBuilder, World, WorldExt};
+use fennel_plugins::Plugin;
+use fennel_registry::{ComponentRegistry, SpawnedBy};
+use crate::assets::{AssetGroupSystem, AssetGroups, AssetGroupsFactory};
+use crate::animation::{AnimationFinished, AnimationSystem, Animations, Animator, AnimatorFactory};
+use fennel_physics::shapes_2d::rigid_body::RigidBody;
+use crate::tilemap::{MapObject, TileCollider, Tilemap, TilemapFactory, TilemapRenderingSystem};
+use crate::ui;
+use crate::ui::system::UiSystem;
+use crate::ui::UiEvent;
+
+/// The 2D plugin for `fennel_runtime`
+///
+/// Registers the [`Animator`] (as `"animator"` in scenes), [`Tilemap`] (as `"tilemap"`),
+/// [`AssetGroups`] (as `"asset_groups"`) and UI widget components, loads animations and adds
+/// the [`AnimationSystem`], the [`TilemapRenderingSystem`], the [`AssetGroupSystem`] and the
+/// [`UiSystem`] to the dispatcher.
+///
+/// The [`UiSystem`] depends on the `event_gather_system` of the graphics plugin, so this plugin
+/// must be added after it.
+#[derive(Default)]
+pub struct Plugin2D {
+	animations: Vec<PathBuf>,
+}
+
+impl Plugin2D {
+	/// Create a new instance of [`Plugin2D`]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Load an animation RON file when the plugin is prepared
+	///
+	/// # Arguments
+	/// * `path`: path to the animation file
+	pub fn with_animation<P: Into<PathBuf>>(mut self, path: P) -> Self {
+		self.animations.push(path.into());
+		self
+	}
+}
+
+impl Plugin for Plugin2D {
+	fn prepare(
+		&mut self,
+		dispatcher_builder: &mut DispatcherBuilder,
+		world: &mut World,
+	) -> Result<(), Box<dyn Error>> {
+		let mut animations = Animations::new();
+		for path in &self.animations {
+			animations.load_file(path)?;
+		}
+		let finished: Vec<AnimationFinished> = Vec::new();
+
+		world.register::<Animator>();
+		world.register::<Tilemap>();
+		world.register::<MapObject>();
+		world.register::<TileCollider>();
+		world.register::<RigidBody>();
+		world.register::<AssetGroups>();
+		world.register::<SpawnedBy>();
+		let mut registry = world.write_resource::<ComponentRegistry>();
+		registry.register("animator", Box::new(AnimatorFactory));
+		registry.register("tilemap", Box::new(TilemapFactory));
+		registry.register("asset_groups", Box::new(AssetGroupsFactory));
+		drop(registry);
+		ui::register(world);
+		let ui_events: Vec<UiEvent> = Vec::new();
+		world.insert(animations);
+		world.insert(finished);
+		world.insert(ui_events);
+		dispatcher_builder.add(AnimationSystem::default(), "animation_system", &[]);
+		dispatcher_builder.add(TilemapRenderingSystem, "tilemap_rendering_system", &[]);
+		dispatcher_builder.add(AssetGroupSystem::default(), "asset_group_system", &[]);
+		dispatcher_builder.add(UiSystem::default(), "ui_system", &["event_gather_system"]);
+		Ok(())
+	}
+
+	fn update(&mut self, _delta_time: f64) -> Result<(), Box<dyn Error>> {
+		Ok(())
+	}
+
+	fn name(&self) -> &'static str {
+		"2d_plugin"
+	}
+}
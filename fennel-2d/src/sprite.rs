@@ -1,23 +1,28 @@
 use log::error;
 use ron::Value;
-use specs::{Entity, Join, LazyUpdate, ReadStorage, System, World, WorldExt, WriteExpect};
+use specs::{Entity, Join, LazyUpdate, ReadExpect, System, World, WorldExt, WriteExpect, WriteStorage};
 use fennel_graphics::graphics::{Drawable, Sprite};
 use fennel_graphics::plugin::system::RenderQueue;
 use fennel_registry::{impl_component_factory, ComponentFactory};
+use fennel_resources::manager::SharedResourceManager;
 
 impl_component_factory!(SpriteFactory, Sprite);
 
 /// ECS system that queues [`Sprite`]s for rendering
 ///
-/// The system reads all Sprite components from the world and obtains a mutable
-/// reference to the host App through the HostPtr resource
+/// The system resolves the images of the sprites to handles once they're loaded, so the
+/// renderer doesn't look them up by name, and queues the sprites to the [`RenderQueue`]
 pub struct SpriteRenderingSystem;
 
 impl<'a> System<'a> for SpriteRenderingSystem {
-    type SystemData = (ReadStorage<'a, Sprite>, WriteExpect<'a, RenderQueue>);
+    type SystemData = (WriteStorage<'a, Sprite>, WriteExpect<'a, RenderQueue>, ReadExpect<'a, SharedResourceManager>);
 
-    fn run(&mut self, (sprites, mut rq): Self::SystemData) {
-        for sprite in (&sprites).join() {
+    fn run(&mut self, (mut sprites, mut rq, resource_manager): Self::SystemData) {
+        let manager = resource_manager.read();
+        for sprite in (&mut sprites).join() {
+            if let Err(e) = sprite.resolve(&manager) {
+                error!("failed to resolve image {}: {e}", sprite.image);
+            }
             rq.queue.push(Drawable::Image(sprite.clone()));
         }
     }
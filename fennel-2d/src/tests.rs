@@ -0,0 +1,284 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use nalgebra::Vector2;
+use specs::{Builder, RunNow, World, WorldExt};
+use fennel_graphics::graphics::{Drawable, SpriteImage};
+use fennel_graphics::plugin::system::{Camera, RenderQueue};
+use fennel_graphics::resources::sprite_sheet::SpriteSheet;
+use fennel_resources::manager::SharedResourceManager;
+use fennel_physics::body::Body;
+use crate::assets::group_changes;
+use crate::animation::{Animator, Clip, Frame, PlaybackMode};
+use crate::tilemap::{parse_tmj, parse_tmx, Tile, Tilemap, TilemapRenderingSystem};
+use crate::ui::layout::{layout, nine_slice, slider_value};
+use crate::ui::{Anchor, Slider, StackDirection, UiNode, WidgetColors};
+
+fn clip(mode: PlaybackMode) -> Clip {
+    Clip {
+        frames: (0..3)
+            .map(|i| Frame { image: format!("sheet:{i}"), duration: 0.1 })
+            .collect(),
+        mode,
+    }
+}
+
+#[test]
+fn animation_loop() {
+    let clip = clip(PlaybackMode::Loop);
+    let mut animator = Animator::new("player".to_string(), "idle".to_string());
+
+    animator.advance(&clip, 0.25);
+    assert_eq!(animator.frame(), 2);
+    animator.advance(&clip, 0.1);
+    assert_eq!(animator.frame(), 0);
+}
+
+#[test]
+fn animation_ping_pong() {
+    let clip = clip(PlaybackMode::PingPong);
+    let mut animator = Animator::new("player".to_string(), "idle".to_string());
+
+    let frames: Vec<usize> = (0..6)
+        .map(|_| {
+            animator.advance(&clip, 0.1);
+            animator.frame()
+        })
+        .collect();
+    assert_eq!(frames, vec![1, 2, 1, 0, 1, 2]);
+}
+
+#[test]
+fn animation_once() {
+    let clip = clip(PlaybackMode::Once);
+    let mut animator = Animator::new("player".to_string(), "attack".to_string());
+
+    assert!(!animator.advance(&clip, 0.25));
+    assert!(animator.advance(&clip, 0.1));
+    assert!(animator.is_finished());
+    assert_eq!(animator.frame(), 2);
+    assert!(!animator.advance(&clip, 1.0));
+
+    animator.play("attack");
+    assert_eq!(animator.frame(), 0);
+    assert!(!animator.is_finished());
+}
+
+#[test]
+fn tile_gid_flags() {
+    let tile = Tile::from_gid(0x8000_0003, 1).unwrap();
+    assert_eq!(tile.id, 2);
+    assert!(tile.flip_horizontal && !tile.flip_vertical && !tile.flip_diagonal);
+
+    assert_eq!(Tile::from_gid(0, 1), None);
+    assert_eq!(Tile::from_gid(0xE000_0000, 1), None);
+}
+
+#[test]
+fn tile_orientation() {
+    let tile = |h, v, d| Tile { id: 0, flip_horizontal: h, flip_vertical: v, flip_diagonal: d };
+
+    assert_eq!(tile(true, false, false).orientation(), (0.0, true, false));
+    // the diagonal flip alone is a transpose: vertical flip + 90° rotation
+    assert_eq!(tile(false, false, true).orientation(), (90.0, false, true));
+    // diagonal + horizontal is a plain 90° clockwise rotation
+    assert_eq!(tile(true, false, true).orientation(), (90.0, false, false));
+
+    assert_eq!(tile(false, false, true).transform_rect((0.0, 4.0, 16.0, 2.0), (16.0, 16.0)), (4.0, 0.0, 2.0, 16.0));
+    assert_eq!(tile(true, true, false).transform_rect((0.0, 0.0, 4.0, 2.0), (16.0, 16.0)), (12.0, 14.0, 4.0, 2.0));
+}
+
+#[test]
+fn tilemap_tmj() {
+    let map = br#"{
+        "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16, "infinite": false,
+        "layers": [
+            {"type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [1, 2, 0, 0, 2147483649, 4], "visible": true},
+            {"type": "objectgroup", "name": "spawns", "objects": [
+                {"id": 1, "name": "spawn", "type": "player", "x": 8, "y": 24, "width": 0, "height": 0,
+                 "properties": [{"name": "animator", "type": "string", "value": "(animation: \"player\", clip: \"idle\")"}]}
+            ]}
+        ],
+        "tilesets": [{"firstgid": 1, "name": "tiles", "tiles": [
+            {"id": 3, "objectgroup": {"objects": [{"x": 0, "y": 8, "width": 16, "height": 8}]}}
+        ]}]
+    }"#;
+    let (tilemap, objects) = parse_tmj(map, Path::new("")).unwrap();
+
+    assert_eq!(tilemap.tileset, "tiles");
+    assert_eq!(tilemap.layers[0].get(1, 0).unwrap().id, 1);
+    assert_eq!(tilemap.layers[0].get(2, 0), None);
+    assert!(tilemap.layers[0].get(1, 1).unwrap().flip_horizontal);
+
+    let boxes = tilemap.collision_boxes();
+    assert_eq!(boxes.len(), 1);
+    assert_eq!((boxes[0].position.x, boxes[0].position.y), (32.0, 24.0));
+
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].object.class, "player");
+    assert_eq!(objects[0].components[0].0, "animator");
+}
+
+#[test]
+fn tilemap_tmx() {
+    let map = br#"<?xml version="1.0" encoding="UTF-8"?>
+        <map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+            <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16">
+                <tile id="3"><objectgroup><object id="1" x="0" y="8" width="16" height="8"/></objectgroup></tile>
+            </tileset>
+            <layer id="1" name="ground" width="3" height="2">
+                <data encoding="csv">1,2,0,
+0,2147483649,4</data>
+            </layer>
+            <layer id="2" name="decor" width="1" height="1" visible="0">
+                <data><tile gid="3"/></data>
+            </layer>
+            <objectgroup id="3" name="spawns">
+                <object id="1" name="spawn" class="player" x="8" y="24">
+                    <properties>
+                        <property name="animator" value="(animation: &quot;player&quot;, clip: &quot;idle&quot;)"/>
+                    </properties>
+                </object>
+            </objectgroup>
+        </map>"#;
+    let (tilemap, objects) = parse_tmx(map, Path::new("")).unwrap();
+
+    assert_eq!(tilemap.tileset, "tiles");
+    assert_eq!(tilemap.layers[0].get(1, 0).unwrap().id, 1);
+    assert_eq!(tilemap.layers[0].get(2, 0), None);
+    assert!(tilemap.layers[0].get(1, 1).unwrap().flip_horizontal);
+    assert_eq!(tilemap.layers[1].get(0, 0).unwrap().id, 2);
+    assert!(!tilemap.layers[1].visible);
+
+    let bodies = tilemap.collision_bodies();
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(*bodies[0].get_position(), Vector2::new(32.0, 24.0));
+
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].object.class, "player");
+    assert_eq!(objects[0].components[0].0, "animator");
+}
+
+#[test]
+fn tilemap_culling() {
+    let (mut tilemap, _) = parse_tmj(br#"{
+        "tilewidth": 16, "tileheight": 16,
+        "layers": [{"type": "tilelayer", "name": "ground", "width": 100, "height": 100, "data": []}],
+        "tilesets": [{"firstgid": 1, "name": "tiles"}]
+    }"#, Path::new("")).unwrap();
+    tilemap.position = (-8.0, 0.0);
+
+    let cells = tilemap.visible_cells((0.0, 0.0, 64.0, 32.0), &tilemap.layers[0]);
+    assert_eq!(cells, ((0, 5), (0, 2)));
+    let cells = tilemap.visible_cells((-500.0, 1500.0, 64.0, 32.0), &tilemap.layers[0]);
+    assert_eq!(cells, ((0, 0), (93, 96)));
+}
+
+#[test]
+fn tilemap_tiles_are_queued_resolved() {
+    let (tilemap, _) = parse_tmj(br#"{
+        "tilewidth": 16, "tileheight": 16,
+        "layers": [{"type": "tilelayer", "name": "ground", "width": 3, "height": 1, "data": [1, 2, 1], "visible": true}],
+        "tilesets": [{"firstgid": 1, "name": "tiles"}]
+    }"#, Path::new("")).unwrap();
+    let mut world = World::new();
+    world.register::<Tilemap>();
+    world.register::<Camera>();
+    world.insert(Camera::new((0.0, 0.0), (64.0, 64.0)));
+    world.insert(RenderQueue::new());
+    let manager = SharedResourceManager::default();
+    world.insert(manager.clone());
+    world.create_entity().with(tilemap).build();
+
+    // nothing is drawn until the tileset is requested, for example with its asset group
+    TilemapRenderingSystem.run_now(&world);
+    assert!(world.read_resource::<RenderQueue>().queue.is_empty());
+
+    let sheet = manager.write().reserve::<SpriteSheet>("tiles");
+    TilemapRenderingSystem.run_now(&world);
+    let frames: Vec<Option<SpriteImage>> = world.read_resource::<RenderQueue>().queue
+        .iter()
+        .map(|drawable| match drawable {
+            Drawable::Image(sprite) => sprite.resolved().cloned(),
+            _ => None,
+        })
+        .collect();
+    let frame = |id: &str| Some(SpriteImage::Frame(sheet, id.into()));
+    assert_eq!(frames, [frame("0"), frame("1"), frame("0")]);
+    // the tilemap keeps its tileset loaded
+    assert_eq!(manager.read().ref_count("tiles"), 1);
+}
+
+#[test]
+fn ui_layout_anchors_and_stacks() {
+    let menu = UiNode::new("menu", (200.0, 100.0))
+        .with_anchor(Anchor::Center, (0.0, 0.0))
+        .with_padding(10.0)
+        .with_stack(StackDirection::Vertical, 5.0);
+    let second = UiNode::new("second", (0.0, 20.0)).with_parent("menu").with_order(1);
+    let first = UiNode::new("first", (50.0, 20.0)).with_parent("menu").with_anchor(Anchor::Right, (0.0, 0.0));
+    let corner = UiNode::new("corner", (30.0, 30.0)).with_anchor(Anchor::BottomRight, (-5.0, -5.0));
+    let orphan = UiNode::new("orphan", (10.0, 10.0)).with_parent("missing");
+
+    let layout = layout(&[&menu, &second, &first, &corner, &orphan], (800.0, 600.0));
+    assert_eq!(layout.rects[0], (300.0, 250.0, 200.0, 100.0));
+    // stacked by order, the anchor only aligns across the stack, 0 fills the content
+    assert_eq!(layout.rects[2], (440.0, 260.0, 50.0, 20.0));
+    assert_eq!(layout.rects[1], (310.0, 285.0, 180.0, 20.0));
+    assert_eq!(layout.rects[3], (765.0, 565.0, 30.0, 30.0));
+    assert_eq!(layout.rects[4], (0.0, 0.0, 10.0, 10.0));
+    assert_eq!(layout.draw_order, vec![0, 2, 1, 3, 4]);
+
+    assert_eq!(layout.hit((320.0, 290.0), |_| true), Some(1));
+    assert_eq!(layout.hit((305.0, 255.0), |_| true), Some(0));
+    assert_eq!(layout.hit((320.0, 290.0), |index| index != 1), Some(0));
+    assert_eq!(layout.hit((5.0, 300.0), |_| true), None);
+}
+
+#[test]
+fn ui_layout_hidden_nodes() {
+    let mut menu = UiNode::new("menu", (100.0, 100.0));
+    menu.visible = false;
+    let child = UiNode::new("child", (10.0, 10.0)).with_parent("menu");
+    // a cycle is never reached from the window
+    let a = UiNode::new("a", (10.0, 10.0)).with_parent("b");
+    let b = UiNode::new("b", (10.0, 10.0)).with_parent("a");
+
+    let layout = layout(&[&menu, &child, &a, &b], (800.0, 600.0));
+    assert!(layout.draw_order.is_empty());
+}
+
+#[test]
+fn ui_slider_value() {
+    let mut slider = Slider { min: 0.0, max: 10.0, value: 0.0, step: 0.0, colors: WidgetColors::default(), dragging: false };
+    let rect = (100.0, 0.0, 200.0, 20.0);
+    assert_eq!(slider_value(&slider, rect, 150.0), 2.5);
+    assert_eq!(slider_value(&slider, rect, 0.0), 0.0);
+    assert_eq!(slider_value(&slider, rect, 1000.0), 10.0);
+    slider.step = 2.0;
+    assert_eq!(slider_value(&slider, rect, 150.0), 2.0);
+}
+
+#[test]
+fn ui_nine_slice() {
+    let parts = nine_slice((0.0, 0.0, 100.0, 50.0), (30.0, 30.0), 10.0);
+    assert_eq!(parts.len(), 9);
+    // corners keep their size
+    assert_eq!(parts[0], ((0.0, 0.0, 10.0, 10.0), (0.0, 0.0, 10.0, 10.0)));
+    assert_eq!(parts[8], ((20.0, 20.0, 10.0, 10.0), (90.0, 40.0, 10.0, 10.0)));
+    // the center stretches
+    assert_eq!(parts[4], ((10.0, 10.0, 10.0, 10.0), (10.0, 10.0, 80.0, 30.0)));
+
+    // no border is a single stretched image
+    assert_eq!(nine_slice((0.0, 0.0, 100.0, 50.0), (30.0, 30.0), 0.0), vec![((0.0, 0.0, 30.0, 30.0), (0.0, 0.0, 100.0, 50.0))]);
+}
+
+#[test]
+fn asset_group_changes() {
+    let set = |groups: &[&str]| groups.iter().map(|group| group.to_string()).collect::<BTreeSet<String>>();
+    let (load, unload) = group_changes(&set(&["menu", "forest"]), &set(&["forest", "boss"]));
+    assert_eq!(load, vec!["boss".to_string()]);
+    assert_eq!(unload, vec!["menu".to_string()]);
+
+    let (load, unload) = group_changes(&set(&["forest"]), &set(&["forest"]));
+    assert!(load.is_empty() && unload.is_empty());
+}
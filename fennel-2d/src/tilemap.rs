@@ -0,0 +1,812 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use anyhow::{bail, Context};
+use log::error;
+use nalgebra::{Vector2, Vector3};
+use ron::Value;
+use roxmltree::Node;
+use serde::Deserialize;
+use specs::{Builder, Entity, Join, LazyUpdate, ReadExpect, ReadStorage, System, World, WorldExt, WriteExpect, WriteStorage};
+use fennel_graphics::graphics::{Drawable, Sprite, SpriteImage, Transform, DEFAULT_LAYER};
+use fennel_graphics::plugin::system::{Camera, RenderQueue};
+use fennel_graphics::resources::sprite_sheet::SpriteSheet;
+use fennel_physics::aabb::BoundingBox;
+use fennel_physics::shapes_2d::rigid_body::RigidBody;
+use fennel_registry::{ComponentFactory, ComponentRegistry, SpawnedBy};
+use fennel_resources::handle::{Handle, StrongHandle};
+use fennel_resources::manager::{ResourceManager, SharedResourceManager};
+use fennel_resources::vfs;
+use fennel_resources::ResourceError;
+
+const FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const FLIP_VERTICAL: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+/// Hexagonal 120° rotation flag, unused by orthogonal maps but still has to be masked out
+const ROTATE_HEXAGONAL: u32 = 0x1000_0000;
+
+/// A single placed tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    /// Tile index in the tileset, which is also the frame name in the sprite sheet
+    pub id: u32,
+    /// Mirrored horizontally
+    pub flip_horizontal: bool,
+    /// Mirrored vertically
+    pub flip_vertical: bool,
+    /// Mirrored along the top-left to bottom-right diagonal
+    pub flip_diagonal: bool,
+}
+
+/// A grid of tiles
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    /// Layer name
+    pub name: String,
+    /// Width in tiles
+    pub width: u32,
+    /// Height in tiles
+    pub height: u32,
+    /// Tiles row by row, `None` for empty cells
+    pub tiles: Vec<Option<Tile>>,
+    /// Is the layer drawn?
+    pub visible: bool,
+}
+
+/// Tilemap component: layered tile grids drawing from one sprite sheet
+///
+/// Tile `id` is drawn as the `"tileset:id"` frame, so the sprite sheet is expected to be sliced
+/// by a grid with the same cell size as the map. Tile layers are drawn on `render_layer` with
+/// `z`, `z + 1`, ... in their order.
+#[derive(Debug, Clone)]
+pub struct Tilemap {
+    /// Name of the sprite sheet asset
+    pub tileset: String,
+    /// Size of a tile (w, h) in pixels
+    pub tile_size: (u32, u32),
+    /// Position of the top-left corner in the world
+    pub position: (f32, f32),
+    /// Tile layers, from the bottom to the top
+    pub layers: Vec<TileLayer>,
+    /// Tile id - collision rectangles (x, y, w, h) relative to the tile
+    pub collisions: HashMap<u32, Vec<(f32, f32, f32, f32)>>,
+    /// Name of the render layer
+    pub render_layer: String,
+    /// Order of the first tile layer inside the render layer
+    pub z: i32,
+    /// Tileset resolved to a handle, see [`Tilemap::resolve_tileset`]
+    resolved: ResolvedTileset,
+}
+
+/// Sprite sheet of a [`Tilemap`] resolved to a handle, with the frames of the tiles drawn so far
+#[derive(Debug, Clone, Default)]
+struct ResolvedTileset {
+    /// Keeps the sprite sheet loaded while the tilemap exists
+    sheet: Option<StrongHandle<SpriteSheet>>,
+    /// Frame of each tile id
+    frames: HashMap<u32, SpriteImage>,
+}
+
+/// Component added to every entity created from a Tiled object
+#[derive(Debug, Clone)]
+pub struct MapObject {
+    /// Object name
+    pub name: String,
+    /// Object class (type)
+    pub class: String,
+    /// Position in the world
+    pub position: (f32, f32),
+    /// Object size (w, h)
+    pub size: (f32, f32),
+}
+
+/// A Tiled object, with its components parsed from custom properties
+#[derive(Debug, Clone)]
+pub struct MapObjectDescriptor {
+    /// The object itself
+    pub object: MapObject,
+    /// Component id - component config
+    pub components: Vec<(String, Value)>,
+}
+
+/// Component of the static bodies created from the collision shapes of a tilemap's tiles, next
+/// to their [`RigidBody`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileCollider {
+    /// Entity of the [`Tilemap`]
+    pub tilemap: Entity,
+}
+
+/// Scene config of a `"tilemap"` component
+#[derive(Deserialize, Debug)]
+struct TilemapConfig {
+    /// Path to the Tiled map, JSON (`.tmj`) or XML (`.tmx`)
+    source: String,
+    /// Sprite sheet name, the name of the map's first tileset if `None`
+    #[serde(default)]
+    tileset: Option<String>,
+    #[serde(default)]
+    position: (f32, f32),
+    #[serde(default = "default_render_layer")]
+    render_layer: String,
+    #[serde(default)]
+    z: i32,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledMap {
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    layers: Vec<TiledLayer>,
+    tilesets: Vec<TiledTilesetRef>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TiledLayer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        name: String,
+        width: u32,
+        height: u32,
+        data: Option<Vec<u32>>,
+        #[serde(default)]
+        encoding: Option<String>,
+        #[serde(default = "default_visible")]
+        visible: bool,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects {
+        objects: Vec<TiledObject>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledObject {
+    #[serde(default)]
+    name: String,
+    /// Called `class` since Tiled 1.9 and `type` before
+    #[serde(default, alias = "class", rename = "type")]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+/// A tileset, either embedded into the map or referencing an external file by `source`
+#[derive(Deserialize, Debug)]
+struct TiledTilesetRef {
+    firstgid: u32,
+    /// Path to an external tileset, relative to the map
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledTileset {
+    name: String,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledTile {
+    id: u32,
+    #[serde(default)]
+    objectgroup: Option<TiledObjectGroup>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledObjectGroup {
+    objects: Vec<TiledObject>,
+}
+
+/// Factory for the `"tilemap"` scene component.
+///
+/// Loads the Tiled map from the config's `source`, inserts the [`Tilemap`] into the entity,
+/// creates an entity for every object in the map's object layers and a static [`RigidBody`] with
+/// a [`TileCollider`] for every collision box of its tiles.
+pub struct TilemapFactory;
+
+/// ECS system that queues the tiles visible by the [`Camera`] for rendering
+///
+/// With several camera entities the tiles inside the bounds of all of their views are queued.
+/// The tiles are queued resolved to frames of the tileset, so drawing them doesn't look names up.
+pub struct TilemapRenderingSystem;
+
+impl specs::Component for Tilemap {
+    type Storage = specs::DenseVecStorage<Self>;
+}
+
+impl specs::Component for MapObject {
+    type Storage = specs::DenseVecStorage<Self>;
+}
+
+impl specs::Component for TileCollider {
+    type Storage = specs::DenseVecStorage<Self>;
+}
+
+fn union_rect(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+    let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+    (x, y, (a.0 + a.2).max(b.0 + b.2) - x, (a.1 + a.3).max(b.1 + b.3) - y)
+}
+
+fn default_render_layer() -> String {
+    DEFAULT_LAYER.to_string()
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl Tile {
+    /// Decode a Tiled global tile id
+    ///
+    /// # Returns
+    /// `None` for an empty cell or a tile of another tileset
+    pub fn from_gid(gid: u32, first_gid: u32) -> Option<Self> {
+        let id = gid & !(FLIP_HORIZONTAL | FLIP_VERTICAL | FLIP_DIAGONAL | ROTATE_HEXAGONAL);
+        if id == 0 || id < first_gid {
+            return None;
+        }
+        Some(Self {
+            id: id - first_gid,
+            flip_horizontal: gid & FLIP_HORIZONTAL != 0,
+            flip_vertical: gid & FLIP_VERTICAL != 0,
+            flip_diagonal: gid & FLIP_DIAGONAL != 0,
+        })
+    }
+
+    /// Rotation and flip flags drawing this tile the way Tiled does
+    ///
+    /// Tiled flips diagonally first, then horizontally, then vertically. SDL flips first and
+    /// rotates after, so the diagonal flip becomes a vertical flip followed by a 90° rotation.
+    ///
+    /// # Returns
+    /// `(rotation, flip_horizontal, flip_vertical)`
+    pub fn orientation(&self) -> (f64, bool, bool) {
+        if self.flip_diagonal {
+            (90.0, self.flip_vertical, !self.flip_horizontal)
+        } else {
+            (0.0, self.flip_horizontal, self.flip_vertical)
+        }
+    }
+
+    /// Apply the tile's flips to a rectangle (x, y, w, h) inside a tile of `tile_size`
+    pub fn transform_rect(&self, rect: (f32, f32, f32, f32), tile_size: (f32, f32)) -> (f32, f32, f32, f32) {
+        let (mut x, mut y, mut w, mut h) = rect;
+        if self.flip_diagonal {
+            (x, y, w, h) = (y, x, h, w);
+        }
+        if self.flip_horizontal {
+            x = tile_size.0 - x - w;
+        }
+        if self.flip_vertical {
+            y = tile_size.1 - y - h;
+        }
+        (x, y, w, h)
+    }
+}
+
+impl TileLayer {
+    /// Get the tile at a cell
+    pub fn get(&self, column: u32, row: u32) -> Option<Tile> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        self.tiles.get((row * self.width + column) as usize).copied().flatten()
+    }
+}
+
+impl Tilemap {
+    /// Resolve the tileset to a handle once it's in the resource manager, or again if it was
+    /// removed since
+    ///
+    /// # Returns
+    /// The sprite sheet, `None` until it's requested, for example with its asset group
+    pub fn resolve_tileset(&mut self, manager: &ResourceManager) -> Result<Option<Handle<SpriteSheet>>, ResourceError> {
+        if let Some(sheet) = self.resolved.sheet.as_ref().filter(|sheet| manager.load_state(sheet.handle()).is_ok()) {
+            return Ok(Some(sheet.handle()));
+        }
+        self.resolved = ResolvedTileset::default();
+        if !manager.is_cached(&self.tileset) {
+            return Ok(None);
+        }
+        let sheet = manager.strong(manager.handle::<SpriteSheet>(&self.tileset)?)?;
+        let handle = sheet.handle();
+        self.resolved.sheet = Some(sheet);
+        Ok(Some(handle))
+    }
+
+    /// Frame of a tile in the tileset resolved by [`Tilemap::resolve_tileset`]
+    fn tile_frame(&mut self, sheet: Handle<SpriteSheet>, id: u32) -> SpriteImage {
+        self.resolved.frames
+            .entry(id)
+            .or_insert_with(|| SpriteImage::Frame(sheet, Arc::from(id.to_string())))
+            .clone()
+    }
+
+    /// Load a tilemap and its objects from a Tiled file, XML if its extension is `.tmx` and
+    /// JSON otherwise
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<(Self, Vec<MapObjectDescriptor>)> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension == "tmx") {
+            Self::load_tmx(path)
+        } else {
+            Self::load_tmj(path)
+        }
+    }
+
+    /// Load a tilemap and its objects from a Tiled JSON (`.tmj`) file
+    ///
+    /// Only the first tileset of the map is used and tile layers must be stored as plain
+    /// arrays (CSV in Tiled), not base64. Object custom properties whose name is a component id
+    /// and whose value is a string are parsed as that component's RON config.
+    pub fn load_tmj<P: AsRef<Path>>(path: P) -> anyhow::Result<(Self, Vec<MapObjectDescriptor>)> {
+        let path = path.as_ref();
+        let data = vfs::read(path)
+            .with_context(|| format!("failed to read tilemap {}", path.display()))?;
+        parse_tmj(&data, path.parent().unwrap_or(Path::new("")))
+            .with_context(|| format!("failed to load tilemap {}", path.display()))
+    }
+
+    /// Load a tilemap and its objects from a Tiled XML (`.tmx`) file, with the same limits as
+    /// [`Tilemap::load_tmj`]. Tile layers may also be stored as `<tile>` elements
+    pub fn load_tmx<P: AsRef<Path>>(path: P) -> anyhow::Result<(Self, Vec<MapObjectDescriptor>)> {
+        let path = path.as_ref();
+        let data = vfs::read(path)
+            .with_context(|| format!("failed to read tilemap {}", path.display()))?;
+        parse_tmx(&data, path.parent().unwrap_or(Path::new("")))
+            .with_context(|| format!("failed to load tilemap {}", path.display()))
+    }
+
+    /// Size of the whole map (w, h) in pixels
+    pub fn size(&self) -> (f32, f32) {
+        let (width, height) = self.layers
+            .iter()
+            .fold((0, 0), |(w, h), layer| (w.max(layer.width), h.max(layer.height)));
+        ((width * self.tile_size.0) as f32, (height * self.tile_size.1) as f32)
+    }
+
+    /// Range of columns and rows intersecting the world-space rectangle `(x, y, w, h)`
+    ///
+    /// # Returns
+    /// `((first_column, end_column), (first_row, end_row))`, ends are exclusive
+    pub fn visible_cells(&self, rect: (f32, f32, f32, f32), layer: &TileLayer) -> ((u32, u32), (u32, u32)) {
+        let (tile_w, tile_h) = (self.tile_size.0 as f32, self.tile_size.1 as f32);
+        let left = (rect.0 - self.position.0) / tile_w;
+        let top = (rect.1 - self.position.1) / tile_h;
+        let right = (rect.0 + rect.2 - self.position.0) / tile_w;
+        let bottom = (rect.1 + rect.3 - self.position.1) / tile_h;
+        let clamp = |value: f32, max: u32| value.max(0.0).min(max as f32) as u32;
+        (
+            (clamp(left.floor(), layer.width), clamp(right.ceil(), layer.width)),
+            (clamp(top.floor(), layer.height), clamp(bottom.ceil(), layer.height)),
+        )
+    }
+
+    /// Bounding boxes of all placed tiles that have collision shapes, in world coordinates
+    pub fn collision_boxes(&self) -> Vec<BoundingBox> {
+        let tile_size = (self.tile_size.0 as f32, self.tile_size.1 as f32);
+        let mut boxes = Vec::new();
+        for layer in &self.layers {
+            for row in 0..layer.height {
+                for column in 0..layer.width {
+                    let Some(tile) = layer.get(column, row) else { continue };
+                    let Some(shapes) = self.collisions.get(&tile.id) else { continue };
+                    let tile_x = self.position.0 + column as f32 * tile_size.0;
+                    let tile_y = self.position.1 + row as f32 * tile_size.1;
+                    for shape in shapes {
+                        let (x, y, w, h) = tile.transform_rect(*shape, tile_size);
+                        boxes.push(BoundingBox::new(w, h, Vector3::new(tile_x + x, tile_y + y, 0.0)));
+                    }
+                }
+            }
+        }
+        boxes
+    }
+
+    /// Static bodies of the [`Tilemap::collision_boxes`], for fennel-physics
+    pub fn collision_bodies(&self) -> Vec<RigidBody> {
+        self.collision_boxes()
+            .into_iter()
+            .map(|bounds| RigidBody::new(
+                Vector2::new(bounds.position.x, bounds.position.y),
+                Vector2::new(bounds.width, bounds.height),
+                // no mass, so gravity doesn't move them
+                0.0,
+            ))
+            .collect()
+    }
+}
+
+/// Parse a Tiled JSON map, external tilesets are resolved relative to `base_dir`
+pub(crate) fn parse_tmj(data: &[u8], base_dir: &Path) -> anyhow::Result<(Tilemap, Vec<MapObjectDescriptor>)> {
+    let map: TiledMap = serde_json::from_slice(data).context("failed to parse tilemap")?;
+    build_tilemap(map, base_dir)
+}
+
+/// Parse a Tiled XML map, external tilesets are resolved relative to `base_dir`
+pub(crate) fn parse_tmx(data: &[u8], base_dir: &Path) -> anyhow::Result<(Tilemap, Vec<MapObjectDescriptor>)> {
+    let text = std::str::from_utf8(data).context("tilemap is not UTF-8")?;
+    let document = roxmltree::Document::parse(text).context("failed to parse tilemap")?;
+    let root = document.root_element();
+    let map = TiledMap {
+        tilewidth: attribute(root, "tilewidth")?,
+        tileheight: attribute(root, "tileheight")?,
+        infinite: optional_attribute::<u8>(root, "infinite")?.unwrap_or(0) != 0,
+        layers: root
+            .children()
+            .filter(|node| ["layer", "objectgroup", "imagelayer", "group"].contains(&node.tag_name().name()))
+            .map(xml_layer)
+            .collect::<anyhow::Result<_>>()?,
+        tilesets: root
+            .children()
+            .filter(|node| node.has_tag_name("tileset"))
+            .map(|node| Ok(TiledTilesetRef {
+                firstgid: attribute(node, "firstgid")?,
+                source: node.attribute("source").map(str::to_string),
+                name: node.attribute("name").map(str::to_string),
+                tiles: xml_tiles(node)?,
+            }))
+            .collect::<anyhow::Result<_>>()?,
+    };
+    build_tilemap(map, base_dir)
+}
+
+/// Attribute of an XML element, `None` if it's missing
+fn optional_attribute<T: FromStr>(node: Node, name: &str) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    node.attribute(name)
+        .map(|value| value.parse().with_context(|| format!("invalid attribute {name} of <{}>", node.tag_name().name())))
+        .transpose()
+}
+
+/// Required attribute of an XML element
+fn attribute<T: FromStr>(node: Node, name: &str) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    optional_attribute(node, name)?.with_context(|| format!("<{}> has no attribute {name}", node.tag_name().name()))
+}
+
+fn xml_layer(node: Node) -> anyhow::Result<TiledLayer> {
+    match node.tag_name().name() {
+        "layer" => {
+            let name: String = attribute(node, "name")?;
+            let data = node.children().find(|child| child.has_tag_name("data"));
+            let data = match data {
+                Some(data) => Some(xml_tile_data(data, &name)?),
+                None => None,
+            };
+            Ok(TiledLayer::Tiles {
+                width: attribute(node, "width")?,
+                height: attribute(node, "height")?,
+                data,
+                encoding: None,
+                visible: optional_attribute::<u8>(node, "visible")?.unwrap_or(1) != 0,
+                name,
+            })
+        }
+        "objectgroup" => Ok(TiledLayer::Objects { objects: xml_objects(node)? }),
+        _ => Ok(TiledLayer::Other),
+    }
+}
+
+/// Global tile ids of a `<data>` element, in CSV or as `<tile>` elements
+fn xml_tile_data(data: Node, layer: &str) -> anyhow::Result<Vec<u32>> {
+    match data.attribute("encoding") {
+        Some("csv") => data.text()
+            .unwrap_or_default()
+            .split(',')
+            .map(|gid| gid.trim().parse().with_context(|| format!("invalid tile in layer '{layer}'")))
+            .collect(),
+        None => data.children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| Ok(optional_attribute(tile, "gid")?.unwrap_or(0)))
+            .collect(),
+        Some(_) => bail!("tile layer '{layer}' must use CSV encoding"),
+    }
+}
+
+fn xml_objects(group: Node) -> anyhow::Result<Vec<TiledObject>> {
+    group.children()
+        .filter(|child| child.has_tag_name("object"))
+        .map(|object| Ok(TiledObject {
+            name: object.attribute("name").unwrap_or_default().to_string(),
+            class: object.attribute("class").or(object.attribute("type")).unwrap_or_default().to_string(),
+            x: attribute(object, "x")?,
+            y: attribute(object, "y")?,
+            width: optional_attribute(object, "width")?.unwrap_or(0.0),
+            height: optional_attribute(object, "height")?.unwrap_or(0.0),
+            properties: object.children()
+                .filter(|child| child.has_tag_name("properties"))
+                .flat_map(|properties| properties.children().filter(|child| child.has_tag_name("property")))
+                .map(|property| Ok(TiledProperty {
+                    name: attribute(property, "name")?,
+                    // multiline strings are stored as the text of the element
+                    value: serde_json::Value::String(
+                        property.attribute("value").or(property.text()).unwrap_or_default().to_string(),
+                    ),
+                }))
+                .collect::<anyhow::Result<_>>()?,
+        }))
+        .collect()
+}
+
+/// Tiles of a `<tileset>` element that have collision shapes
+fn xml_tiles(tileset: Node) -> anyhow::Result<Vec<TiledTile>> {
+    tileset.children()
+        .filter(|child| child.has_tag_name("tile"))
+        .map(|tile| Ok(TiledTile {
+            id: attribute(tile, "id")?,
+            objectgroup: match tile.children().find(|child| child.has_tag_name("objectgroup")) {
+                Some(group) => Some(TiledObjectGroup { objects: xml_objects(group)? }),
+                None => None,
+            },
+        }))
+        .collect()
+}
+
+/// Parse an external tileset, XML if its extension is `.tsx` and JSON otherwise
+fn parse_tileset(path: &Path) -> anyhow::Result<TiledTileset> {
+    let data = vfs::read(path)
+        .with_context(|| format!("failed to read tileset {}", path.display()))?;
+    if path.extension().is_some_and(|extension| extension == "tsx") {
+        let text = std::str::from_utf8(&data).context("tileset is not UTF-8")?;
+        let document = roxmltree::Document::parse(text).context("failed to parse tileset")?;
+        let root = document.root_element();
+        Ok(TiledTileset { name: attribute(root, "name")?, tiles: xml_tiles(root)? })
+    } else {
+        serde_json::from_slice(&data).context("failed to parse tileset")
+    }
+}
+
+/// Make a tilemap out of a parsed Tiled map
+fn build_tilemap(map: TiledMap, base_dir: &Path) -> anyhow::Result<(Tilemap, Vec<MapObjectDescriptor>)> {
+    if map.infinite {
+        bail!("infinite maps are not supported");
+    }
+
+    let tileset_ref = map.tilesets.into_iter().next().context("tilemap has no tilesets")?;
+    let first_gid = tileset_ref.firstgid;
+    let tileset = match tileset_ref.source {
+        Some(source) => parse_tileset(&base_dir.join(source))?,
+        None => TiledTileset {
+            name: tileset_ref.name.context("tileset has neither a source nor a name")?,
+            tiles: tileset_ref.tiles,
+        },
+    };
+
+    let collisions = tileset.tiles
+        .iter()
+        .filter_map(|tile| {
+            let group = tile.objectgroup.as_ref()?;
+            let shapes = group.objects
+                .iter()
+                .map(|object| (object.x, object.y, object.width, object.height))
+                .collect();
+            Some((tile.id, shapes))
+        })
+        .collect();
+
+    let mut layers = Vec::new();
+    let mut objects = Vec::new();
+    for layer in map.layers {
+        match layer {
+            TiledLayer::Tiles { name, width, height, data, encoding, visible } => {
+                if encoding.is_some_and(|encoding| encoding != "csv") {
+                    bail!("tile layer '{name}' must use CSV encoding");
+                }
+                let data = data.with_context(|| format!("tile layer '{name}' has no data"))?;
+                layers.push(TileLayer {
+                    name,
+                    width,
+                    height,
+                    tiles: data.into_iter().map(|gid| Tile::from_gid(gid, first_gid)).collect(),
+                    visible,
+                });
+            }
+            TiledLayer::Objects { objects: layer_objects } => {
+                for object in layer_objects {
+                    let components = object.properties
+                        .iter()
+                        .filter_map(|property| {
+                            let config = property.value.as_str()?;
+                            match ron::from_str::<Value>(config) {
+                                Ok(value) => Some((property.name.clone(), value)),
+                                Err(e) => {
+                                    error!("failed to parse property {} of object '{}': {e}", property.name, object.name);
+                                    None
+                                }
+                            }
+                        })
+                        .collect();
+                    objects.push(MapObjectDescriptor {
+                        object: MapObject {
+                            name: object.name,
+                            class: object.class,
+                            position: (object.x, object.y),
+                            size: (object.width, object.height),
+                        },
+                        components,
+                    });
+                }
+            }
+            TiledLayer::Other => {}
+        }
+    }
+
+    Ok((
+        Tilemap {
+            tileset: tileset.name,
+            tile_size: (map.tilewidth, map.tileheight),
+            position: (0.0, 0.0),
+            layers,
+            collisions,
+            render_layer: default_render_layer(),
+            z: 0,
+            resolved: ResolvedTileset::default(),
+        },
+        objects,
+    ))
+}
+
+/// Create an entity for every map object, with a [`MapObject`] component and the components
+/// from its properties, built through the world's [`ComponentRegistry`]
+///
+/// The objects are [`SpawnedBy`] the tilemap entity, so they're deleted along with its scene.
+pub fn spawn_objects(world: &mut World, tilemap: Entity, offset: (f32, f32), objects: Vec<MapObjectDescriptor>) {
+    // factories need `&mut World`, so take the registry out while they run
+    let Some(registry) = world.remove::<ComponentRegistry>() else {
+        error!("no component registry in the world, map objects were not spawned");
+        return;
+    };
+    for mut descriptor in objects {
+        descriptor.object.position.0 += offset.0;
+        descriptor.object.position.1 += offset.1;
+        let entity = world.create_entity().with(descriptor.object).with(SpawnedBy { owner: tilemap }).build();
+        for (id, config) in &descriptor.components {
+            match registry.get(id) {
+                Some(factory) => factory.insert(world, entity, config),
+                None => error!("factory {id} not found for a map object"),
+            }
+        }
+    }
+    world.insert(registry);
+}
+
+/// Create an entity with a static [`RigidBody`] and a [`TileCollider`] for every collision box
+/// of a tilemap, [`SpawnedBy`] the tilemap entity
+pub fn spawn_colliders(world: &mut World, tilemap: Entity, bodies: Vec<RigidBody>) {
+    for body in bodies {
+        world.create_entity().with(body).with(TileCollider { tilemap }).with(SpawnedBy { owner: tilemap }).build();
+    }
+}
+
+impl TilemapFactory {
+    fn load(value: &Value) -> Option<(Tilemap, Vec<MapObjectDescriptor>)> {
+        let config = match Value::into_rust::<TilemapConfig>(value.clone()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("failed to construct a Tilemap: {e}");
+                return None;
+            }
+        };
+        let (mut tilemap, objects) = match Tilemap::load(&config.source) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("{e:#}");
+                return None;
+            }
+        };
+        if let Some(tileset) = config.tileset {
+            tilemap.tileset = tileset;
+        }
+        tilemap.position = config.position;
+        tilemap.render_layer = config.render_layer;
+        tilemap.z = config.z;
+        Some((tilemap, objects))
+    }
+}
+
+impl ComponentFactory for TilemapFactory {
+    fn insert(&self, world: &mut World, entity: Entity, value: &Value) {
+        if let Some((tilemap, objects)) = Self::load(value) {
+            let (offset, bodies) = (tilemap.position, tilemap.collision_bodies());
+            let _ = world.write_storage::<Tilemap>().insert(entity, tilemap);
+            spawn_objects(world, entity, offset, objects);
+            spawn_colliders(world, entity, bodies);
+        }
+    }
+
+    fn insert_lazy(&self, lazy: &LazyUpdate, entity: Entity, value: &Value) {
+        if let Some((tilemap, objects)) = Self::load(value) {
+            let (offset, bodies) = (tilemap.position, tilemap.collision_bodies());
+            lazy.insert(entity, tilemap);
+            lazy.exec_mut(move |world| {
+                spawn_objects(world, entity, offset, objects);
+                spawn_colliders(world, entity, bodies);
+            });
+        }
+    }
+}
+
+impl<'a> System<'a> for TilemapRenderingSystem {
+    type SystemData = (
+        WriteStorage<'a, Tilemap>,
+        ReadExpect<'a, Camera>,
+        ReadStorage<'a, Camera>,
+        WriteExpect<'a, RenderQueue>,
+        ReadExpect<'a, SharedResourceManager>,
+    );
+
+    fn run(&mut self, (mut tilemaps, camera, camera_components, mut rq, resource_manager): Self::SystemData) {
+        let view = camera_components
+            .join()
+            .map(Camera::view_rect)
+            .reduce(union_rect)
+            .unwrap_or_else(|| camera.view_rect());
+        let manager = resource_manager.read();
+        for tilemap in (&mut tilemaps).join() {
+            let sheet = match tilemap.resolve_tileset(&manager) {
+                Ok(Some(sheet)) => sheet,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("failed to resolve tileset {}: {e}", tilemap.tileset);
+                    continue;
+                }
+            };
+            let (tile_w, tile_h) = (tilemap.tile_size.0 as f32, tilemap.tile_size.1 as f32);
+            for index in 0..tilemap.layers.len() {
+                let layer = &tilemap.layers[index];
+                if !layer.visible {
+                    continue;
+                }
+                let ((first_column, end_column), (first_row, end_row)) = tilemap.visible_cells(view, layer);
+                // the tiles of a layer only differ by their frame and placement
+                let template = Sprite::new(String::new(), Transform::new((0.0, 0.0), 1.0, 0.0), false)
+                    .with_origin((0.5, 0.5))
+                    .with_layer(&tilemap.render_layer, tilemap.z + index as i32);
+                for row in first_row..end_row {
+                    for column in first_column..end_column {
+                        let Some(tile) = tilemap.layers[index].get(column, row) else { continue };
+                        let (rotation, flip_horizontal, flip_vertical) = tile.orientation();
+                        // tiles are placed by their center so that rotated tiles stay in their cells
+                        let center = (
+                            tilemap.position.0 + (column as f32 + 0.5) * tile_w,
+                            tilemap.position.1 + (row as f32 + 0.5) * tile_h,
+                        );
+                        let mut sprite = template.clone()
+                            .with_resolved(tilemap.tile_frame(sheet, tile.id))
+                            .with_flip(flip_horizontal, flip_vertical);
+                        sprite.transform = Transform::new(center, 1.0, rotation);
+                        rq.queue.push(Drawable::Image(sprite));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Layout of UI nodes: anchors inside of the parent, stacks and padding
+
+use std::collections::HashMap;
+use log::warn;
+use crate::ui::{Slider, StackDirection, UiNode};
+
+/// A rectangle as (x, y, w, h)
+pub type Rect = (f32, f32, f32, f32);
+
+/// Result of [`layout`]
+#[derive(Debug, Default)]
+pub struct Layout {
+    /// Rectangle of every node, in the order the nodes were given
+    pub rects: Vec<Rect>,
+    /// Indices of the visible nodes, parents before their children and siblings by `order`
+    pub draw_order: Vec<usize>,
+}
+
+impl Layout {
+    /// Topmost visible node under `point` accepted by `filter`
+    pub fn hit(&self, point: (f32, f32), filter: impl Fn(usize) -> bool) -> Option<usize> {
+        self.draw_order
+            .iter()
+            .rev()
+            .copied()
+            .find(|&index| contains(self.rects[index], point) && filter(index))
+    }
+}
+
+/// Is `point` inside of `rect`?
+pub fn contains(rect: Rect, point: (f32, f32)) -> bool {
+    point.0 >= rect.0 && point.0 < rect.0 + rect.2 && point.1 >= rect.1 && point.1 < rect.1 + rect.3
+}
+
+/// Lay out UI nodes in a window `screen` pixels big
+///
+/// Nodes without a parent are placed in the window. Nodes whose parent doesn't exist are
+/// treated the same way, with a warning. Nodes caught in a parent cycle are never reached and
+/// stay hidden.
+pub fn layout(nodes: &[&UiNode], screen: (f32, f32)) -> Layout {
+    let by_name: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.name.as_str(), index))
+        .collect();
+
+    let mut roots = Vec::new();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (index, node) in nodes.iter().enumerate() {
+        match node.parent.as_deref().map(|parent| (parent, by_name.get(parent))) {
+            None => roots.push(index),
+            Some((_, Some(&parent))) => children[parent].push(index),
+            Some((parent, None)) => {
+                warn!("parent {parent} of UI node {} doesn't exist", node.name);
+                roots.push(index);
+            }
+        }
+    }
+    // `sort_by_key` is stable, so siblings with the same order keep the order they were given in
+    roots.sort_by_key(|&index| nodes[index].order);
+    for siblings in &mut children {
+        siblings.sort_by_key(|&index| nodes[index].order);
+    }
+
+    let mut layout = Layout { rects: vec![(0.0, 0.0, 0.0, 0.0); nodes.len()], draw_order: Vec::new() };
+    let screen_node = UiNode::new("", screen);
+    place_children(nodes, &children, &roots, &screen_node, (0.0, 0.0, screen.0, screen.1), &mut layout);
+    layout
+}
+
+fn place_children(
+    nodes: &[&UiNode],
+    children: &[Vec<usize>],
+    siblings: &[usize],
+    parent: &UiNode,
+    parent_rect: Rect,
+    layout: &mut Layout,
+) {
+    let content = (
+        parent_rect.0 + parent.padding,
+        parent_rect.1 + parent.padding,
+        (parent_rect.2 - parent.padding * 2.0).max(0.0),
+        (parent_rect.3 - parent.padding * 2.0).max(0.0),
+    );
+
+    let mut cursor = 0.0;
+    for &index in siblings {
+        let node = nodes[index];
+        if !node.visible {
+            continue;
+        }
+        // a size of 0 fills the parent content on that axis
+        let w = if node.size.0 > 0.0 { node.size.0 } else { content.2 };
+        let h = if node.size.1 > 0.0 { node.size.1 } else { content.3 };
+        let (ax, ay) = node.anchor.factors();
+        let (x, y) = match parent.stack.map(|stack| (stack.direction, stack.spacing)) {
+            None => (
+                content.0 + ax * (content.2 - w),
+                content.1 + ay * (content.3 - h),
+            ),
+            Some((StackDirection::Vertical, spacing)) => {
+                let y = content.1 + cursor;
+                cursor += h + spacing;
+                (content.0 + ax * (content.2 - w), y)
+            }
+            Some((StackDirection::Horizontal, spacing)) => {
+                let x = content.0 + cursor;
+                cursor += w + spacing;
+                (x, content.1 + ay * (content.3 - h))
+            }
+        };
+        let rect = (x + node.offset.0, y + node.offset.1, w, h);
+        layout.rects[index] = rect;
+        layout.draw_order.push(index);
+        place_children(nodes, children, &children[index], node, rect, layout);
+    }
+}
+
+/// Value of a slider when its handle is dragged to `x`
+pub fn slider_value(slider: &Slider, rect: Rect, x: f32) -> f32 {
+    let t = if rect.2 > 0.0 { ((x - rect.0) / rect.2).clamp(0.0, 1.0) } else { 0.0 };
+    let value = slider.min + t * (slider.max - slider.min);
+    if slider.step > 0.0 {
+        let steps = ((value - slider.min) / slider.step).round();
+        (slider.min + steps * slider.step).clamp(slider.min.min(slider.max), slider.max.max(slider.min))
+    } else {
+        value
+    }
+}
+
+/// Split a panel into the 9 parts of its image: corners keep their size, edges stretch along
+/// themselves and the center stretches both ways
+///
+/// # Returns
+/// Pairs of the source rectangle in the image and the destination rectangle on screen, parts
+/// with no area are skipped
+pub fn nine_slice(rect: Rect, image_size: (f32, f32), border: f32) -> Vec<(Rect, Rect)> {
+    // the border can't be wider than half of the image or of the panel
+    let src_border = border.min(image_size.0 / 2.0).min(image_size.1 / 2.0);
+    let dst_border = src_border.min(rect.2 / 2.0).min(rect.3 / 2.0);
+    let src_columns = [(0.0, src_border), (src_border, image_size.0 - src_border * 2.0), (image_size.0 - src_border, src_border)];
+    let src_rows = [(0.0, src_border), (src_border, image_size.1 - src_border * 2.0), (image_size.1 - src_border, src_border)];
+    let dst_columns = [(rect.0, dst_border), (rect.0 + dst_border, rect.2 - dst_border * 2.0), (rect.0 + rect.2 - dst_border, dst_border)];
+    let dst_rows = [(rect.1, dst_border), (rect.1 + dst_border, rect.3 - dst_border * 2.0), (rect.1 + rect.3 - dst_border, dst_border)];
+
+    let mut parts = Vec::with_capacity(9);
+    for row in 0..3 {
+        for column in 0..3 {
+            let src = (src_columns[column].0, src_rows[row].0, src_columns[column].1, src_rows[row].1);
+            let dst = (dst_columns[column].0, dst_rows[row].0, dst_columns[column].1, dst_rows[row].1);
+            if src.2 > 0.0 && src.3 > 0.0 && dst.2 > 0.0 && dst.3 > 0.0 {
+                parts.push((src, dst));
+            }
+        }
+    }
+    parts
+}
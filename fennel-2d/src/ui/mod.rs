@@ -0,0 +1,399 @@
+//! Retained-mode game UI
+//!
+//! Every widget is an entity with a [`UiNode`] component, which places it on screen, and a
+//! widget component: [`Label`], [`Button`], [`Panel`], [`Slider`], [`Checkbox`] or
+//! [`TextInput`]. Nodes reference their parent by name, so a whole menu can be written in a
+//! scene:
+//!
+//! ```ron
+//! (
+//!     id: "menu",
+//!     components: [
+//!         (id: "ui_node", config: (name: "menu", anchor: Center, size: (240, 200), padding: 12,
+//!             stack: Some((direction: Vertical, spacing: 8)))),
+//!         (id: "panel", config: (color: (20, 20, 30, 220))),
+//!     ],
+//! ),
+//! (
+//!     id: "play",
+//!     components: [
+//!         (id: "ui_node", config: (name: "play", parent: Some("menu"), size: (0, 40))),
+//!         (id: "button", config: (text: "Play", font: "Terminus")),
+//!     ],
+//! ),
+//! ```
+//!
+//! Widgets are hit-tested with the mouse events from the graphics plugin, report what happened
+//! through the `Vec<UiEvent>` resource and are drawn into the fixed [`RenderQueue`] on the
+//! [`UI_LAYER`].
+//!
+//! [`RenderQueue`]: fennel_graphics::plugin::system::RenderQueue
+
+use log::error;
+use ron::Value;
+use serde::Deserialize;
+use specs::{Entity, LazyUpdate, World, WorldExt};
+use fennel_graphics::text::TextAlign;
+use fennel_registry::{impl_component_factory, ComponentFactory};
+
+/// Layout of UI nodes
+pub mod layout;
+/// The UI system
+pub mod system;
+
+impl_component_factory!(UiNodeFactory, UiNode);
+impl_component_factory!(LabelFactory, Label);
+impl_component_factory!(ButtonFactory, Button);
+impl_component_factory!(PanelFactory, Panel);
+impl_component_factory!(SliderFactory, Slider);
+impl_component_factory!(CheckboxFactory, Checkbox);
+impl_component_factory!(TextInputFactory, TextInput);
+
+/// Name of the render layer UI is drawn on
+pub const UI_LAYER: &str = "ui";
+
+/// Point of the parent a node is aligned to
+///
+/// Inside of a stack only the axis across the stack is used.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    /// Top-left corner
+    #[default]
+    TopLeft,
+    /// Middle of the top edge
+    Top,
+    /// Top-right corner
+    TopRight,
+    /// Middle of the left edge
+    Left,
+    /// Center
+    Center,
+    /// Middle of the right edge
+    Right,
+    /// Bottom-left corner
+    BottomLeft,
+    /// Middle of the bottom edge
+    Bottom,
+    /// Bottom-right corner
+    BottomRight,
+}
+
+impl Anchor {
+    /// Position of the anchor relative to the parent size, `(0.5, 0.5)` being the center
+    pub fn factors(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// Direction children of a stack are placed in
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackDirection {
+    /// From the top to the bottom
+    Vertical,
+    /// From the left to the right
+    Horizontal,
+}
+
+/// Places children one after another instead of anchoring them
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Stack {
+    /// Direction the children are placed in
+    pub direction: StackDirection,
+    /// Space between two children
+    #[serde(default)]
+    pub spacing: f32,
+}
+
+/// Position and size of a widget on screen
+#[derive(Deserialize, Debug, Clone)]
+pub struct UiNode {
+    /// Unique name, used by children to reference their parent and in [`UiEvent`]s
+    pub name: String,
+    /// Name of the parent node, the window if `None`
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Point of the parent the node is aligned to
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Offset from the anchored position
+    #[serde(default)]
+    pub offset: (f32, f32),
+    /// Width and height, 0 fills the content of the parent on that axis
+    pub size: (f32, f32),
+    /// Space between the border of the node and its children
+    #[serde(default)]
+    pub padding: f32,
+    /// Stack the children instead of anchoring them
+    #[serde(default)]
+    pub stack: Option<Stack>,
+    /// Order among the siblings, lower comes first in stacks and is drawn first
+    #[serde(default)]
+    pub order: i32,
+    /// Hidden nodes and their children are neither drawn nor hit-tested
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    /// Rectangle (x, y, w, h) on screen, computed by the [`system::UiSystem`]
+    #[serde(skip)]
+    pub rect: (f32, f32, f32, f32),
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl UiNode {
+    /// Create a node at the top-left corner of the window
+    pub fn new(name: &str, size: (f32, f32)) -> Self {
+        Self {
+            name: name.to_string(),
+            parent: None,
+            anchor: Anchor::TopLeft,
+            offset: (0.0, 0.0),
+            size,
+            padding: 0.0,
+            stack: None,
+            order: 0,
+            visible: true,
+            rect: (0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Place the node inside of another one
+    pub fn with_parent(mut self, parent: &str) -> Self {
+        self.parent = Some(parent.to_string());
+        self
+    }
+
+    /// Align the node to a point of its parent, moved by `offset`
+    pub fn with_anchor(mut self, anchor: Anchor, offset: (f32, f32)) -> Self {
+        self.anchor = anchor;
+        self.offset = offset;
+        self
+    }
+
+    /// Keep children `padding` away from the border
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Stack the children
+    pub fn with_stack(mut self, direction: StackDirection, spacing: f32) -> Self {
+        self.stack = Some(Stack { direction, spacing });
+        self
+    }
+
+    /// Set the order among the siblings
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+/// Text
+#[derive(Deserialize, Debug, Clone)]
+pub struct Label {
+    /// The text
+    pub text: String,
+    /// Font name registered in the resource manager
+    pub font: String,
+    /// Font size
+    #[serde(default = "default_font_size")]
+    pub size: f32,
+    /// RGB color
+    #[serde(default = "default_text_color")]
+    pub color: (u8, u8, u8),
+    /// Horizontal alignment, the text is wrapped at the node width
+    #[serde(default)]
+    pub align: TextAlign,
+}
+
+/// Colors of a widget background in its different states
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct WidgetColors {
+    /// Color when the widget isn't interacted with
+    pub normal: (u8, u8, u8, u8),
+    /// Color when the mouse is over the widget
+    pub hovered: (u8, u8, u8, u8),
+    /// Color while the widget is held down
+    pub pressed: (u8, u8, u8, u8),
+}
+
+impl Default for WidgetColors {
+    fn default() -> Self {
+        Self {
+            normal: (60, 60, 75, 255),
+            hovered: (80, 80, 100, 255),
+            pressed: (40, 40, 50, 255),
+        }
+    }
+}
+
+/// Clickable button with a centered text, sends [`UiEvent::Clicked`] when released over it
+#[derive(Deserialize, Debug, Clone)]
+pub struct Button {
+    /// The text
+    pub text: String,
+    /// Font name registered in the resource manager
+    pub font: String,
+    /// Font size
+    #[serde(default = "default_font_size")]
+    pub size: f32,
+    /// RGB text color
+    #[serde(default = "default_text_color")]
+    pub text_color: (u8, u8, u8),
+    /// Background colors
+    #[serde(default)]
+    pub colors: WidgetColors,
+    /// Is the mouse over the button?
+    #[serde(skip)]
+    pub hovered: bool,
+    /// Is the button held down?
+    #[serde(skip)]
+    pub pressed: bool,
+}
+
+/// Background filled with a color or with an image stretched as a 9-slice
+#[derive(Deserialize, Debug, Clone)]
+pub struct Panel {
+    /// Image to draw, the panel is filled with `color` if `None`
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Size of the image in pixels, required to slice it
+    #[serde(default)]
+    pub image_size: (f32, f32),
+    /// Width of the image border that is kept unstretched on the corners and edges
+    #[serde(default)]
+    pub border: f32,
+    /// Fill color, or tint and alpha of the image
+    #[serde(default = "default_panel_color")]
+    pub color: (u8, u8, u8, u8),
+}
+
+/// Horizontal slider, sends [`UiEvent::ValueChanged`] while being dragged
+#[derive(Deserialize, Debug, Clone)]
+pub struct Slider {
+    /// Value at the left end
+    pub min: f32,
+    /// Value at the right end
+    pub max: f32,
+    /// Current value
+    pub value: f32,
+    /// Values are rounded to multiples of `step` from `min`, unless it's 0
+    #[serde(default)]
+    pub step: f32,
+    /// Track and handle colors
+    #[serde(default)]
+    pub colors: WidgetColors,
+    /// Is the handle being dragged?
+    #[serde(skip)]
+    pub dragging: bool,
+}
+
+/// Box toggled by clicking it, sends [`UiEvent::Toggled`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct Checkbox {
+    /// Is the box checked?
+    #[serde(default)]
+    pub checked: bool,
+    /// Box and check mark colors
+    #[serde(default)]
+    pub colors: WidgetColors,
+}
+
+/// Single-line text field, focused by clicking it, sends [`UiEvent::TextChanged`] while typing
+/// and [`UiEvent::Submitted`] on enter
+#[derive(Deserialize, Debug, Clone)]
+pub struct TextInput {
+    /// Current text
+    #[serde(default)]
+    pub text: String,
+    /// Text shown while `text` is empty
+    #[serde(default)]
+    pub placeholder: String,
+    /// Font name registered in the resource manager
+    pub font: String,
+    /// Font size
+    #[serde(default = "default_font_size")]
+    pub size: f32,
+    /// Maximum number of characters, unlimited if `None`
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Background colors, `pressed` is used while focused
+    #[serde(default)]
+    pub colors: WidgetColors,
+    /// Does the field receive typed text?
+    #[serde(skip)]
+    pub focused: bool,
+}
+
+fn default_font_size() -> f32 {
+    16.0
+}
+
+fn default_text_color() -> (u8, u8, u8) {
+    (240, 240, 240)
+}
+
+fn default_panel_color() -> (u8, u8, u8, u8) {
+    (30, 30, 40, 230)
+}
+
+/// Something the user did with a widget, referenced by the name of its [`UiNode`]
+///
+/// Events are collected in the `Vec<UiEvent>` resource, which the [`system::UiSystem`] empties
+/// every tick, so systems reading them should depend on `"ui_system"`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiEvent {
+    /// A button was clicked
+    Clicked(String),
+    /// A checkbox was toggled
+    Toggled(String, bool),
+    /// A slider was moved
+    ValueChanged(String, f32),
+    /// The text of a text input changed
+    TextChanged(String, String),
+    /// Enter was pressed in a text input
+    Submitted(String, String),
+}
+
+macro_rules! impl_ui_component {
+    ($($component:ident),*) => {
+        $(
+            impl specs::Component for $component {
+                type Storage = specs::DenseVecStorage<Self>;
+            }
+        )*
+    };
+}
+
+impl_ui_component!(UiNode, Label, Button, Panel, Slider, Checkbox, TextInput);
+
+/// Register the UI components and their scene factories
+pub(crate) fn register(world: &mut World) {
+    world.register::<UiNode>();
+    world.register::<Label>();
+    world.register::<Button>();
+    world.register::<Panel>();
+    world.register::<Slider>();
+    world.register::<Checkbox>();
+    world.register::<TextInput>();
+    let mut registry = world.write_resource::<fennel_registry::ComponentRegistry>();
+    registry.register("ui_node", Box::new(UiNodeFactory));
+    registry.register("label", Box::new(LabelFactory));
+    registry.register("button", Box::new(ButtonFactory));
+    registry.register("panel", Box::new(PanelFactory));
+    registry.register("slider", Box::new(SliderFactory));
+    registry.register("checkbox", Box::new(CheckboxFactory));
+    registry.register("text_input", Box::new(TextInputFactory));
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use sdl3::keyboard::Keycode;
+use sdl3::mouse::MouseButton;
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+use fennel_graphics::graphics::{Drawable, Sprite, Transform};
+use fennel_graphics::plugin::event_handler::PluginEvent;
+use fennel_graphics::plugin::system::{RenderQueue, TextInputState, WindowSize};
+use fennel_graphics::text::{TextAlign, TextLayout};
+use crate::ui::layout::{layout, nine_slice, slider_value, Rect};
+use crate::ui::{Button, Checkbox, Label, Panel, Slider, TextInput, UiEvent, UiNode, WidgetColors, UI_LAYER};
+
+/// Color of the placeholder of an empty [`TextInput`]
+const PLACEHOLDER_COLOR: (u8, u8, u8) = (140, 140, 140);
+/// Color of widget outlines and of the checkbox mark
+const OUTLINE_COLOR: (u8, u8, u8, u8) = (200, 200, 210, 255);
+
+/// ECS system laying out the UI, handling the mouse and keyboard and drawing the widgets
+///
+/// Widgets are laid out in the [`WindowSize`]. The [`TextInputState`] is on while a
+/// [`TextInput`] is focused.
+#[derive(Default)]
+pub struct UiSystem {
+    /// Last known mouse position
+    cursor: (f32, f32),
+}
+
+impl<'a> System<'a> for UiSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, UiNode>,
+        ReadStorage<'a, Label>,
+        WriteStorage<'a, Button>,
+        ReadStorage<'a, Panel>,
+        WriteStorage<'a, Slider>,
+        WriteStorage<'a, Checkbox>,
+        WriteStorage<'a, TextInput>,
+        ReadExpect<'a, Vec<PluginEvent>>,
+        ReadExpect<'a, WindowSize>,
+        ReadExpect<'a, TextInputState>,
+        WriteExpect<'a, RenderQueue>,
+        WriteExpect<'a, Vec<UiEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut nodes,
+            labels,
+            mut buttons,
+            panels,
+            mut sliders,
+            mut checkboxes,
+            mut text_inputs,
+            plugin_events,
+            window_size,
+            text_input_state,
+            mut rq,
+            mut ui_events,
+        ): Self::SystemData,
+    ) {
+        ui_events.clear();
+
+        let (node_entities, names, computed) = {
+            let (node_entities, node_refs): (Vec<Entity>, Vec<&UiNode>) = (&entities, &nodes).join().unzip();
+            let names: Vec<String> = node_refs.iter().map(|node| node.name.clone()).collect();
+            (node_entities, names, layout(&node_refs, window_size.get()))
+        };
+        for (&entity, &rect) in node_entities.iter().zip(&computed.rects) {
+            if let Some(node) = nodes.get_mut(entity) {
+                node.rect = rect;
+            }
+        }
+        let index_of: HashMap<Entity, usize> = node_entities.iter().enumerate().map(|(index, &entity)| (entity, index)).collect();
+        let name = |entity: Entity| index_of.get(&entity).map(|&index| names[index].clone()).unwrap_or_default();
+        let rect = |entity: Entity| index_of.get(&entity).map(|&index| computed.rects[index]).unwrap_or_default();
+        let interactive: Vec<bool> = node_entities
+            .iter()
+            .map(|&entity| buttons.contains(entity) || sliders.contains(entity) || checkboxes.contains(entity) || text_inputs.contains(entity))
+            .collect();
+        let interactive = |index: usize| interactive[index];
+
+        for event in plugin_events.iter() {
+            match event {
+                PluginEvent::MouseMotionEvent(motion) => {
+                    self.cursor = (motion.x, motion.y);
+                    for (entity, slider) in (&entities, &mut sliders).join().filter(|(_, slider)| slider.dragging) {
+                        let value = slider_value(slider, rect(entity), motion.x);
+                        if value != slider.value {
+                            slider.value = value;
+                            ui_events.push(UiEvent::ValueChanged(name(entity), value));
+                        }
+                    }
+                }
+                PluginEvent::MouseClickEvent(click) if click.mouse_btn == MouseButton::Left => {
+                    self.cursor = (click.x, click.y);
+                    let hit = computed.hit(self.cursor, interactive).map(|index| node_entities[index]);
+                    for (entity, text_input) in (&entities, &mut text_inputs).join() {
+                        text_input.focused = Some(entity) == hit;
+                    }
+                    let Some(hit) = hit else { continue };
+                    if let Some(button) = buttons.get_mut(hit) {
+                        button.pressed = true;
+                    }
+                    if let Some(slider) = sliders.get_mut(hit) {
+                        slider.dragging = true;
+                        slider.value = slider_value(slider, rect(hit), click.x);
+                        ui_events.push(UiEvent::ValueChanged(name(hit), slider.value));
+                    }
+                    if let Some(checkbox) = checkboxes.get_mut(hit) {
+                        checkbox.checked = !checkbox.checked;
+                        ui_events.push(UiEvent::Toggled(name(hit), checkbox.checked));
+                    }
+                }
+                PluginEvent::MouseButtonUpEvent(click) if click.mouse_btn == MouseButton::Left => {
+                    self.cursor = (click.x, click.y);
+                    let hit = computed.hit(self.cursor, interactive).map(|index| node_entities[index]);
+                    for (entity, button) in (&entities, &mut buttons).join() {
+                        if button.pressed && Some(entity) == hit {
+                            ui_events.push(UiEvent::Clicked(name(entity)));
+                        }
+                        button.pressed = false;
+                    }
+                    for slider in (&mut sliders).join() {
+                        slider.dragging = false;
+                    }
+                }
+                PluginEvent::TextInputEvent(input) => {
+                    for (entity, text_input) in (&entities, &mut text_inputs).join().filter(|(_, input)| input.focused) {
+                        let room = text_input.max_length
+                            .map(|max| max.saturating_sub(text_input.text.chars().count()))
+                            .unwrap_or(usize::MAX);
+                        text_input.text.extend(input.text.chars().take(room));
+                        ui_events.push(UiEvent::TextChanged(name(entity), text_input.text.clone()));
+                    }
+                }
+                PluginEvent::KeyboardEvent(key) => {
+                    for (entity, text_input) in (&entities, &mut text_inputs).join().filter(|(_, input)| input.focused) {
+                        match key.keycode {
+                            Some(Keycode::Backspace) => {
+                                let removed = text_input.text.pop();
+                                if removed.is_some() {
+                                    ui_events.push(UiEvent::TextChanged(name(entity), text_input.text.clone()));
+                                }
+                            }
+                            Some(Keycode::Return | Keycode::KpEnter) => {
+                                ui_events.push(UiEvent::Submitted(name(entity), text_input.text.clone()));
+                            }
+                            Some(Keycode::Escape) => text_input.focused = false,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // typed text only reaches the window while a field is focused
+        text_input_state.set_active((&text_inputs).join().any(|input| input.focused));
+
+        let hovered = computed.hit(self.cursor, interactive).map(|index| node_entities[index]);
+        for (entity, button) in (&entities, &mut buttons).join() {
+            button.hovered = Some(entity) == hovered;
+        }
+
+        for (z, &index) in computed.draw_order.iter().enumerate() {
+            let entity = node_entities[index];
+            let rect = computed.rects[index];
+            let z = z as i32;
+            if let Some(panel) = panels.get(entity) {
+                draw_panel(&mut rq.fixed, panel, rect, z);
+            }
+            if let Some(button) = buttons.get(entity) {
+                let color = widget_color(&button.colors, button.hovered, button.pressed);
+                rq.fixed.push(filled_rect(rect, color, z));
+                rq.fixed.push(text(&button.text, &button.font, button.size, button.text_color, TextAlign::Center, rect, z));
+            }
+            if let Some(label) = labels.get(entity) {
+                rq.fixed.push(text(&label.text, &label.font, label.size, label.color, label.align, rect, z));
+            }
+            if let Some(slider) = sliders.get(entity) {
+                draw_slider(&mut rq.fixed, slider, rect, Some(entity) == hovered, z);
+            }
+            if let Some(checkbox) = checkboxes.get(entity) {
+                let color = widget_color(&checkbox.colors, Some(entity) == hovered, false);
+                rq.fixed.push(filled_rect(rect, color, z));
+                rq.fixed.push(outlined_rect(rect, OUTLINE_COLOR, z));
+                if checkbox.checked {
+                    let inset = rect.2.min(rect.3) / 4.0;
+                    let mark = (rect.0 + inset, rect.1 + inset, rect.2 - inset * 2.0, rect.3 - inset * 2.0);
+                    rq.fixed.push(filled_rect(mark, OUTLINE_COLOR, z));
+                }
+            }
+            if let Some(text_input) = text_inputs.get(entity) {
+                let color = widget_color(&text_input.colors, Some(entity) == hovered, text_input.focused);
+                rq.fixed.push(filled_rect(rect, color, z));
+                rq.fixed.push(outlined_rect(rect, OUTLINE_COLOR, z));
+                let padded = (rect.0 + 4.0, rect.1, rect.2 - 8.0, rect.3);
+                if text_input.text.is_empty() && !text_input.focused {
+                    rq.fixed.push(text(&text_input.placeholder, &text_input.font, text_input.size, PLACEHOLDER_COLOR, TextAlign::Left, padded, z));
+                } else {
+                    let caret = if text_input.focused { "_" } else { "" };
+                    let content = format!("{}{caret}", text_input.text);
+                    rq.fixed.push(text(&content, &text_input.font, text_input.size, (240, 240, 240), TextAlign::Left, padded, z));
+                }
+            }
+        }
+    }
+}
+
+fn widget_color(colors: &WidgetColors, hovered: bool, pressed: bool) -> (u8, u8, u8, u8) {
+    if pressed {
+        colors.pressed
+    } else if hovered {
+        colors.hovered
+    } else {
+        colors.normal
+    }
+}
+
+fn filled_rect(rect: Rect, color: (u8, u8, u8, u8), z: i32) -> Drawable {
+    Drawable::Rect { w: rect.2, h: rect.3, x: rect.0, y: rect.1, color, filled: true, thickness: 1.0, layer: UI_LAYER.to_string(), z }
+}
+
+fn outlined_rect(rect: Rect, color: (u8, u8, u8, u8), z: i32) -> Drawable {
+    Drawable::Rect { w: rect.2, h: rect.3, x: rect.0, y: rect.1, color, filled: false, thickness: 1.0, layer: UI_LAYER.to_string(), z }
+}
+
+/// Text wrapped at the width of `rect` and centered vertically, assuming a line is about as
+/// tall as the font size
+fn text(content: &str, font: &str, size: f32, color: (u8, u8, u8), align: TextAlign, rect: Rect, z: i32) -> Drawable {
+    Drawable::Text {
+        font: font.to_string(),
+        position: (rect.0, rect.1 + ((rect.3 - size) / 2.0).max(0.0)),
+        text: content.to_string(),
+        color,
+        size,
+        layout: TextLayout::default().with_max_width(rect.2).with_align(align),
+        layer: UI_LAYER.to_string(),
+        z,
+    }
+}
+
+fn draw_panel(queue: &mut Vec<Drawable>, panel: &Panel, rect: Rect, z: i32) {
+    let (r, g, b, a) = panel.color;
+    let Some(image) = &panel.image else {
+        queue.push(filled_rect(rect, panel.color, z));
+        return;
+    };
+    for (source, destination) in nine_slice(rect, panel.image_size, panel.border) {
+        let sprite = Sprite::new(image.clone(), Transform::new((destination.0, destination.1), 1.0, 0.0), true)
+            .with_source(source)
+            .with_size((destination.2, destination.3))
+            .with_tint((r, g, b), a)
+            .with_layer(UI_LAYER, z);
+        queue.push(Drawable::Image(sprite));
+    }
+}
+
+fn draw_slider(queue: &mut Vec<Drawable>, slider: &Slider, rect: Rect, hovered: bool, z: i32) {
+    let track_height = (rect.3 / 4.0).max(1.0);
+    let track = (rect.0, rect.1 + (rect.3 - track_height) / 2.0, rect.2, track_height);
+    queue.push(filled_rect(track, slider.colors.normal, z));
+
+    let range = slider.max - slider.min;
+    let t = if range != 0.0 { ((slider.value - slider.min) / range).clamp(0.0, 1.0) } else { 0.0 };
+    let handle_width = rect.3 / 2.0;
+    let handle = (rect.0 + t * rect.2 - handle_width / 2.0, rect.1, handle_width, rect.3);
+    let color = widget_color(&slider.colors, hovered, slider.dragging);
+    queue.push(filled_rect(handle, color, z));
+    queue.push(outlined_rect(handle, OUTLINE_COLOR, z));
+}
@@ -3,8 +3,10 @@ use fennel_audio::Audio;
 
 fn main() -> Result<(), Box<dyn Error>> {
 	let mut audio = Audio::new()?;
-	let sink1 = audio.play_file("assets/music.ogg")?;
-	let sink2 = audio.play_file("assets/440.wav")?;
+	audio.load_dir("assets")?;
+	audio.wait();
+	let sink1 = audio.play("music")?;
+	let sink2 = audio.play("beep")?;
 	std::thread::sleep(std::time::Duration::from_secs(5));
 	println!("stopped first sink");
 	sink1.stop();
@@ -12,4 +14,4 @@ fn main() -> Result<(), Box<dyn Error>> {
 	println!("stopped second sink");
 	sink2.stop();
 	Ok(())
-}
\ No newline at end of file
+}
@@ -1,34 +1,169 @@
 pub mod plugin;
+pub mod sound;
+#[cfg(test)]
+mod tests;
 
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
 use std::path::Path;
-use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, SpatialSink};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use fennel_resources::handle::KeepAlive;
+use fennel_resources::loader::{LoadFailure, LoadProgress, Loader, DEFAULT_GROUP};
+use fennel_resources::manager::ResourceManager;
+use fennel_resources::manifest::{AudioOptions, ManifestAsset};
+use rodio::{OutputStream, OutputStreamBuilder, Sink, SpatialSink};
+use crate::sound::Sound;
 
 pub struct Audio {
-	stream_handle: OutputStream
+	stream_handle: OutputStream,
+	/// Sounds loaded from manifests, by name
+	sounds: ResourceManager,
+	/// Decodes the sounds on worker threads, behind a mutex because its receiver isn't `Sync`
+	loader: Mutex<Loader<()>>,
+	/// Sounds of the loaded manifests by group, the ungrouped ones in [`DEFAULT_GROUP`]
+	groups: HashMap<String, Vec<ManifestAsset>>,
+	/// Sounds kept loaded by the active groups
+	held: HashMap<String, Vec<KeepAlive>>,
 }
 
+/// Time spent finishing loaded sounds per [`Audio::poll`]
+const POLL_BUDGET: Duration = Duration::from_millis(2);
+
 impl Audio {
 	/// Create a new instance of [`Audio`]
 	pub fn new() -> Result<Self, Box<dyn Error>> {
 		let stream_handle = OutputStreamBuilder::open_default_stream()?;
 		Ok(Self {
-			stream_handle
+			stream_handle,
+			sounds: ResourceManager::new(),
+			loader: Mutex::new(Loader::default()),
+			groups: HashMap::new(),
+			held: HashMap::new(),
 		})
 	}
 
+	/// Load the `Audio` assets of the directory's `manifest.toml`, so they can be played by name
+	///
+	/// Sounds are loaded in the background and can be played once [`Audio::poll`] finished them.
+	/// Only the sounds without a `group` are loaded right away, the others with
+	/// [`Audio::load_group`]. Sounds are decoded when they're loaded, except the ones with
+	/// `stream = true` which are decoded while they play, for long music:
+	///
+	/// ```toml
+	/// [[assets]]
+	/// name = "music"
+	/// path = "music.ogg"
+	/// type = "Audio"
+	/// stream = true
+	/// ```
+	pub fn load_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Box<dyn Error>> {
+		for asset in sound::read_manifest(path.as_ref())? {
+			let group = asset.entry.group.as_deref().unwrap_or(DEFAULT_GROUP);
+			self.groups.entry(group.to_string()).or_default().push(asset);
+		}
+		self.load_group(DEFAULT_GROUP);
+		Ok(())
+	}
+
+	/// Start loading the sounds of a manifest group, they stay loaded until
+	/// [`Audio::unload_group`]
+	pub fn load_group(&mut self, group: &str) {
+		let assets = self.groups.get(group).cloned().unwrap_or_default();
+		let loader = self.loader.get_mut().unwrap_or_else(PoisonError::into_inner);
+		for asset in assets {
+			let name = asset.entry.name.clone();
+			if !self.sounds.is_cached(&name) {
+				loader.request(&mut self.sounds, &name, group, move || {
+					let options: AudioOptions = asset.entry.options()?;
+					Sound::load(&asset.entry.name, asset.path(), options.stream).map_err(|e| anyhow::anyhow!("{e}"))
+				}, |sound, _| Ok(sound));
+			}
+			if group == DEFAULT_GROUP {
+				continue;
+			}
+			let Ok(keep_alive) = self.sounds.keep_alive(&name) else { continue };
+			self.sounds.release(&name).ok();
+			self.held.entry(group.to_string()).or_default().push(keep_alive);
+		}
+	}
+
+	/// Stop keeping the sounds of a manifest group loaded, the ones no other active group uses
+	/// are removed. Sinks already playing them go on
+	pub fn unload_group(&mut self, group: &str) {
+		self.loader.get_mut().unwrap_or_else(PoisonError::into_inner).forget_group(group);
+		self.held.remove(group);
+		self.sounds.collect_unused();
+	}
+
+	/// Finish the sounds decoded in the background, call it regularly, [`crate::plugin::AudioPlugin`]
+	/// does it every tick
+	///
+	/// # Returns
+	/// The sounds that failed to load
+	pub fn poll(&mut self) -> Vec<LoadFailure> {
+		let loader = self.loader.get_mut().unwrap_or_else(PoisonError::into_inner);
+		loader.poll(&mut self.sounds, &mut (), POLL_BUDGET)
+	}
+
+	/// Block until every requested sound is loaded, see [`Audio::poll`]
+	pub fn wait(&mut self) -> Vec<LoadFailure> {
+		let loader = self.loader.get_mut().unwrap_or_else(PoisonError::into_inner);
+		loader.wait(&mut self.sounds, &mut ())
+	}
+
+	/// Progress of a group, empty if its loading didn't start
+	pub fn progress(&self, group: &str) -> LoadProgress {
+		self.loader.lock().unwrap_or_else(PoisonError::into_inner).progress(group)
+	}
+
+	/// Is any sound still being loaded?
+	pub fn is_loading(&self) -> bool {
+		self.loader.lock().unwrap_or_else(PoisonError::into_inner).is_loading()
+	}
+
+	/// Sounds loaded with [`Audio::load_dir`]
+	pub fn sounds(&self) -> &ResourceManager {
+		&self.sounds
+	}
+
+	/// Play a sound loaded with [`Audio::load_dir`], an error if it's still loading
+	///
+	/// # Returns
+	/// [`Sink`] wrapped in a [`Result`], which you must take ownership of, because otherwise
+	/// the audio won't be played.
+	pub fn play(&mut self, name: &str) -> Result<Sink, Box<dyn Error>> {
+		let source = self.sounds.get(self.sounds.handle::<Sound>(name)?)?.source()?;
+		let sink = Sink::connect_new(self.stream_handle.mixer());
+		sink.append(source);
+		Ok(sink)
+	}
+
+	/// Play a sound loaded with [`Audio::load_dir`] from a position in space. See
+	/// [`Audio::play`]
+	pub fn play_spatial(
+		&mut self,
+		name: &str,
+		emitter_position: [f32; 3],
+		left_ear: [f32; 3],
+		right_ear: [f32; 3],
+	) -> Result<SpatialSink, Box<dyn Error>> {
+		let source = self.sounds.get(self.sounds.handle::<Sound>(name)?)?.source()?;
+		let sink = SpatialSink::connect_new(self.stream_handle.mixer(), emitter_position, left_ear, right_ear);
+		sink.append(source);
+		Ok(sink)
+	}
+
 	/// Play audio from a file.
 	///
 	/// # Arguments
-	/// * `path` - path to the audio file
+	/// * `path` - path to the audio file, read through the VFS
 	///
 	/// # Returns
 	/// [`Sink`] wrapped in a [`Result`], which you must take ownership of, because otherwise
 	/// the audio won't be played.
 	pub fn play_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Sink, Box<dyn Error>> {
-		let file = File::open(path)?;
-		let source = Decoder::try_from(file)?;
+		let source = sound::decode(path.as_ref())?;
 		let sink = Sink::connect_new(self.stream_handle.mixer());
 		sink.append(source);
 		Ok(sink)
@@ -37,7 +172,7 @@ impl Audio {
 	/// Play audio from a file.
 	///
 	/// # Arguments
-	/// * `path` - path to the audio file
+	/// * `path` - path to the audio file, read through the VFS
 	///
 	/// # Returns
 	/// [`Sink`] wrapped in a [`Result`], which you must take ownership of, because otherwise
@@ -49,8 +184,7 @@ impl Audio {
 		left_ear: [f32; 3],
 		right_ear: [f32; 3],
 	) -> Result<SpatialSink, Box<dyn Error>> {
-		let file = File::open(path)?;
-		let source = Decoder::try_from(file)?;
+		let source = sound::decode(path.as_ref())?;
 		let sink = SpatialSink::connect_new(self.stream_handle.mixer(), emitter_position, left_ear, right_ear);
 		sink.append(source);
 		Ok(sink)
@@ -1,24 +1,40 @@
+use std::collections::BTreeSet;
 use std::error::Error;
+use std::path::PathBuf;
+use log::error;
 use rodio::Sink;
-use shred::{DispatcherBuilder, World};
+use shred::{DispatcherBuilder, System, World, WriteExpect};
+use specs::{Join, ReadStorage, WorldExt};
+use fennel_registry::AssetGroups;
+use fennel_resources::info::AssetInfo;
 use fennel_plugins::Plugin;
 use crate::Audio;
 
 extern crate rodio;
 
-pub struct AudioPlugin;
+pub struct AudioPlugin {
+	assets_path: Option<PathBuf>,
+}
 
 impl AudioPlugin {
 	/// Create a new instance of [`AudioPlugin`]
 	pub fn new() -> Self {
-		Self
+		Self { assets_path: None }
+	}
+
+	/// Load the audio assets of a manifest directory, such as `assets://`, so systems can play
+	/// them by name with [`Audio::play`]. Grouped sounds are loaded while an [`AssetGroups`]
+	/// component references their group
+	pub fn with_assets<P: Into<PathBuf>>(mut self, path: P) -> Self {
+		self.assets_path = Some(path.into());
+		self
 	}
 }
 
 impl Plugin for AudioPlugin {
 	fn prepare(
 		&mut self,
-		_dispatcher_builder: &mut DispatcherBuilder,
+		dispatcher_builder: &mut DispatcherBuilder,
 		world: &mut World,
 	) -> Result<(), Box<dyn Error>> {
 		// sometimes i wish i was born a girl, i just want to wear cool clothes have long cool hair dye it etc
@@ -28,7 +44,14 @@ impl Plugin for AudioPlugin {
 		// out of scope anyway, so we provide them a way to store sinks
 		let sinks: Vec<Sink> = Vec::new();
 		world.insert(sinks);
-		world.insert(Audio::new()?);
+		let mut audio = Audio::new()?;
+		if let Some(path) = &self.assets_path {
+			audio.load_dir(path)?;
+		}
+		world.insert(audio);
+		world.entry::<AssetInfo>().or_insert_with(AssetInfo::default);
+		world.register::<AssetGroups>();
+		dispatcher_builder.add(AudioSystem::default(), "audio_system", &[]);
 		Ok(())
 	}
 
@@ -41,6 +64,34 @@ impl Plugin for AudioPlugin {
 	}
 }
 
+/// Loads the sound groups referenced by [`AssetGroups`] components, finishes the sounds loaded
+/// in the background and keeps them in the [`AssetInfo`]
+#[derive(Default)]
+struct AudioSystem {
+	/// Groups requested so far
+	loaded: BTreeSet<String>,
+}
+
+impl<'a> System<'a> for AudioSystem {
+	type SystemData = (WriteExpect<'a, Audio>, WriteExpect<'a, AssetInfo>, ReadStorage<'a, AssetGroups>);
+
+	fn run(&mut self, (mut audio, mut info, asset_groups): Self::SystemData) {
+		let wanted: BTreeSet<String> = asset_groups.join().flat_map(|groups| groups.groups.iter().cloned()).collect();
+		for group in wanted.difference(&self.loaded) {
+			audio.load_group(group);
+		}
+		for group in self.loaded.difference(&wanted) {
+			audio.unload_group(group);
+		}
+		self.loaded = wanted;
+
+		for failure in audio.poll() {
+			error!("failed to load sound '{}': {}", failure.name, failure.error);
+		}
+		info.sync("audio", audio.sounds());
+	}
+}
+
 impl Default for AudioPlugin {
 	fn default() -> Self {
 		Self::new()
@@ -0,0 +1,111 @@
+//! Sounds declared in an assets manifest, played by name with [`crate::Audio::play`]
+
+use std::any::Any;
+use std::error::Error;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rodio::buffer::SamplesBuffer;
+use rodio::{Decoder, Source};
+use fennel_resources::info::AssetMetadata;
+use fennel_resources::manifest::{Manifest, ManifestAsset};
+use fennel_resources::resource::Resource;
+use fennel_resources::vfs::{self, VfsReader};
+
+/// Audio assets of a directory's `manifest.toml` and of the manifests it includes
+pub(crate) fn read_manifest(dir: &Path) -> Result<Vec<ManifestAsset>, Box<dyn Error>> {
+	let assets = Manifest::read(dir)?.assets;
+	Ok(assets.into_iter().filter(|asset| asset.entry.class == "Audio").collect())
+}
+
+/// Sound asset stored in the resource manager of [`crate::Audio`]
+pub struct Sound {
+	name: String,
+	data: SoundData,
+	/// Length of the sound, if the format tells it
+	duration: Option<Duration>,
+}
+
+enum SoundData {
+	/// Samples decoded when the sound was loaded, shared by every playback
+	Decoded(SamplesBuffer),
+	/// File decoded while the sound plays
+	Streamed(PathBuf),
+}
+
+impl Sound {
+	/// Load a sound, decoding it right away unless it's `streamed`, in which case only the
+	/// beginning of the file is read to know its duration
+	pub fn load<P: Into<PathBuf>>(name: &str, path: P, streamed: bool) -> Result<Self, Box<dyn Error>> {
+		let path = path.into();
+		let decoder = decode(&path)?;
+		let duration = decoder.total_duration();
+		let data = if streamed {
+			SoundData::Streamed(path)
+		} else {
+			let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+			SoundData::Decoded(SamplesBuffer::new(channels, sample_rate, decoder.collect::<Vec<f32>>()))
+		};
+		Ok(Self { name: name.to_string(), data, duration })
+	}
+
+	/// Length of the sound, `None` if it can't be known without decoding a streamed sound
+	pub fn duration(&self) -> Option<Duration> {
+		match &self.data {
+			SoundData::Decoded(samples) => samples.total_duration(),
+			SoundData::Streamed(_) => self.duration,
+		}
+	}
+
+	/// Is the sound decoded while it plays?
+	pub fn is_streamed(&self) -> bool {
+		matches!(self.data, SoundData::Streamed(_))
+	}
+
+	/// A new source playing the sound from the start
+	pub(crate) fn source(&self) -> Result<Box<dyn Source + Send>, Box<dyn Error>> {
+		Ok(match &self.data {
+			SoundData::Decoded(samples) => Box::new(samples.clone()),
+			SoundData::Streamed(path) => Box::new(decode(path)?),
+		})
+	}
+}
+
+/// Open an audio file through the VFS, it's read progressively as it's decoded
+pub(crate) fn decode(path: &Path) -> Result<Decoder<BufReader<VfsReader>>, Box<dyn Error>> {
+	let mut reader = vfs::open(path)?;
+	let len = reader.seek(SeekFrom::End(0))?;
+	reader.rewind()?;
+	Ok(Decoder::builder()
+		.with_data(BufReader::new(reader))
+		.with_byte_len(len)
+		.with_seekable(true)
+		.build()?)
+}
+
+impl Resource for Sound {
+	fn data(&self) -> &dyn Any {
+		&self.data
+	}
+
+	fn data_mut(&mut self) -> &mut dyn Any {
+		&mut self.data
+	}
+
+	fn name(&self) -> String {
+		self.name.clone()
+	}
+
+	fn memory_usage(&self) -> usize {
+		match &self.data {
+			SoundData::Decoded(samples) => samples.total_duration().map_or(0, |duration| {
+				(duration.as_secs_f64() * samples.sample_rate() as f64 * samples.channels() as f64) as usize * size_of::<f32>()
+			}),
+			SoundData::Streamed(_) => 0,
+		}
+	}
+
+	fn metadata(&self) -> Option<AssetMetadata> {
+		Some(AssetMetadata::Audio { duration: self.duration() })
+	}
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+use fennel_resources::info::AssetMetadata;
+use fennel_resources::manifest::AudioOptions;
+use fennel_resources::resource::Resource;
+use crate::sound::{read_manifest, Sound};
+
+#[test]
+fn sounds_are_decoded_unless_streamed() {
+	let beep = Sound::load("beep", "../assets/440.wav", false).unwrap();
+	assert!(!beep.is_streamed());
+	assert!(beep.memory_usage() > 0);
+	assert!(beep.duration().is_some_and(|duration| !duration.is_zero()));
+
+	// streamed sounds are only probed for their duration when loaded, and decoded when played
+	let music = Sound::load("music", "../assets/music.ogg", true).unwrap();
+	assert!(music.is_streamed());
+	assert_eq!(music.memory_usage(), 0);
+	assert!(music.source().is_ok());
+	assert_eq!(music.metadata(), Some(AssetMetadata::Audio { duration: music.duration() }));
+	assert!(Sound::load("missing", "../assets/missing.ogg", false).is_err());
+}
+
+#[test]
+fn manifest_audio_assets() {
+	let sounds = read_manifest(Path::new("../assets")).unwrap();
+	let music = sounds.iter().find(|sound| sound.entry.name == "music").unwrap();
+	assert_eq!(music.path(), Path::new("../assets/music.ogg"));
+	assert!(music.entry.options::<AudioOptions>().unwrap().stream);
+	assert!(sounds.iter().all(|sound| sound.entry.class == "Audio"));
+}
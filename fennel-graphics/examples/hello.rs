@@ -1,14 +1,11 @@
-use std::{
-    path::PathBuf,
-    sync::{Arc, Mutex},
-};
+use std::path::PathBuf;
 use fennel_graphics::{
     Window,
     events::{self, KeyboardEvent, WindowEventHandler},
     graphics,
 };
 use sdl3::pixels::Color;
-use fennel_resources::manager::ResourceManager;
+use fennel_resources::manager::SharedResourceManager;
 
 struct State;
 
@@ -55,17 +52,13 @@ impl WindowEventHandler for State {
 }
 
 fn main() -> anyhow::Result<()> {
-    let resource_manager = Arc::new(Mutex::new(ResourceManager::new()));
+    let resource_manager = SharedResourceManager::default();
     let graphics = graphics::GraphicsBuilder::new()
         .window_name(String::from("game"))
         .dimensions((500, 500))
         .resource_manager(resource_manager.clone())
         .initializer(|graphics| {
-            let mut resource_manager = match resource_manager.try_lock() {
-                Ok(guard) => guard,
-                Err(e) => return Err(anyhow::anyhow!("failed to lock resource_manager: {}", e)),
-            };
-            fennel_graphics::resources::load_dir(&mut resource_manager, PathBuf::from("assets"), graphics)?;
+            fennel_graphics::resources::load_dir(&mut resource_manager.write(), PathBuf::from("assets"), graphics)?;
             Ok(())
         })
         .build().expect("failed to build graphics");
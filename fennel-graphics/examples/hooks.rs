@@ -3,10 +3,7 @@
 // You can't have ImGui or something that utilizes GPU rendering and draw something onto the canvas
 // at the same time.
 
-use std::{
-    path::PathBuf,
-    sync::{Arc, Mutex},
-};
+use std::path::PathBuf;
 use fennel_graphics::{
     Window,
     events::{self, WindowEventHandler},
@@ -20,7 +17,7 @@ use sdl3::{
     gpu::{ColorTargetInfo, Device, LoadOp, ShaderFormat, StoreOp},
     pixels::Color,
 };
-use fennel_resources::manager::ResourceManager;
+use fennel_resources::manager::SharedResourceManager;
 
 struct State;
 struct MyHook {
@@ -113,17 +110,13 @@ impl Hook for MyHook {
 }
 
 fn main() -> anyhow::Result<()> {
-    let resource_manager = Arc::new(Mutex::new(ResourceManager::new()));
+    let resource_manager = SharedResourceManager::default();
     let graphics = graphics::GraphicsBuilder::new()
         .window_name(String::from("game"))
         .dimensions((1360, 768))
         .resource_manager(resource_manager.clone())
         .initializer(|graphics| {
-            let mut resource_manager = match resource_manager.try_lock() {
-                Ok(guard) => guard,
-                Err(e) => return Err(anyhow::anyhow!("failed to lock resource_manager: {}", e)),
-            };
-            fennel_graphics::resources::load_dir(&mut resource_manager, PathBuf::from("../../assets"), graphics)?;
+            fennel_graphics::resources::load_dir(&mut resource_manager.write(), PathBuf::from("../../assets"), graphics)?;
             Ok(())
         })
         .build();
@@ -11,6 +11,7 @@
 //! - `MouseMotionEvent`: Represents mouse motion events
 //! - `MouseClickEvent`: Represents mouse button click events
 //! - `MouseWheelEvent`: Represents mouse wheel events
+//! - `TextInputEvent`: Represents text typed by the user
 //!
 //! ## Trait
 //!
@@ -119,6 +120,17 @@ pub struct MouseWheelEvent {
     pub mouse_y: f32,
 }
 
+/// Represents text typed by the user, with the keyboard layout and input method applied.
+#[derive(Debug)]
+pub struct TextInputEvent {
+    /// When the event happened (in nanos)
+    pub timestamp: u64,
+    /// The window with keyboard focus, if any
+    pub window_id: u32,
+    /// The typed text
+    pub text: String,
+}
+
 /// Trait that any type that is to be supplied to [`crate::events::run`] should implement.
 pub trait WindowEventHandler {
     /// Update the application logic
@@ -170,6 +182,14 @@ pub trait WindowEventHandler {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+    /// Handle a text input event, only sent after text input was started on the window
+    fn text_input_event(
+        &mut self,
+        _window: &mut Window,
+        _event: TextInputEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 /// Run the main loop.
@@ -352,6 +372,20 @@ pub fn run(
                             mouse_y,
                         },
                     )?,
+
+                Event::TextInput {
+                    timestamp,
+                    window_id,
+                    text,
+                } => state
+                    .text_input_event(
+                        window,
+                        TextInputEvent {
+                            timestamp,
+                            window_id,
+                            text,
+                        },
+                    )?,
                 _ => {}
             }
         }
@@ -5,18 +5,37 @@
 //! - `Graphics::new(...)`: initialize SDL, create a centered resizable window and return [`Graphics`]
 //!
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::Duration;
 use anyhow::Context;
+use log::{debug, error};
 use sdl3::Sdl;
-use sdl3::pixels::{Color, PixelFormat};
-use sdl3::render::{Canvas, FRect};
+use sdl3::pixels::Color;
+use sdl3::pixels::FColor;
+use sdl3::rect::Rect;
+use sdl3::pixels::PixelFormat;
+use sdl3::render::{BlendMode, Canvas, FPoint, FRect, Texture, Vertex, VertexIndices};
 use sdl3::video::Window;
 use serde::Deserialize;
-use fennel_resources::manager::ResourceManager;
-use crate::resources::font::{Font, InternalDummyFont, InternalFont};
+use fennel_resources::asset_loader::AssetLoaders;
+use fennel_resources::handle::{Handle, KeepAlive, StrongHandle};
+use fennel_resources::loader::{Loader, DEFAULT_GROUP};
+use fennel_resources::manager::{LoadState, ResourceManager, SharedResourceManager};
+use fennel_resources::manifest::ImageOptions;
+use fennel_resources::vfs;
+use fennel_resources::ResourceError;
+use crate::resources;
+use crate::resources::font::{DummyFont, InternalDummyFont, InternalFont};
+use crate::resources::gpu::GpuResources;
 use crate::resources::image::{Image, InnerImage};
+use crate::resources::sprite_sheet::SpriteSheet;
+use crate::plugin::system::{CameraView, RenderTarget};
+use crate::shapes;
+use crate::text::{layout_block, PositionedLines, TextLayout, TextShadow};
+use crate::text_cache::{TextCache, TextCacheConfig, TextCacheMode};
 
 /// Owned SDL variables used for rendering
 ///
@@ -31,8 +50,20 @@ pub struct Graphics {
     pub texture_creator: Rc<sdl3::render::TextureCreator<sdl3::video::WindowContext>>,
     /// SDL3 TTF context required for text rendering
     pub ttf_context: sdl3::ttf::Sdl3TtfContext,
-    /// Reference to [`ResourceManager`]
-    resource_manager: Arc<Mutex<ResourceManager>>,
+    /// Cache of rendered text, separate from the named assets in the [`ResourceManager`]
+    pub text_cache: TextCache,
+    /// Decodes assets on worker threads, their textures are created in [`Graphics::poll_assets`]
+    pub loader: Loader<GpuResources>,
+    /// Textures and opened fonts of the assets, which only this thread can use
+    pub gpu: GpuResources,
+    /// Loaders of the custom asset types the manifests can declare
+    pub asset_loaders: AssetLoaders,
+    /// Assets of the loaded manifests, by group
+    pub(crate) catalog: resources::Catalog,
+    /// Asset data any thread can read, shared with the ECS
+    resource_manager: SharedResourceManager,
+    /// Images drawn by name since the last [`Graphics::poll_assets`], kept loaded until then
+    drawn_by_name: HashMap<String, KeepAlive>,
 }
 
 /// Config for [`Graphics::new`] and for [`GraphicsBuilder`]
@@ -44,6 +75,10 @@ pub struct WindowConfig {
     pub is_fullscreen: bool,
     /// Is the window centered by default?
     pub is_centered: bool,
+    /// Render offscreen: no window is shown, SDL uses its dummy video driver and the software
+    /// renderer, so rendering works without a display. Read the frames with
+    /// [`Graphics::screenshot`]
+    pub is_headless: bool,
 }
 
 /// Builder for creating a Graphics instance.
@@ -51,25 +86,44 @@ pub struct GraphicsBuilder<F>
 where
     F: Fn(&mut Graphics) -> anyhow::Result<()>,
 {
-    resource_manager: Option<Arc<Mutex<ResourceManager>>>,
+    resource_manager: Option<SharedResourceManager>,
     dimensions: (u32, u32),
     name: String,
     initializer: Option<F>,
     config: WindowConfig,
+    text_cache: TextCacheConfig,
 }
 
 
+/// Name of the render layer used when none is specified
+pub const DEFAULT_LAYER: &str = "world";
+
 /// A drawable primitive that can be queued for rendering
 ///
+/// Every drawable belongs to a named render layer and has a `z` order inside of that layer,
+/// see [`crate::plugin::system::RenderLayers`].
+///
 /// Variants:
-/// - Image(Sprite) - a sprite to draw
-/// - Rect { w, h, x, y } - a rectangle specified with width, height, x and y position (all `f32`)
-#[derive(Debug)]
+/// - Image(Sprite) - a sprite to draw, the layer and z order are taken from the sprite
+/// - Rect, Circle, Line, Polyline, Polygon - primitive shapes in an RGBA color
+/// - Text - a text, optionally wrapped and aligned, see [`TextLayout`]
+///
+/// Shapes with `filled` set to `false` are outlined with lines `thickness` pixels wide.
+#[derive(Debug, Clone)]
 pub enum Drawable {
     /// A sprite. Use for queueing render of some image
     Image(Sprite),
-    /// A basic rectangle
-    Rect { w: f32, h: f32, x: f32, y: f32 },
+    /// A rectangle with its top-left corner at (x, y). Rectangles stay axis-aligned when the
+    /// camera is rotated, use a polygon for a rectangle that has to rotate with the world
+    Rect { w: f32, h: f32, x: f32, y: f32, color: (u8, u8, u8, u8), filled: bool, thickness: f32, layer: String, z: i32 },
+    /// A circle
+    Circle { center: (f32, f32), radius: f32, color: (u8, u8, u8, u8), filled: bool, thickness: f32, layer: String, z: i32 },
+    /// A single line segment
+    Line { from: (f32, f32), to: (f32, f32), color: (u8, u8, u8, u8), thickness: f32, layer: String, z: i32 },
+    /// Connected line segments, a `closed` polyline also connects the last point to the first one
+    Polyline { points: Vec<(f32, f32)>, closed: bool, color: (u8, u8, u8, u8), thickness: f32, layer: String, z: i32 },
+    /// A polygon, filled as a triangle fan around its first point
+    Polygon { points: Vec<(f32, f32)>, color: (u8, u8, u8, u8), filled: bool, thickness: f32, layer: String, z: i32 },
     /// Text drawable.
     ///
     /// # Fields
@@ -77,23 +131,201 @@ pub enum Drawable {
     /// * `position`: Position in `(f32, f32)` relative to the window
     /// * `text`: The text itself to render
     /// * `color`: RGB tuple of `u8`
-    /// * `size`: Font size in `f32`
-    Text { font: String, position: (f32, f32), text: String, color: (u8, u8, u8), size: f32 },
+    /// * `size`: Font size in `f32`, 0 for the default size of the font from the manifest
+    /// * `layout`: Wrapping, alignment, line spacing, outline and shadow of the text
+    /// * `layer`: Name of the render layer
+    /// * `z`: Order inside the render layer, higher is drawn later
+    Text {
+        font: String,
+        position: (f32, f32),
+        text: String,
+        color: (u8, u8, u8),
+        size: f32,
+        layout: TextLayout,
+        layer: String,
+        z: i32,
+    },
+}
+
+impl Drawable {
+    /// Name of the render layer this drawable belongs to
+    pub fn layer(&self) -> &str {
+        match self {
+            Drawable::Image(sprite) => &sprite.layer,
+            Drawable::Rect { layer, .. }
+            | Drawable::Circle { layer, .. }
+            | Drawable::Line { layer, .. }
+            | Drawable::Polyline { layer, .. }
+            | Drawable::Polygon { layer, .. }
+            | Drawable::Text { layer, .. } => layer,
+        }
+    }
+
+    /// Order of this drawable inside of its render layer
+    pub fn z(&self) -> i32 {
+        match self {
+            Drawable::Image(sprite) => sprite.z,
+            Drawable::Rect { z, .. }
+            | Drawable::Circle { z, .. }
+            | Drawable::Line { z, .. }
+            | Drawable::Polyline { z, .. }
+            | Drawable::Polygon { z, .. }
+            | Drawable::Text { z, .. } => *z,
+        }
+    }
+
+    /// Area (x, y, w, h) the drawable covers, used to cull drawables outside of the camera view
+    ///
+    /// `None` if it can't be known without the graphics thread: for text, and for sprites
+    /// with neither a `size` nor a `source` as the size of their image is unknown.
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let inflate = |(x, y, w, h): (f32, f32, f32, f32), by: f32| (x - by, y - by, w + by * 2.0, h + by * 2.0);
+        match self {
+            Drawable::Image(sprite) => {
+                let (w, h) = sprite.size.or(sprite.source.map(|(_, _, w, h)| (w, h)))?;
+                let scale = sprite.transform.scale as f32;
+                let (w, h) = (w * scale, h * scale);
+                let pivot = sprite.transform.position;
+                let rect = (pivot.0 - sprite.origin.0 * w, pivot.1 - sprite.origin.1 * h, w, h);
+                shapes::bounding_box(shapes::rotated_rect(rect, pivot, sprite.transform.rotation))
+            }
+            Drawable::Rect { w, h, x, y, filled, thickness, .. } => {
+                let outline = if *filled { 0.0 } else { thickness / 2.0 };
+                Some(inflate((*x, *y, *w, *h), outline))
+            }
+            Drawable::Circle { center, radius, filled, thickness, .. } => {
+                let outline = if *filled { 0.0 } else { thickness / 2.0 };
+                Some(inflate((center.0 - radius, center.1 - radius, radius * 2.0, radius * 2.0), outline))
+            }
+            Drawable::Line { from, to, thickness, .. } => {
+                shapes::bounding_box([*from, *to]).map(|rect| inflate(rect, thickness / 2.0))
+            }
+            Drawable::Polyline { points, thickness, .. } | Drawable::Polygon { points, thickness, .. } => {
+                shapes::bounding_box(points.iter().copied()).map(|rect| inflate(rect, thickness / 2.0))
+            }
+            Drawable::Text { .. } => None,
+        }
+    }
 }
 
 /// A simple renderable sprite.
 ///
 /// # Fields
 /// - image: identifier or path of the image to draw
-/// - position: tuple (x, y) position on screen
+/// - transform: position, scale and rotation of the sprite
+/// - source: optional (x, y, w, h) part of the image to draw, the whole image is drawn if `None`
+/// - size: optional size to stretch the drawn part of the image to, before scaling
+/// - tint, alpha: color and alpha modulation
+/// - flip_horizontal, flip_vertical: mirror the sprite
+/// - origin: pivot point, relative to the sprite size, `(0.5, 0.5)` is the center
 #[derive(Deserialize, Debug, Clone)]
 pub struct Sprite {
-    /// Sprite asset id in the resource manager
+    /// Sprite asset id in the resource manager, or a `"sheet:frame"` reference to a frame of a
+    /// sprite sheet
     pub image: String,
     /// Representing sprite's transformation in the 2D world
     pub transform: Transform,
     /// Is this sprite fixed on screen? (not affected by camera)
-    pub fixed: bool
+    pub fixed: bool,
+    /// Name of the render layer the sprite is drawn on
+    #[serde(default = "default_layer")]
+    pub layer: String,
+    /// Order inside the render layer, higher is drawn later
+    #[serde(default)]
+    pub z: i32,
+    /// Part of the image to draw as (x, y, w, h) in pixels, the whole image if `None`
+    #[serde(default)]
+    pub source: Option<(f32, f32, f32, f32)>,
+    /// Size in pixels the image part is stretched to before scaling, its own size if `None`
+    #[serde(default)]
+    pub size: Option<(f32, f32)>,
+    /// RGB color multiplied into the image, white leaves it unchanged
+    #[serde(default = "default_tint")]
+    pub tint: (u8, u8, u8),
+    /// Alpha multiplied into the image, 255 is fully opaque
+    #[serde(default = "default_alpha")]
+    pub alpha: u8,
+    /// Mirror the sprite horizontally
+    #[serde(default)]
+    pub flip_horizontal: bool,
+    /// Mirror the sprite vertically
+    #[serde(default)]
+    pub flip_vertical: bool,
+    /// Pivot of the sprite relative to its size: the sprite is placed so the pivot lands on
+    /// `transform.position` and is rotated around it. `(0.0, 0.0)` is the top-left corner
+    #[serde(default)]
+    pub origin: (f32, f32),
+    /// `image` resolved in the resource manager, see [`Sprite::resolve`]
+    #[serde(skip)]
+    resolved: Option<SpriteImage>,
+    /// Keeps the resolved image loaded while the sprite exists
+    #[serde(skip)]
+    keep_alive: Option<KeepAlive>,
+}
+
+/// Image of a [`Sprite`] resolved to a handle, so drawing it doesn't look its name up
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpriteImage {
+    /// A whole image
+    Image(Handle<Image>),
+    /// A frame of a sprite sheet
+    Frame(Handle<SpriteSheet>, Arc<str>),
+}
+
+impl SpriteImage {
+    /// Resolve an image name or a `"sheet:frame"` reference
+    ///
+    /// # Returns
+    /// `None` if neither the image nor the sprite sheet is in the resource manager
+    pub fn resolve(manager: &ResourceManager, key: &str) -> Result<Option<Self>, ResourceError> {
+        // "sheet:frame" references a frame of a sprite sheet, unless an image is registered
+        // under this exact name. An image that failed to load, such as a frame once requested as
+        // a file, doesn't hide the frame
+        let image_failed = manager.handle::<Image>(key)
+            .and_then(|handle| manager.load_state(handle))
+            .is_ok_and(|state| matches!(state, LoadState::Failed(_)));
+        match Self::split_frame(key) {
+            Some((sheet, frame)) if (!manager.is_cached(key) || image_failed) && manager.is_cached(sheet) => {
+                Ok(Some(Self::Frame(manager.handle(sheet)?, Arc::from(frame))))
+            }
+            _ if manager.is_cached(key) => Ok(Some(Self::Image(manager.handle(key)?))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Split a `"sheet:frame"` reference, paths such as `assets://player.png` or `C:\player.png`
+    /// aren't references
+    pub(crate) fn split_frame(key: &str) -> Option<(&str, &str)> {
+        key.split_once(':').filter(|(_, frame)| !frame.contains(['/', '\\']))
+    }
+
+    /// Does the handle still refer to a resource? It becomes stale once the resource is removed
+    pub fn is_current(&self, manager: &ResourceManager) -> bool {
+        match self {
+            Self::Image(handle) => manager.load_state(*handle).is_ok(),
+            Self::Frame(handle, _) => manager.load_state(*handle).is_ok(),
+        }
+    }
+
+    /// Reference keeping the image or the sprite sheet loaded while it's held
+    pub fn keep_alive(&self, manager: &ResourceManager) -> Result<KeepAlive, ResourceError> {
+        match self {
+            Self::Image(handle) => Ok(manager.strong(*handle)?.keep_alive()),
+            Self::Frame(handle, _) => Ok(manager.strong(*handle)?.keep_alive()),
+        }
+    }
+}
+
+fn default_layer() -> String {
+    DEFAULT_LAYER.to_string()
+}
+
+fn default_tint() -> (u8, u8, u8) {
+    (255, 255, 255)
+}
+
+fn default_alpha() -> u8 {
+    255
 }
 
 impl specs::Component for Sprite {
@@ -123,13 +355,127 @@ impl Sprite {
     /// * `image`: [`String`] identifier of the image in the resource manager
     /// * `transform`: [`Transform`] of the sprite (position, scale, rotation)
     /// * `fixed`: is this sprite fixed on the screen?
+    ///
+    /// The sprite is put on the [`DEFAULT_LAYER`] with z order of zero, use [`Sprite::with_layer`]
+    /// to change that.
     pub fn new(image: String, transform: Transform, fixed: bool) -> Self {
         Self {
             image,
             transform,
-            fixed
+            fixed,
+            layer: default_layer(),
+            z: 0,
+            source: None,
+            size: None,
+            tint: default_tint(),
+            alpha: default_alpha(),
+            flip_horizontal: false,
+            flip_vertical: false,
+            origin: (0.0, 0.0),
+            resolved: None,
+            keep_alive: None,
         }
     }
+
+    /// Creates a new instance of [`Sprite`] drawing an image already resolved to a handle,
+    /// keeping it loaded while the sprite exists
+    ///
+    /// The sprite has no `image` name, so it draws nothing once the image is removed.
+    pub fn from_handle(image: &StrongHandle<Image>, transform: Transform, fixed: bool) -> Self {
+        Self {
+            resolved: Some(SpriteImage::Image(image.handle())),
+            keep_alive: Some(image.keep_alive()),
+            ..Self::new(String::new(), transform, fixed)
+        }
+    }
+
+    /// Change the image of the sprite, it's resolved again on the next [`Sprite::resolve`]
+    ///
+    /// Assigning `image` directly keeps drawing the previously resolved image.
+    pub fn set_image(&mut self, image: &str) {
+        if self.image != image {
+            self.image.clear();
+            self.image.push_str(image);
+            self.resolved = None;
+            self.keep_alive = None;
+        }
+    }
+
+    /// The image resolved by [`Sprite::resolve`], if any
+    pub fn resolved(&self) -> Option<&SpriteImage> {
+        self.resolved.as_ref()
+    }
+
+    /// Resolve `image` to a handle once it's in the resource manager, or again if the image was
+    /// removed since. Until then the name is looked up on every draw
+    ///
+    /// The resolved image is kept loaded while the sprite exists, so it's unloaded once no
+    /// sprite references it, unless it's kept by an asset group or by the manifest.
+    pub fn resolve(&mut self, manager: &ResourceManager) -> Result<(), ResourceError> {
+        if self.resolved.as_ref().is_some_and(|resolved| resolved.is_current(manager)) || self.image.is_empty() {
+            return Ok(());
+        }
+        let resolved = SpriteImage::resolve(manager, &self.image)?;
+        self.keep_alive = resolved.as_ref().map(|resolved| resolved.keep_alive(manager)).transpose()?;
+        self.resolved = resolved;
+        Ok(())
+    }
+
+    /// Draw an image already resolved to a handle, whoever resolved it keeps it loaded
+    ///
+    /// Unlike [`Sprite::resolve`], the image is drawn as is, meant for sprites made every frame
+    /// such as the tiles of a tilemap.
+    pub fn with_resolved(mut self, image: SpriteImage) -> Self {
+        self.resolved = Some(image);
+        self.keep_alive = None;
+        self
+    }
+
+    /// Draw only a part of the image
+    ///
+    /// # Arguments
+    /// * `source`: (x, y, w, h) rectangle in pixels
+    pub fn with_source(mut self, source: (f32, f32, f32, f32)) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Stretch the sprite to a size in pixels, before scaling
+    pub fn with_size(mut self, size: (f32, f32)) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Set the color and alpha modulation of the sprite
+    pub fn with_tint(mut self, tint: (u8, u8, u8), alpha: u8) -> Self {
+        self.tint = tint;
+        self.alpha = alpha;
+        self
+    }
+
+    /// Mirror the sprite
+    pub fn with_flip(mut self, horizontal: bool, vertical: bool) -> Self {
+        self.flip_horizontal = horizontal;
+        self.flip_vertical = vertical;
+        self
+    }
+
+    /// Set the pivot of the sprite, relative to its size
+    pub fn with_origin(mut self, origin: (f32, f32)) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Move the sprite to another render layer
+    ///
+    /// # Arguments
+    /// * `layer`: name of the render layer
+    /// * `z`: order inside the render layer, higher is drawn later
+    pub fn with_layer(mut self, layer: &str, z: i32) -> Self {
+        self.layer = layer.to_string();
+        self.z = z;
+        self
+    }
 }
 
 impl Transform {
@@ -160,14 +506,16 @@ where
                 is_resizable: false,
                 is_fullscreen: false,
                 is_centered: false,
+                is_headless: false,
             },
+            text_cache: TextCacheConfig::default(),
         }
     }
 
     /// Set the resource manager
     pub fn resource_manager(
         mut self,
-        resource_manager: Arc<Mutex<ResourceManager>>,
+        resource_manager: SharedResourceManager,
     ) -> GraphicsBuilder<F> {
         self.resource_manager = Some(resource_manager);
         self
@@ -212,6 +560,18 @@ where
         self
     }
 
+    /// Will rendering happen offscreen, without a display?
+    pub fn headless(mut self, headless: bool) -> GraphicsBuilder<F> {
+        self.config.is_headless = headless;
+        self
+    }
+
+    /// Configure the text cache
+    pub fn text_cache(mut self, config: TextCacheConfig) -> GraphicsBuilder<F> {
+        self.text_cache = config;
+        self
+    }
+
     /// Build `Graphics`
     ///
     /// # Panic
@@ -227,13 +587,15 @@ where
             None => return Err(anyhow::anyhow!("no initializer supplied")),
         };
 
-        Graphics::new(
+        let mut graphics = Graphics::new(
             self.name,
             self.dimensions,
             resource_manager,
             initializer,
             self.config,
-        )
+        )?;
+        graphics.text_cache.set_config(self.text_cache);
+        Ok(graphics)
     }
 }
 
@@ -266,13 +628,17 @@ impl Graphics {
     pub fn new<F>(
         name: String,
         dimensions: (u32, u32),
-        resource_manager: Arc<Mutex<ResourceManager>>,
+        resource_manager: SharedResourceManager,
         resource_initialization: F,
         config: WindowConfig,
     ) -> anyhow::Result<Graphics>
     where
         F: Fn(&mut Graphics) -> anyhow::Result<()>,
     {
+        if config.is_headless {
+            // must be set before the video subsystem is initialized
+            sdl3::hint::set("SDL_VIDEO_DRIVER", "dummy");
+        }
         let sdl_context = sdl3::init()?;
         let ttf_context = sdl3::ttf::init()?;
         let video_subsystem = sdl_context.video()?;
@@ -295,16 +661,25 @@ impl Graphics {
             &mut builder
         };
 
-        let window = builder.build()?;
-
-        let canvas = window.into_canvas();
-        let texture_creator = canvas.texture_creator();
+        let canvas = if config.is_headless {
+            let window = builder.hidden().build()?;
+            sdl3::render::create_renderer(window, Some(c"software"))?
+        } else {
+            builder.build()?.into_canvas()
+        };
+        let texture_creator = Rc::new(canvas.texture_creator());
         let mut graphics = Graphics {
             canvas,
             sdl_context,
-            texture_creator: Rc::new(texture_creator),
+            texture_creator: texture_creator.clone(),
             ttf_context,
+            text_cache: TextCache::default(),
+            loader: Loader::default(),
+            gpu: GpuResources::new(texture_creator),
+            asset_loaders: AssetLoaders::default(),
+            catalog: resources::Catalog::default(),
             resource_manager,
+            drawn_by_name: HashMap::new(),
         };
 
         resource_initialization(&mut graphics)?;
@@ -312,6 +687,133 @@ impl Graphics {
         Ok(graphics)
     }
 
+    /// Shared reference to the [`ResourceManager`]
+    pub fn resource_manager(&self) -> SharedResourceManager {
+        self.resource_manager.clone()
+    }
+
+    /// Create the textures of the assets decoded in the background, for up to `budget`, log the
+    /// assets that failed to load, remove the released assets nothing references anymore, and
+    /// drop the textures and fonts of the assets removed from the [`ResourceManager`]
+    ///
+    /// Images drawn by path are released, they're removed once no sprite resolved to them and
+    /// they weren't drawn since the previous call.
+    pub fn poll_assets(&mut self, budget: Duration) {
+        let mut manager = self.resource_manager.write();
+        if self.loader.is_loading() {
+            let failures = self.loader.poll(&mut manager, &mut self.gpu, budget);
+            resources::log_failures(&failures);
+        }
+        for name in manager.collect_unused() {
+            debug!("unloading asset '{name}'");
+        }
+        self.drawn_by_name.clear();
+        self.gpu.prune(&manager);
+    }
+
+    /// Clear the canvas and draw camera views into their viewports or render targets, without
+    /// presenting the canvas
+    ///
+    /// Drawables that fail to draw are logged and skipped. Consecutive sprites sharing a texture are
+    /// drawn as one batch, and sprites outside of their viewport are skipped.
+    pub fn render_views(&mut self, views: &[CameraView]) {
+        self.canvas.clear();
+        for view in views {
+            if let Some(target) = &view.target {
+                if let Err(e) = self.bind_target(target) {
+                    error!("failed to draw into render target {}: {e:#}", target.name);
+                    continue;
+                }
+            }
+            let (x, y, w, h) = view.viewport;
+            self.canvas.set_viewport(Rect::new(x as i32, y as i32, w as u32, h as u32));
+            self.draw_view(view);
+            if view.target.is_some() {
+                self.canvas.set_viewport(None);
+                self.unbind_target();
+            }
+        }
+        self.canvas.set_viewport(None);
+    }
+
+    /// Make the texture of a render target the destination of drawing, creating the texture if
+    /// it doesn't exist or has another size, and clear it if the target has a clear color
+    fn bind_target(&mut self, target: &RenderTarget) -> anyhow::Result<()> {
+        let image = self.target_texture(target)?;
+        let texture = image.texture.borrow();
+        // sdl3 only exposes render targets through a closure borrowing the canvas, which would
+        // keep the drawing methods of `Graphics` out of reach
+        let bound = unsafe { sdl3::sys::render::SDL_SetRenderTarget(self.canvas.raw(), texture.raw()) };
+        if !bound {
+            anyhow::bail!("{}", sdl3::get_error());
+        }
+        if let Some(color) = target.clear_color {
+            self.with_draw_color(color, |canvas| {
+                canvas.clear();
+                Ok::<(), std::convert::Infallible>(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Make the window the destination of drawing again
+    fn unbind_target(&mut self) {
+        unsafe {
+            sdl3::sys::render::SDL_SetRenderTarget(self.canvas.raw(), std::ptr::null_mut());
+        }
+    }
+
+    /// Get the texture of a render target, (re)creating it if needed. The target is registered
+    /// as an [`Image`] in the resource manager, so sprites can draw it
+    fn target_texture(&mut self, target: &RenderTarget) -> anyhow::Result<Rc<InnerImage>> {
+        if let Some(image) = self.gpu.image(&target.name) {
+            if (image.width(), image.height()) == target.size {
+                return Ok(image);
+            }
+        }
+
+        let (width, height) = target.size;
+        let mut texture = unsafe {
+            std::mem::transmute::<Texture<'_>, Texture<'static>>(
+                self.texture_creator.create_texture_target(PixelFormat::RGBA32, width, height)?,
+            )
+        };
+        texture.set_blend_mode(BlendMode::Blend);
+        let image = self.gpu.insert_texture(&target.name, texture, width, height);
+        self.resource_manager.write().insert(Image::new(target.name.clone(), width, height, ImageOptions::default()));
+        Ok(image)
+    }
+
+    /// Draw any [`Drawable`] on the canvas
+    pub fn draw(&mut self, drawable: &Drawable) -> anyhow::Result<()> {
+        match drawable {
+            Drawable::Image(sprite) => self.draw_sprite(sprite).context("failed to draw image"),
+            Drawable::Text { font, position, text, color, size, layout, .. } => self
+                .draw_text_layout(text, *position, font, *color, *size, layout)
+                .context("failed to draw text"),
+            Drawable::Rect { w, h, x, y, color, filled, thickness, .. } => self
+                .draw_colored_rect((*x, *y, *w, *h), *color, *filled, *thickness)
+                .context("failed to draw rectangle"),
+            Drawable::Circle { center, radius, color, filled, thickness, .. } => self
+                .draw_circle(*center, *radius, *color, *filled, *thickness)
+                .context("failed to draw circle"),
+            Drawable::Line { from, to, color, thickness, .. } => self
+                .draw_polyline(&[*from, *to], false, *color, *thickness)
+                .context("failed to draw line"),
+            Drawable::Polyline { points, closed, color, thickness, .. } => self
+                .draw_polyline(points, *closed, *color, *thickness)
+                .context("failed to draw polyline"),
+            Drawable::Polygon { points, color, filled, thickness, .. } => {
+                let result = if *filled {
+                    self.draw_polygon(points, *color)
+                } else {
+                    self.draw_polyline(points, true, *color, *thickness)
+                };
+                result.context("failed to draw polygon")
+            }
+        }
+    }
+
     /// Draw a rectangle on position (x, y) with dimensions (w, h)
     pub fn draw_rect(&mut self, width: f32, height: f32, x: f32, y: f32) -> anyhow::Result<()> {
         self.canvas.draw_rect(FRect {
@@ -323,12 +825,97 @@ impl Graphics {
         Ok(())
     }
 
-    /// Draw a rectangle on position (x, y) with dimensions (w, h)
+    /// Draw a line from `p1` to `p2`
     pub fn draw_line(&mut self, p1: (f32, f32), p2: (f32, f32)) -> anyhow::Result<()> {
         self.canvas.draw_line(p1, p2)?;
         Ok(())
     }
 
+    /// Draw a filled or outlined rectangle on position (x, y) with dimensions (w, h) in an RGBA color
+    pub fn draw_colored_rect(
+        &mut self,
+        rect: (f32, f32, f32, f32),
+        color: (u8, u8, u8, u8),
+        filled: bool,
+        thickness: f32,
+    ) -> anyhow::Result<()> {
+        let (x, y, w, h) = rect;
+        if filled {
+            return self.with_draw_color(color, |canvas| canvas.fill_rect(FRect { x, y, w, h }));
+        }
+        self.draw_polyline(&shapes::rect_points(x, y, w, h), true, color, thickness)
+    }
+
+    /// Draw a filled or outlined circle in an RGBA color
+    pub fn draw_circle(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        color: (u8, u8, u8, u8),
+        filled: bool,
+        thickness: f32,
+    ) -> anyhow::Result<()> {
+        let points = shapes::circle_points(center, radius);
+        if filled {
+            return self.draw_polygon(&points, color);
+        }
+        self.draw_polyline(&points, true, color, thickness)
+    }
+
+    /// Draw connected line segments through `points` in an RGBA color
+    ///
+    /// A `closed` polyline also connects the last point to the first one. Lines up to 1 pixel
+    /// thick are drawn by the renderer's line drawing, thicker ones are triangulated.
+    pub fn draw_polyline(
+        &mut self,
+        points: &[(f32, f32)],
+        closed: bool,
+        color: (u8, u8, u8, u8),
+        thickness: f32,
+    ) -> anyhow::Result<()> {
+        if thickness > 1.0 {
+            return self.fill_triangles(&shapes::thick_polyline(points, closed, thickness), color);
+        }
+        let mut points: Vec<FPoint> = points.iter().map(|&(x, y)| FPoint::new(x, y)).collect();
+        if let (true, Some(&first)) = (closed, points.first()) {
+            points.push(first);
+        }
+        self.with_draw_color(color, |canvas| canvas.draw_lines(points.as_slice()))
+    }
+
+    /// Draw a filled polygon as a triangle fan around its first point in an RGBA color
+    pub fn draw_polygon(&mut self, points: &[(f32, f32)], color: (u8, u8, u8, u8)) -> anyhow::Result<()> {
+        self.fill_triangles(&shapes::triangle_fan(points), color)
+    }
+
+    /// Draw a triangle list in an RGBA color
+    fn fill_triangles(&mut self, triangles: &[(f32, f32)], color: (u8, u8, u8, u8)) -> anyhow::Result<()> {
+        let color = FColor::from(Color::RGBA(color.0, color.1, color.2, color.3));
+        let vertices: Vec<Vertex> = triangles
+            .iter()
+            .map(|&(x, y)| Vertex { position: FPoint::new(x, y), color, tex_coord: FPoint::new(0.0, 0.0) })
+            .collect();
+        self.canvas.render_geometry(&vertices, None, VertexIndices::Sequential)?;
+        Ok(())
+    }
+
+    /// Run `draw` with the canvas draw color set to `color`, the previous draw color and blend mode
+    /// are restored afterward as the draw color is also used to clear the canvas
+    fn with_draw_color<E: std::error::Error + Send + Sync + 'static>(
+        &mut self,
+        color: (u8, u8, u8, u8),
+        draw: impl FnOnce(&mut Canvas<Window>) -> Result<(), E>,
+    ) -> anyhow::Result<()> {
+        let previous_color = self.canvas.draw_color();
+        let previous_blend_mode = self.canvas.blend_mode();
+        self.canvas.set_draw_color(Color::RGBA(color.0, color.1, color.2, color.3));
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        let result = draw(&mut self.canvas);
+        self.canvas.set_draw_color(previous_color);
+        self.canvas.set_blend_mode(previous_blend_mode);
+        Ok(result?)
+    }
+
     /// Cache an image if it isn't cached and draw it on the canvas
     ///
     /// # Returns
@@ -347,43 +934,168 @@ impl Graphics {
         flip_horizontal: bool,
         flip_vertical: bool,
     ) -> anyhow::Result<()> {
-        let manager = self.resource_manager.clone();
+        let sprite = Sprite::new(path, Transform::new(position, 1.0, rotation), true)
+            .with_flip(flip_horizontal, flip_vertical);
+        self.draw_sprite(&sprite)
+    }
 
-        let mut manager = match manager.try_lock() {
-            Ok(guard) => guard,
-            Err(e) => return Err(anyhow::anyhow!("failed to lock resource_manager: {}", e)),
+    /// Cache the sprite's image if it isn't cached and draw it on the canvas, honoring the
+    /// sprite's scale, source rectangle, tint, alpha, flip flags and origin
+    ///
+    /// If the sprite's image is a `"sheet:frame"` reference, the frame of the sprite sheet is
+    /// drawn and the sprite's `source` is ignored. Images are loaded in the background, nothing
+    /// is drawn until the image is loaded.
+    ///
+    /// # Returns
+    /// - `Ok(())` on success.
+    /// - `Err(Box<dyn std::error::Error>)` on failure
+    pub fn draw_sprite(&mut self, sprite: &Sprite) -> anyhow::Result<()> {
+        let shared = self.resource_manager.clone();
+        let mut manager = shared.write();
+        let Some((image, frame)) = self.sprite_image(&mut manager, sprite)? else {
+            return Ok(());
         };
-        let key = path.clone();
-        if !manager.is_cached(&key) {
-            // rust programmers when they have to .clone()
-            let texture = Image::load(PathBuf::from(path.clone()), "".to_string(), self);
-            manager.insert(texture?);
-        }
-
-        let image = manager.get(&key)?.data()
-            .downcast_ref::<Rc<InnerImage>>().context("failed to downcast image")?;
-
-        let dst_rect = FRect::new(
-            position.0,
-            position.1,
-            image.width as f32,
-            image.height as f32,
-        );
+        drop(manager);
 
+        let image_size = (image.width() as f32, image.height() as f32);
+        let (src_rect, dst_rect, pivot) = sprite_rects(sprite, image_size, frame);
+        let mut texture = image.texture.borrow_mut();
+        texture.set_color_mod(sprite.tint.0, sprite.tint.1, sprite.tint.2);
+        texture.set_alpha_mod(sprite.alpha);
         self.canvas
             .copy_ex(
-                &image.texture,
-                None,
+                &texture,
+                Some(src_rect),
                 Some(dst_rect),
-                rotation,
-                None,
-                flip_horizontal,
-                flip_vertical,
+                sprite.transform.rotation,
+                Some(pivot),
+                sprite.flip_horizontal,
+                sprite.flip_vertical,
             )?;
 
         Ok(())
     }
 
+    /// Find the texture of a sprite, by its resolved handle if it has one, else by name,
+    /// requesting the image to be loaded from a file if it isn't cached
+    ///
+    /// # Returns
+    /// The texture and, for a `"sheet:frame"` reference, the frame rectangle in the sheet. `None`
+    /// while the image is being loaded
+    fn sprite_image(
+        &mut self,
+        manager: &mut ResourceManager,
+        sprite: &Sprite,
+    ) -> anyhow::Result<Option<ResolvedImage>> {
+        if let Some(resolved) = sprite.resolved() {
+            if sprite.image.is_empty() || resolved.is_current(manager) {
+                return self.resolved_image(manager, resolved);
+            }
+        }
+        let key = sprite.image.as_str();
+        let resolved = match SpriteImage::resolve(manager, key)? {
+            Some(resolved) => resolved,
+            // a frame of a sprite sheet that isn't requested yet, such as one of a group whose
+            // request hasn't arrived, or a file that doesn't exist
+            None if SpriteImage::split_frame(key).is_some() || !vfs::exists(key) => return Ok(None),
+            None => {
+                // images drawn by path are unloaded once nothing draws them anymore
+                resources::request_image(manager, key, PathBuf::from(key), ImageOptions::default(), DEFAULT_GROUP, self);
+                manager.release(key).ok();
+                let Some(resolved) = SpriteImage::resolve(manager, key)? else { return Ok(None) };
+                resolved
+            }
+        };
+        if !self.drawn_by_name.contains_key(key) {
+            self.drawn_by_name.insert(key.to_string(), resolved.keep_alive(manager)?);
+        }
+        self.resolved_image(manager, &resolved)
+    }
+
+    /// Find the texture of a resolved sprite image
+    fn resolved_image(
+        &mut self,
+        manager: &ResourceManager,
+        resolved: &SpriteImage,
+    ) -> anyhow::Result<Option<ResolvedImage>> {
+        match resolved {
+            SpriteImage::Frame(handle, frame) => {
+                let rect = match manager.get(*handle) {
+                    Ok(sheet) => sheet.frame(frame)
+                        .with_context(|| format!("sprite sheet has no frame '{frame}'"))?,
+                    Err(ResourceError::NotLoaded) => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                };
+                Ok(self.gpu.handle_image(manager, *handle).map(|image| (image, Some(rect))))
+            }
+            SpriteImage::Image(handle) => match manager.get(*handle) {
+                Ok(_) => Ok(self.gpu.handle_image(manager, *handle).map(|image| (image, None))),
+                Err(ResourceError::NotLoaded) => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+        }
+    }
+
+    /// Draw the drawables of a view, batching consecutive sprites sharing a texture into a
+    /// single `render_geometry` call and skipping sprites outside of the view
+    ///
+    /// Images are resolved once per view rather than once per sprite.
+    fn draw_view(&mut self, view: &CameraView) {
+        let screen = (0.0, 0.0, view.viewport.2, view.viewport.3);
+        let mut images: HashMap<ImageKey, Option<ResolvedImage>> = HashMap::new();
+        let mut batch = SpriteBatch::default();
+
+        for drawable in &view.drawables {
+            let Drawable::Image(sprite) = drawable else {
+                self.flush_batch(&mut batch);
+                self.draw(drawable).unwrap_or_else(|e| error!("{e:#}"));
+                continue;
+            };
+            let key = match sprite.resolved() {
+                Some(resolved) => ImageKey::Resolved(resolved),
+                None => ImageKey::Name(&sprite.image),
+            };
+            if !images.contains_key(&key) {
+                // the lock is only held while resolving, so ECS systems can read in between
+                let shared = self.resource_manager.clone();
+                let resolved = self.sprite_image(&mut shared.write(), sprite);
+                let resolved = resolved
+                    .map_err(|e| error!("failed to draw image {}: {e:#}", sprite.image))
+                    .ok()
+                    .flatten();
+                images.insert(key.clone(), resolved);
+            }
+            let Some(Some((image, frame))) = images.get(&key) else { continue };
+
+            let image_size = (image.width() as f32, image.height() as f32);
+            let (src, dst, pivot) = sprite_rects(sprite, image_size, *frame);
+            let pivot = (dst.x + pivot.x, dst.y + pivot.y);
+            let corners = shapes::rotated_rect((dst.x, dst.y, dst.w, dst.h), pivot, sprite.transform.rotation);
+            if !shapes::bounding_box(corners).is_some_and(|bounds| shapes::overlaps(bounds, screen)) {
+                continue;
+            }
+            if !batch.image.as_ref().is_some_and(|batched| Rc::ptr_eq(batched, image)) {
+                self.flush_batch(&mut batch);
+                batch.image = Some(image.clone());
+            }
+            batch.push(sprite, corners, src, image_size);
+        }
+        self.flush_batch(&mut batch);
+    }
+
+    /// Draw the batched sprites and empty the batch
+    fn flush_batch(&mut self, batch: &mut SpriteBatch) {
+        let Some(image) = batch.image.take() else { return };
+        let mut texture = image.texture.borrow_mut();
+        // tint and alpha are in the vertex colors
+        texture.set_color_mod(255, 255, 255);
+        texture.set_alpha_mod(255);
+        self.canvas
+            .render_geometry(&batch.vertices, Some(&texture), VertexIndices::Sequential)
+            .unwrap_or_else(|e| error!("failed to draw a sprite batch: {e}"));
+        batch.vertices.clear();
+    }
+
     /// Create a texture from font + text and render it on the canvas
     pub fn draw_text(
         &mut self,
@@ -393,65 +1105,220 @@ impl Graphics {
         color: (u8, u8, u8),
         size: f32,
     ) -> anyhow::Result<()> {
-        let color = Color::RGB(color.0, color.1, color.2);
-        let manager = self.resource_manager.clone();
+        self.draw_text_layout(&text, position, &font, color, size, &TextLayout::default())
+    }
 
-        let mut manager = match manager.try_lock() {
-            Ok(guard) => guard,
-            Err(e) => return Err(anyhow::anyhow!("failed to lock resource_manager: {}", e)),
-        };
+    /// Draw a text broken into lines and decorated according to `layout`
+    ///
+    /// `position` is the top-left corner of the text block.
+    pub fn draw_text_layout(
+        &mut self,
+        text: &str,
+        position: (f32, f32),
+        font: &str,
+        color: (u8, u8, u8),
+        size: f32,
+        layout: &TextLayout,
+    ) -> anyhow::Result<()> {
+        let shared = self.resource_manager.clone();
+        let (font_key, font) = self.sized_font(&shared.read(), font, size)?;
+        let (lines, _) = layout_lines(&font, text, layout)?;
 
-        // dumbass solution. either way, i see no other solution to this.
-        // as sdl3 requires us to create a texture from font to draw text,
-        // we will be caching it as an [`resources::image::Image`] under this key
-        let cache_key = format!(
-            "{}|{}|{}|{:x?}",
-            font,
-            text,
-            size,
-            color.to_u32(&PixelFormat::RGBA32)
-        );
-        let font: &InternalDummyFont = {
-            manager.get(&font)?.data()
-                .downcast_ref::<InternalDummyFont>().context("failed to downcast font")?
+        // effects are drawn below the text itself, the outline is faked by drawing the text
+        // shifted in 8 directions
+        let mut passes = Vec::new();
+        if let Some(shadow) = layout.shadow {
+            passes.push(shadow);
+        }
+        if let Some(outline) = layout.outline {
+            for dx in [-1.0, 0.0, 1.0] {
+                for dy in [-1.0, 0.0, 1.0] {
+                    if (dx, dy) != (0.0, 0.0) {
+                        let offset = (dx * outline.thickness, dy * outline.thickness);
+                        passes.push(TextShadow { offset, color: outline.color });
+                    }
+                }
+            }
+        }
+        passes.push(TextShadow { offset: (0.0, 0.0), color });
+
+        for TextShadow { offset, color } in passes {
+            for (line, (x, y)) in &lines {
+                let line_position = (position.0 + x + offset.0, position.1 + y + offset.1);
+                self.draw_text_line(&font_key, &font, line, line_position, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Measure the size of a text drawn with [`Graphics::draw_text`]. Systems measure text with
+    /// [`crate::text::measure_text`] instead
+    pub fn measure_text(&mut self, font: &str, size: f32, text: &str) -> anyhow::Result<(f32, f32)> {
+        self.measure_text_layout(font, size, text, &TextLayout::default())
+    }
+
+    /// Measure the size of the text block drawn with [`Graphics::draw_text_layout`], not counting
+    /// the outline and the shadow
+    pub fn measure_text_layout(
+        &mut self,
+        font: &str,
+        size: f32,
+        text: &str,
+        layout: &TextLayout,
+    ) -> anyhow::Result<(f32, f32)> {
+        let shared = self.resource_manager.clone();
+        let (_, font) = self.sized_font(&shared.read(), font, size)?;
+        Ok(layout_lines(&font, text, layout)?.1)
+    }
+
+    /// Get the font registered as `font` opened in `size`, opening it if it isn't yet. A size
+    /// of 0 stands for the default size of the font from the manifest
+    fn sized_font(
+        &mut self,
+        manager: &ResourceManager,
+        font: &str,
+        size: f32,
+    ) -> anyhow::Result<(String, Rc<InternalFont>)> {
+        let font: &InternalDummyFont = manager.get(manager.handle::<DummyFont>(font)?)?.inner();
+        let size = match font.options.size {
+            Some(default_size) if size <= 0.0 => default_size,
+            _ => size,
         };
 
         let font_key = format!("{}|{}", font.name, size);
 
-        if !manager.is_cached(&font_key.clone()) {
-            let asset = Font::load(font.path.clone(), font_key.clone(), self, Some(size));
-            manager.insert(asset?);
-        }
-        
-        if !manager.is_cached(&cache_key.clone()) {
-            let font = manager.get(&font_key)?.data()
-                .downcast_ref::<Rc<InternalFont>>().context("failed to downcast font")?;
-            let surface = font.buffer
-                .render(&text)
-                .blended(color)
-                .map_err(|e| anyhow::anyhow!("render error: {e}"))?;
-            let image = Image::load_from_surface(cache_key.clone(), self, surface);
-            manager.insert(image?);
-        }
-        let texture = manager.get(&cache_key)?.data()
-            .downcast_ref::<Rc<InnerImage>>().context("failed to downcast image")?;
-
-        let dst_rect = FRect::new(
-            position.0,
-            position.1,
-            texture.width as f32,
-            texture.height as f32,
-        );
-        self.canvas
-            .copy_ex(
-                &texture.texture,
-                None,
-                Some(dst_rect),
-                0.0,
-                None,
-                false,
-                false,
-            )?;
+        if let Some(opened) = self.gpu.font(&font_key) {
+            return Ok((font_key, opened));
+        }
+        // the sizes live as long as the font they're opened from, see `GpuResources::prune`
+        let opened = InternalFont::load(font.path.clone(), &self.ttf_context, size, font.options.hinting)?;
+        Ok((font_key.clone(), self.gpu.insert_font(font_key, opened)))
+    }
+
+    /// Render a single line of text with its top-left corner at `position` through the
+    /// [`TextCache`]
+    fn draw_text_line(
+        &mut self,
+        font_key: &str,
+        font: &InternalFont,
+        text: &str,
+        position: (f32, f32),
+        color: (u8, u8, u8),
+    ) -> anyhow::Result<()> {
+        // sdl3_ttf refuses to render empty strings
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        match self.text_cache.config().mode {
+            TextCacheMode::Strings => {
+                let image = self.text_cache.string_texture(&self.texture_creator, font, font_key, text)?;
+                let mut texture = image.texture.borrow_mut();
+                texture.set_color_mod(color.0, color.1, color.2);
+                texture.set_alpha_mod(255);
+                let dst_rect = FRect::new(position.0, position.1, image.width() as f32, image.height() as f32);
+                self.canvas.copy(&texture, None, Some(dst_rect))?;
+            }
+            TextCacheMode::Glyphs => {
+                let (texture, glyphs) = self.text_cache.glyphs(&self.texture_creator, font, font_key, text)?;
+                let mut texture = texture.borrow_mut();
+                texture.set_color_mod(color.0, color.1, color.2);
+                texture.set_alpha_mod(255);
+                let mut pen = position.0;
+                for glyph in glyphs {
+                    if let Some(rect) = glyph.rect {
+                        let dst_rect = FRect::new(pen, position.1, rect.w, rect.h);
+                        self.canvas.copy(&texture, Some(rect), Some(dst_rect))?;
+                    }
+                    pen += glyph.advance;
+                }
+            }
+        }
         Ok(())
     }
 }
+
+/// An image and, for a sprite sheet frame, the frame rectangle
+type ResolvedImage = (Rc<InnerImage>, Option<(f32, f32, f32, f32)>);
+
+/// Image of a sprite in a view: the handle it was resolved to, else its name
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum ImageKey<'a> {
+    Resolved(&'a SpriteImage),
+    Name(&'a str),
+}
+
+/// Sprites sharing a texture, as textured triangles
+#[derive(Default)]
+pub(crate) struct SpriteBatch {
+    image: Option<Rc<InnerImage>>,
+    pub(crate) vertices: Vec<Vertex>,
+}
+
+impl SpriteBatch {
+    /// Add the two triangles of a sprite
+    ///
+    /// # Arguments
+    /// * `corners`: destination corners, clockwise from the unrotated top-left one
+    /// * `src`: source rectangle in the texture
+    /// * `texture_size`: size of the texture, to normalize texture coordinates
+    pub(crate) fn push(&mut self, sprite: &Sprite, corners: [(f32, f32); 4], src: FRect, texture_size: (f32, f32)) {
+        let (mut u0, mut u1) = (src.x / texture_size.0, (src.x + src.w) / texture_size.0);
+        let (mut v0, mut v1) = (src.y / texture_size.1, (src.y + src.h) / texture_size.1);
+        if sprite.flip_horizontal {
+            std::mem::swap(&mut u0, &mut u1);
+        }
+        if sprite.flip_vertical {
+            std::mem::swap(&mut v0, &mut v1);
+        }
+        let color = FColor {
+            r: sprite.tint.0 as f32 / 255.0,
+            g: sprite.tint.1 as f32 / 255.0,
+            b: sprite.tint.2 as f32 / 255.0,
+            a: sprite.alpha as f32 / 255.0,
+        };
+        let tex_coords = [(u0, v0), (u1, v0), (u1, v1), (u0, v1)];
+        self.vertices.extend([0, 1, 2, 0, 2, 3].map(|corner| Vertex {
+            position: FPoint::new(corners[corner].0, corners[corner].1),
+            color,
+            tex_coord: FPoint::new(tex_coords[corner].0, tex_coords[corner].1),
+        }));
+    }
+}
+
+/// Source rectangle, destination rectangle and rotation pivot (relative to the destination)
+/// of a sprite, honoring its frame or source, size, scale and origin
+pub(crate) fn sprite_rects(
+    sprite: &Sprite,
+    image_size: (f32, f32),
+    frame: Option<(f32, f32, f32, f32)>,
+) -> (FRect, FRect, FPoint) {
+    let (src_x, src_y, src_w, src_h) = frame.or(sprite.source).unwrap_or((0.0, 0.0, image_size.0, image_size.1));
+    let scale = sprite.transform.scale as f32;
+    let (width, height) = sprite.size.unwrap_or((src_w, src_h));
+    let (width, height) = (width * scale, height * scale);
+    let pivot = FPoint::new(sprite.origin.0 * width, sprite.origin.1 * height);
+    let dst = FRect::new(
+        sprite.transform.position.0 - pivot.x,
+        sprite.transform.position.1 - pivot.y,
+        width,
+        height,
+    );
+    (FRect::new(src_x, src_y, src_w, src_h), dst, pivot)
+}
+
+/// Break a text into lines positioned relative to the top-left corner of the text block
+fn layout_lines(
+    font: &InternalFont,
+    text: &str,
+    layout: &TextLayout,
+) -> anyhow::Result<(PositionedLines, (f32, f32))> {
+    let measure = |line: &str| -> anyhow::Result<f32> {
+        if line.is_empty() {
+            return Ok(0.0);
+        }
+        Ok(font.buffer.size_of(line)?.0 as f32)
+    };
+    let height = font.buffer.height() as f32;
+    layout_block(text, layout, height, font.buffer.recommended_line_spacing() as f32, measure)
+}
@@ -10,11 +10,18 @@ pub mod graphics;
 pub mod hooks;
 /// Resource management
 pub mod resources;
+/// Screenshots and golden-image comparison
+pub mod screenshot;
 /// The graphics plugin
 pub mod plugin;
-
-unsafe impl Send for Window {}
-unsafe impl Sync for Window {}
+/// Triangulation of primitive shapes
+pub mod shapes;
+/// Layout of multi-line text
+pub mod text;
+/// Bounded cache of rendered text
+pub mod text_cache;
+#[cfg(test)]
+mod tests;
 
 /// Main window struct
 ///
@@ -0,0 +1,144 @@
+//! Debug overlay drawn with the canvas on top of every camera
+//!
+//! ImGui can't share the canvas with the rest of the rendering (see the `hooks` example), so the
+//! overlay is made of plain [`Drawable`]s instead. Systems fill windows of text lines by title:
+//!
+//! ```ignore
+//! fn run(&mut self, (mut overlay, positions): Self::SystemData) {
+//!     if let Some(overlay) = overlay.as_mut() {
+//!         overlay.window("player").line("position", format!("{:?}", positions.player));
+//!     }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use crate::graphics::Drawable;
+use crate::text::TextLayout;
+
+/// Name of the render layer the overlay is drawn on
+pub const DEBUG_LAYER: &str = "debug";
+
+/// Space between the window border and its text, and between windows
+const PADDING: f32 = 6.0;
+/// Width of a character relative to the font size, the overlay can't measure text on the ECS side
+/// so it assumes a monospace font
+const CHAR_WIDTH: f32 = 0.6;
+
+/// A window of the [`DebugOverlay`]
+#[derive(Debug, Default, Clone)]
+pub struct DebugWindow {
+	/// Text lines of the window
+	pub lines: Vec<String>,
+}
+
+impl DebugWindow {
+	/// Add a line of text
+	pub fn text(&mut self, text: impl Into<String>) -> &mut Self {
+		self.lines.push(text.into());
+		self
+	}
+
+	/// Add a `label: value` line
+	pub fn line(&mut self, label: &str, value: impl std::fmt::Display) -> &mut Self {
+		self.lines.push(format!("{label}: {value}"));
+		self
+	}
+}
+
+/// Debug overlay resource, inserted by [`crate::plugin::GraphicsPlugin::with_debug_overlay`]
+///
+/// Windows are kept until they are submitted again or removed, so the order in which systems
+/// run doesn't matter. They are stacked from the top-left corner of the window, sorted by title.
+#[derive(Debug, Clone)]
+pub struct DebugOverlay {
+	/// Is the overlay drawn?
+	pub visible: bool,
+	/// Font name registered in the resource manager
+	pub font: String,
+	/// Font size
+	pub font_size: f32,
+	windows: BTreeMap<String, DebugWindow>,
+}
+
+impl DebugOverlay {
+	/// Create a visible overlay without windows
+	pub fn new(font: &str, font_size: f32) -> Self {
+		Self {
+			visible: true,
+			font: font.to_string(),
+			font_size,
+			windows: BTreeMap::new(),
+		}
+	}
+
+	/// Start (re)filling a window: the window is created if it doesn't exist, and its lines are
+	/// cleared otherwise
+	pub fn window(&mut self, title: &str) -> &mut DebugWindow {
+		let window = self.windows.entry(title.to_string()).or_default();
+		window.lines.clear();
+		window
+	}
+
+	/// Remove a window
+	pub fn remove(&mut self, title: &str) -> Option<DebugWindow> {
+		self.windows.remove(title)
+	}
+
+	/// Titles of the windows
+	pub fn titles(&self) -> impl Iterator<Item = &str> {
+		self.windows.keys().map(String::as_str)
+	}
+
+	/// Get a window
+	pub fn get(&self, title: &str) -> Option<&DebugWindow> {
+		self.windows.get(title)
+	}
+
+	/// Drawables of the overlay in window coordinates, empty if the overlay is hidden
+	pub fn drawables(&self) -> Vec<Drawable> {
+		if !self.visible {
+			return Vec::new();
+		}
+
+		let line_height = self.font_size * 1.2;
+		let mut drawables = Vec::new();
+		let mut y = PADDING;
+		for (title, window) in &self.windows {
+			let widest = window.lines
+				.iter()
+				.chain(std::iter::once(title))
+				.map(|line| line.chars().count())
+				.max()
+				.unwrap_or(0);
+			let w = widest as f32 * self.font_size * CHAR_WIDTH + PADDING * 2.0;
+			let h = (window.lines.len() + 1) as f32 * line_height + PADDING * 2.0;
+
+			drawables.push(Drawable::Rect {
+				w,
+				h,
+				x: PADDING,
+				y,
+				color: (16, 16, 16, 200),
+				filled: true,
+				thickness: 1.0,
+				layer: DEBUG_LAYER.to_string(),
+				z: 0,
+			});
+			let lines = std::iter::once((title, (255, 220, 90))).chain(window.lines.iter().map(|line| (line, (230, 230, 230))));
+			for (index, (line, color)) in lines.enumerate() {
+				drawables.push(Drawable::Text {
+					font: self.font.clone(),
+					position: (PADDING * 2.0, y + PADDING + index as f32 * line_height),
+					text: line.clone(),
+					color,
+					size: self.font_size,
+					layout: TextLayout::default(),
+					layer: DEBUG_LAYER.to_string(),
+					z: 1,
+				});
+			}
+			y += h + PADDING;
+		}
+		drawables
+	}
+}
@@ -1,21 +1,105 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use kanal::{Receiver, Sender};
-use log::error;
-use crate::events::{KeyboardEvent, MouseClickEvent, MouseMotionEvent, MouseWheelEvent, WindowEventHandler};
-use crate::graphics::Drawable;
-use crate::Window;
+use log::{error, info};
+use sdl3::keyboard::Keycode;
+use crate::events::{KeyboardEvent, MouseClickEvent, MouseMotionEvent, MouseWheelEvent, TextInputEvent, WindowEventHandler};
+use crate::plugin::system::{AssetReport, AssetRequest, CameraView, TextInputState, WindowSize};
+use crate::{resources, Window};
 
+/// Time spent creating textures of loaded assets per frame, so loading doesn't stall the frame
+const ASSET_BUDGET: Duration = Duration::from_millis(4);
+
+/// Window events forwarded to the ECS, gathered into a `Vec<PluginEvent>` resource every tick
+///
+/// Presses and releases are separate: `KeyboardEvent` and `MouseClickEvent` used to be sent for
+/// releases as well, systems reacting to both must now also match `KeyUpEvent` and
+/// `MouseButtonUpEvent`.
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum PluginEvent {
+	/// A key was pressed
 	KeyboardEvent(KeyboardEvent),
+	/// A key was released
+	KeyUpEvent(KeyboardEvent),
 	MouseMotionEvent(MouseMotionEvent),
+	/// A mouse button was pressed
 	MouseClickEvent(MouseClickEvent),
+	/// A mouse button was released
+	MouseButtonUpEvent(MouseClickEvent),
 	MouseWheelEvent(MouseWheelEvent),
+	/// Text was typed
+	TextInputEvent(TextInputEvent),
 }
 
 pub(crate) struct EventHandler {
-	pub(crate) render_receiver: Receiver<Vec<Drawable>>,
+	pub(crate) render_receiver: Receiver<Vec<CameraView>>,
 	pub(crate) event_sender: Sender<PluginEvent>,
+	pub(crate) screenshot_receiver: Receiver<PathBuf>,
+	/// Key saving a screenshot into the directory
+	pub(crate) screenshot_key: Option<(Keycode, PathBuf)>,
+	/// Screenshots to take on the next drawn frame
+	pub(crate) pending_screenshots: Vec<PathBuf>,
+	pub(crate) asset_receiver: Receiver<AssetRequest>,
+	pub(crate) report_sender: Sender<AssetReport>,
+	/// Last report sent to the ECS, to only send changes
+	pub(crate) last_report: Option<AssetReport>,
+	/// Number of asset requests handled so far
+	pub(crate) handled_requests: u64,
+	/// Interval between checks for changed asset files, and the time of the last check
+	pub(crate) hot_reload: Option<(Duration, Instant)>,
+	/// Size of the window shared with the ECS
+	pub(crate) window_size: WindowSize,
+	/// Is text input wanted by the ECS?
+	pub(crate) text_input: TextInputState,
+	/// Is text input started on the window?
+	pub(crate) text_input_started: bool,
+}
+
+impl EventHandler {
+	/// Handle group requests, finish loaded assets and report the progress and memory usage
+	fn update_assets(&mut self, window: &mut Window) -> anyhow::Result<()> {
+		let graphics = &mut window.graphics;
+		let manager = graphics.resource_manager();
+		while let Ok(Some(request)) = self.asset_receiver.try_recv() {
+			let mut manager = manager.write();
+			match request {
+				AssetRequest::LoadGroup(group) => resources::queue_group(&mut manager, &group, graphics)
+					.unwrap_or_else(|e| error!("failed to load asset group: {e:#}")),
+				AssetRequest::UnloadGroup(group) => resources::unload_group(&mut manager, &group, graphics),
+			}
+			self.handled_requests += 1;
+		}
+
+		if let Some((interval, last_check)) = &mut self.hot_reload {
+			if last_check.elapsed() >= *interval {
+				*last_check = Instant::now();
+				resources::reload_changed(&mut manager.write(), graphics)?;
+			}
+		}
+
+		resources::queue_waiting(&mut manager.write(), graphics);
+		graphics.poll_assets(ASSET_BUDGET);
+		let report = AssetReport {
+			groups: resources::progress(graphics),
+			memory: manager.read().memory_report(),
+			handled: self.handled_requests,
+		};
+		if self.last_report.as_ref() != Some(&report) {
+			self.report_sender.send(report.clone())?;
+			self.last_report = Some(report);
+		}
+		Ok(())
+	}
+}
+
+/// A path in `directory` named after the current time, so screenshots never overwrite each other
+fn screenshot_path(directory: &Path) -> PathBuf {
+	let millis = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_millis())
+		.unwrap_or_default();
+	directory.join(format!("screenshot-{millis}.png"))
 }
 
 // Corpse locked in the bathroom
@@ -25,34 +109,37 @@ pub(crate) struct EventHandler {
 // Bleeding out inside my closet
 
 impl WindowEventHandler for EventHandler {
-	fn update(&mut self, _window: &mut Window) -> anyhow::Result<()> {
+	fn update(&mut self, window: &mut Window) -> anyhow::Result<()> {
+		self.update_assets(window).unwrap_or_else(|e| error!("{e:#}"));
+		if let Ok(size) = window.graphics.canvas.output_size() {
+			self.window_size.set(size);
+		}
+		if self.text_input.is_active() != self.text_input_started {
+			self.text_input_started = !self.text_input_started;
+			let sdl_window = window.graphics.canvas.window();
+			let text_input = sdl_window.subsystem().text_input();
+			if self.text_input_started {
+				text_input.start(sdl_window);
+			} else {
+				text_input.stop(sdl_window);
+			}
+		}
 		Ok(())
 	}
 
 	// if there are drawables available then clear the screen, iterate over the queue, draw 'em
 	// and then outside the if-block present the canvas, this is made to avoid flickering
 	fn draw(&mut self, window: &mut Window) -> anyhow::Result<()> {
-		if let Ok(Some(queue)) = self.render_receiver.try_recv() {
-			window.graphics.canvas.clear();
-			for drawable in queue {
-				match drawable {
-					Drawable::Image(sprite) => {
-						window.graphics.draw_image(
-							sprite.image,
-							sprite.transform.position,
-							sprite.transform.rotation,
-							false,
-							false
-						).unwrap_or_else(|e| { error!("failed to draw image: {e}"); });
-					},
-					Drawable::Text { font, position, text, color, size } => {
-						window.graphics.draw_text(text, position, font, color, size)
-							.unwrap_or_else(|e| { error!("failed to draw text: {e}"); });
-					},
-					Drawable::Rect { w, h, x, y } => {
-						window.graphics.draw_rect(x, y, w, h)
-							.unwrap_or_else(|e| { error!("failed to draw rectangle: {e}"); });
-					}
+		if let Ok(Some(views)) = self.render_receiver.try_recv() {
+			window.graphics.render_views(&views);
+			// screenshots are taken before presenting, the back buffer is undefined afterward
+			while let Ok(Some(path)) = self.screenshot_receiver.try_recv() {
+				self.pending_screenshots.push(path);
+			}
+			for path in self.pending_screenshots.drain(..) {
+				match window.graphics.save_screenshot(&path) {
+					Ok(()) => info!("saved screenshot to {}", path.display()),
+					Err(e) => error!("failed to save screenshot to {}: {e:#}", path.display()),
 				}
 			}
 			window.graphics.canvas.present();
@@ -61,12 +148,17 @@ impl WindowEventHandler for EventHandler {
 	}
 
 	fn key_down_event(&mut self, _window: &mut Window, event: KeyboardEvent) -> anyhow::Result<()> {
+		if let Some((key, directory)) = &self.screenshot_key {
+			if event.keycode == Some(*key) && !event.repeat {
+				self.pending_screenshots.push(screenshot_path(directory));
+			}
+		}
 		self.event_sender.send(PluginEvent::KeyboardEvent(event))?;
 		Ok(())
 	}
 
 	fn key_up_event(&mut self, _window: &mut Window, event: KeyboardEvent) -> anyhow::Result<()> {
-		self.event_sender.send(PluginEvent::KeyboardEvent(event))?;
+		self.event_sender.send(PluginEvent::KeyUpEvent(event))?;
 		Ok(())
 	}
 
@@ -81,7 +173,7 @@ impl WindowEventHandler for EventHandler {
 	}
 
 	fn mouse_button_up_event(&mut self, _window: &mut Window, event: MouseClickEvent) -> anyhow::Result<()> {
-		self.event_sender.send(PluginEvent::MouseClickEvent(event))?;
+		self.event_sender.send(PluginEvent::MouseButtonUpEvent(event))?;
 		Ok(())
 	}
 
@@ -89,4 +181,9 @@ impl WindowEventHandler for EventHandler {
 		self.event_sender.send(PluginEvent::MouseWheelEvent(event))?;
 		Ok(())
 	}
+
+	fn text_input_event(&mut self, _window: &mut Window, event: TextInputEvent) -> anyhow::Result<()> {
+		self.event_sender.send(PluginEvent::TextInputEvent(event))?;
+		Ok(())
+	}
 }
\ No newline at end of file
@@ -1,25 +1,42 @@
 use std::error::Error;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use log::debug;
-use specs::{DispatcherBuilder, World};
+use sdl3::keyboard::Keycode;
+use specs::{DispatcherBuilder, World, WorldExt};
 use fennel_plugins::Plugin;
-use fennel_resources::manager::ResourceManager;
-use crate::graphics::{Drawable, Graphics, WindowConfig};
+use fennel_resources::asset_loader::AssetLoaders;
+use fennel_resources::info::AssetInfo;
+use fennel_resources::manager::SharedResourceManager;
+use crate::graphics::{Graphics, Sprite, WindowConfig};
+use crate::plugin::debug_overlay::DebugOverlay;
+use crate::text_cache::TextCacheConfig;
 use crate::Window;
 use crate::events;
 use crate::events::WindowEventHandler;
 use crate::plugin::event_handler::{EventHandler, PluginEvent};
-use crate::plugin::system::{Camera, CleanupSystem, EventGatherSystem, QueuedRenderingSystem, RenderQueue};
+use crate::plugin::system::{AssetReport, AssetSystem, Assets, Camera, CameraSystem, CameraView, CleanupSystem, EventGatherSystem, QueuedRenderingSystem, RenderLayers, RenderQueue, Screenshots, TextInputState, WindowSize};
 
 pub mod system;
 pub mod event_handler;
+pub mod debug_overlay;
 
 /// The graphics module plugin for `fennel_runtime`
+///
+/// Plugins add their own asset types by registering an
+/// [`AssetLoader`](fennel_resources::asset_loader::AssetLoader) into the [`AssetLoaders`] of the
+/// world during `prepare`. Assets of a type without a loader wait for one to be registered, so
+/// the plugins prepared after the graphics one can register theirs.
 pub struct GraphicsPlugin {
 	name: &'static str,
 	dimensions: (u32, u32),
 	assets_path: String,
+	layers: RenderLayers,
+	text_cache: TextCacheConfig,
+	debug_overlay: Option<DebugOverlay>,
+	headless: bool,
+	screenshot_key: Option<(Keycode, PathBuf)>,
+	hot_reload: Option<Duration>,
 }
 
 impl GraphicsPlugin {
@@ -33,9 +50,57 @@ impl GraphicsPlugin {
 		Self {
 			name,
 			dimensions,
-			assets_path: assets_path.to_string()
+			assets_path: assets_path.to_string(),
+			layers: RenderLayers::default(),
+			text_cache: TextCacheConfig::default(),
+			debug_overlay: None,
+			headless: false,
+			screenshot_key: None,
+			hot_reload: None,
 		}
 	}
+
+	/// Replace the default render layers (`background`, `world`, `foreground`, `ui`)
+	///
+	/// # Arguments
+	/// * `layers`: layer names, from the bottom to the top
+	pub fn with_layers(mut self, layers: &[&str]) -> Self {
+		self.layers = RenderLayers::new(layers.iter().map(|layer| layer.to_string()).collect());
+		self
+	}
+
+	/// Enable the [`DebugOverlay`], drawn with a font registered in the resource manager
+	pub fn with_debug_overlay(mut self, font: &str, font_size: f32) -> Self {
+		self.debug_overlay = Some(DebugOverlay::new(font, font_size));
+		self
+	}
+
+	/// Render offscreen without a window, e.g. to take screenshots on a machine without a display
+	pub fn headless(mut self) -> Self {
+		self.headless = true;
+		self
+	}
+
+	/// Save a screenshot into `directory` whenever `key` is pressed. Systems can also request
+	/// screenshots through the [`Screenshots`] resource
+	pub fn with_screenshot_key<P: Into<PathBuf>>(mut self, key: Keycode, directory: P) -> Self {
+		self.screenshot_key = Some((key, directory.into()));
+		self
+	}
+
+	/// Configure the memory budget and the mode of the text cache
+	pub fn with_text_cache(mut self, config: TextCacheConfig) -> Self {
+		self.text_cache = config;
+		self
+	}
+
+	/// Check the files of loaded assets and the asset manifests for changes every `interval`,
+	/// reloading what changed. Meant for development: reloaded images replace their textures in
+	/// place and fonts are opened again, so entities keep drawing them by the same name.
+	pub fn with_hot_reload(mut self, interval: Duration) -> Self {
+		self.hot_reload = Some(interval);
+		self
+	}
 }
 
 impl Plugin for GraphicsPlugin {
@@ -48,40 +113,78 @@ impl Plugin for GraphicsPlugin {
 		let name = self.name;
 		let dimensions = self.dimensions;
 		let assets_path = self.assets_path.clone();
-		let (render_sender, render_receiver) = kanal::unbounded::<Vec<Drawable>>();
+		let text_cache = self.text_cache;
+		let window_config = WindowConfig { is_headless: self.headless, ..WindowConfig::default() };
+		let screenshot_key = self.screenshot_key.clone();
+		let hot_reload = self.hot_reload;
+		let (screenshot_sender, screenshot_receiver) = kanal::unbounded::<PathBuf>();
+		let (render_sender, render_receiver) = kanal::unbounded::<Vec<CameraView>>();
 		let (event_sender, event_receiver) = kanal::unbounded::<PluginEvent>();
+		let (asset_sender, asset_receiver) = kanal::unbounded();
+		let (report_sender, report_receiver) = kanal::unbounded::<AssetReport>();
 		let plugin_event_vec: Vec<PluginEvent> = Vec::new();
+		// written by the graphics thread as assets load, systems read sizes and metadata from it
+		let resource_manager = SharedResourceManager::default();
+		let window_size = WindowSize::new(dimensions);
+		let text_input = TextInputState::default();
 
 		world.insert(RenderQueue::new());
-		world.insert(Camera::new((0.0, 0.0), (0.0, 0.0)));
+		world.insert(Camera::new((0.0, 0.0), (dimensions.0 as f32, dimensions.1 as f32)));
+		world.insert(self.layers.clone());
+		if let Some(debug_overlay) = self.debug_overlay.take() {
+			world.insert(debug_overlay);
+		}
+		world.insert(Screenshots::new(screenshot_sender));
+		world.insert(window_size.clone());
+		world.insert(text_input.clone());
+		world.insert(Assets::new(asset_sender, report_receiver));
+		world.insert(resource_manager.clone());
+		// other plugins register the loaders of their asset types into it
+		let asset_loaders = world.entry::<AssetLoaders>().or_insert_with(AssetLoaders::default).clone();
+		// shared with the other plugins filling it, such as the audio one
+		world.entry::<AssetInfo>().or_insert_with(AssetInfo::default);
 		world.insert(render_sender);
 		world.insert(event_receiver);
 		world.insert(plugin_event_vec);
-		dispatcher_builder.add(QueuedRenderingSystem, "queued_rendering_system", &[]);
+		world.register::<Sprite>();
+		world.register::<Camera>();
+		dispatcher_builder.add(CameraSystem::default(), "camera_system", &[]);
+		dispatcher_builder.add(QueuedRenderingSystem, "queued_rendering_system", &["camera_system"]);
 		dispatcher_builder.add(EventGatherSystem, "event_gather_system", &[]);
+		dispatcher_builder.add(AssetSystem, "asset_system", &[]);
 		dispatcher_builder.add_thread_local(CleanupSystem);
 
 		std::thread::spawn(move || {
-			let resource_manager = Arc::new(Mutex::new(ResourceManager::new()));
 			let graphics = Graphics::new(
 				String::from(name),
 				dimensions,
-				resource_manager.clone(),
+				resource_manager,
 				|graphics| -> anyhow::Result<()> {
-					let mut resource_manager = match resource_manager.try_lock() {
-						Ok(guard) => guard,
-						Err(e) => return Err(anyhow::anyhow!("failed to lock resource_manager: {}", e)),
-					};
-					crate::resources::load_dir(&mut resource_manager, assets_path.parse()?, graphics)?;
+					graphics.asset_loaders = asset_loaders.clone();
+					// assets are loaded in the background, the window shows up right away
+					let resource_manager = graphics.resource_manager();
+					crate::resources::queue_dir(&mut resource_manager.write(), assets_path.parse()?, graphics)?;
 					Ok(())
 				},
-				WindowConfig::default(),
+				window_config,
 			).expect("failed to create graphics");
 			let mut window = Window::new(graphics);
+			window.graphics.text_cache.set_config(text_cache);
 			let handler: &'static mut dyn WindowEventHandler = {
 				let boxed = Box::new(EventHandler {
 					render_receiver,
 					event_sender,
+					screenshot_receiver,
+					screenshot_key,
+					pending_screenshots: Vec::new(),
+					asset_receiver,
+					report_sender,
+					last_report: None,
+					handled_requests: 0,
+					hot_reload: hot_reload.map(|interval| (interval, Instant::now())),
+					window_size,
+					text_input,
+					text_input_started: false,
 				});
 				Box::leak(boxed) as &'static mut dyn WindowEventHandler
 			};
@@ -1,61 +1,322 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Instant;
 use kanal::{Receiver, Sender};
 use log::error;
-use specs::{ReadExpect, System, WriteExpect};
-use crate::graphics::{Drawable, Transform};
+use specs::{Entity, Join, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+use fennel_resources::loader::{LoadProgress, DEFAULT_GROUP};
+use fennel_resources::info::AssetInfo;
+use fennel_resources::manager::{MemoryUsage, SharedResourceManager};
+use crate::graphics::{Drawable, Sprite, DEFAULT_LAYER};
+use crate::plugin::debug_overlay::DebugOverlay;
 use crate::plugin::event_handler::PluginEvent;
+use crate::shapes;
 
 /// A simple queue of [`Drawable`] items to be consumed by a rendering system
 pub struct RenderQueue {
 	/// List of drawables which are rendered in order from index zero
 	pub queue: Vec<Drawable>,
+	/// Drawables in window coordinates, drawn over every camera and never transformed, for
+	/// interfaces that must not be repeated in every split-screen view
+	pub fixed: Vec<Drawable>,
+	/// Textures to draw into before the window, see [`RenderQueue::target`]
+	pub targets: Vec<TargetQueue>,
+}
+
+/// A texture that views can be drawn into instead of the window
+///
+/// The texture is registered in the resource manager under `name`, so sprites can show it with
+/// `Sprite::image` set to that name. It's created the first time it's drawn into, recreated
+/// when its size changes and keeps its content between frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderTarget {
+	/// Name of the texture in the resource manager
+	pub name: String,
+	/// Width and height of the texture in pixels
+	pub size: (u32, u32),
+	/// Color the texture is cleared with before drawing, `None` keeps the previous content and
+	/// draws over it
+	pub clear_color: Option<(u8, u8, u8, u8)>,
+}
+
+/// Drawables queued for a [`RenderTarget`]
+#[derive(Debug)]
+pub struct TargetQueue {
+	/// The texture drawn into
+	pub target: RenderTarget,
+	/// If set, the main queue is also drawn into the texture as seen by this camera, below the
+	/// target's own drawables. Useful for minimaps and portals
+	pub camera: Option<Camera>,
+	/// Drawables in texture coordinates, sorted by layer and z order like the main queue
+	pub drawables: Vec<Drawable>,
+}
+
+/// Size of the window in pixels, kept up to date by the graphics thread
+///
+/// The fixed queue and the [`DebugOverlay`] cover the whole window whatever the cameras show,
+/// interfaces are laid out in it. Clones share the same size.
+#[derive(Debug, Clone)]
+pub struct WindowSize {
+	size: Arc<RwLock<(u32, u32)>>,
+}
+
+impl WindowSize {
+	pub(crate) fn new(size: (u32, u32)) -> Self {
+		Self { size: Arc::new(RwLock::new(size)) }
+	}
+
+	/// Width and height of the window
+	pub fn get(&self) -> (f32, f32) {
+		let (w, h) = *self.size.read().unwrap_or_else(PoisonError::into_inner);
+		(w as f32, h as f32)
+	}
+
+	pub(crate) fn set(&self, size: (u32, u32)) {
+		*self.size.write().unwrap_or_else(PoisonError::into_inner) = size;
+	}
+}
+
+/// Resource turning the text input of the window on and off
+///
+/// While it's on, typed text is sent as [`PluginEvent::TextInputEvent`]s and the platform may
+/// show an on-screen keyboard or an input method window. It's off by default, the UI turns it on
+/// while a text field is focused. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct TextInputState {
+	active: Arc<AtomicBool>,
+}
+
+impl TextInputState {
+	/// Turn the text input on or off, from the next frame of the graphics thread
+	pub fn set_active(&self, active: bool) {
+		self.active.store(active, Ordering::Relaxed);
+	}
+
+	/// Is the text input wanted?
+	pub fn is_active(&self) -> bool {
+		self.active.load(Ordering::Relaxed)
+	}
+}
+
+/// Resource requesting screenshots from the graphics thread
+///
+/// A screenshot is saved after the next frame is drawn, failures are logged.
+pub struct Screenshots {
+	sender: Sender<PathBuf>,
+}
+
+impl Screenshots {
+	pub(crate) fn new(sender: Sender<PathBuf>) -> Self {
+		Self { sender }
+	}
+
+	/// Save the next frame to `path`, in the format matching its extension
+	pub fn take(&self, path: impl Into<PathBuf>) {
+		if let Err(e) = self.sender.send(path.into()) {
+			error!("failed to request a screenshot: {e}");
+		}
+	}
+}
+
+/// Request sent from [`Assets`] to the graphics thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AssetRequest {
+	/// Load the assets of a manifest group in the background
+	LoadGroup(String),
+	/// Stop keeping the assets of a manifest group loaded
+	UnloadGroup(String),
+}
+
+/// State of the assets sent from the graphics thread to [`Assets`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct AssetReport {
+	/// Progress of each group
+	pub(crate) groups: HashMap<String, LoadProgress>,
+	/// Memory used by each resource type
+	pub(crate) memory: Vec<MemoryUsage>,
+	/// Number of [`AssetRequest`]s handled so far
+	pub(crate) handled: u64,
+}
+
+/// Resource loading and unloading asset groups and reporting their progress
+///
+/// Assets are declared with a `group` in the manifest and are loaded in the background by the
+/// graphics thread. The progress is updated every tick by the `"asset_system"`, so a loading
+/// screen can be shown until [`Assets::progress`] is done.
+pub struct Assets {
+	sender: Sender<AssetRequest>,
+	receiver: Receiver<AssetReport>,
+	/// Latest report, `None` until the graphics thread sends the first one
+	report: Option<AssetReport>,
+	/// Number of requests sent, the groups they change are loading until they're handled
+	requested: AtomicU64,
+}
+
+impl Assets {
+	pub(crate) fn new(sender: Sender<AssetRequest>, receiver: Receiver<AssetReport>) -> Self {
+		Self { sender, receiver, report: None, requested: AtomicU64::new(0) }
+	}
+
+	/// Start loading the assets of a manifest group
+	pub fn load_group(&self, group: &str) {
+		self.request(AssetRequest::LoadGroup(group.to_string()));
+	}
+
+	/// Stop keeping the assets of a manifest group loaded, they're removed from the resource
+	/// manager unless something else still references them
+	pub fn unload_group(&self, group: &str) {
+		self.request(AssetRequest::UnloadGroup(group.to_string()));
+	}
+
+	/// Progress of a group, empty until its loading starts
+	pub fn progress(&self, group: &str) -> LoadProgress {
+		self.report.as_ref().and_then(|report| report.groups.get(group)).copied().unwrap_or_default()
+	}
+
+	/// Progress of the assets loaded at startup
+	pub fn startup_progress(&self) -> LoadProgress {
+		self.progress(DEFAULT_GROUP)
+	}
+
+	/// Progress of all groups together
+	pub fn total(&self) -> LoadProgress {
+		let groups = self.report.iter().flat_map(|report| report.groups.values());
+		groups.fold(LoadProgress::default(), |total, &progress| total + progress)
+	}
+
+	/// Is any asset still being loaded?
+	///
+	/// Assets are considered loading until the graphics thread reports on the startup assets
+	/// and on every group requested since.
+	pub fn is_loading(&self) -> bool {
+		let Some(report) = &self.report else {
+			return true;
+		};
+		report.handled < self.requested.load(Ordering::Relaxed) || !self.total().is_done()
+	}
+
+	/// Number and memory usage of the loaded resources of each type, the biggest first
+	pub fn memory(&self) -> &[MemoryUsage] {
+		self.report.as_ref().map_or(&[], |report| &report.memory)
+	}
+
+	fn request(&self, request: AssetRequest) {
+		match self.sender.send(request) {
+			Ok(()) => {
+				self.requested.fetch_add(1, Ordering::Relaxed);
+			}
+			Err(e) => error!("failed to send an asset request: {e}"),
+		}
+	}
+}
+
+/// Ordered list of named render layers.
+///
+/// Drawables are sorted by the index of their layer in this list first and by their `z` order
+/// second, so layers listed later are drawn on top of the earlier ones. Drawables on a layer
+/// which isn't listed are drawn after all the known layers.
+#[derive(Debug, Clone)]
+pub struct RenderLayers {
+	/// Layer names, from the bottom to the top
+	pub layers: Vec<String>,
+}
+
+/// Drawables as seen by one camera, ready to be drawn into its viewport
+#[derive(Debug)]
+pub struct CameraView {
+	/// Area (x, y, w, h) of the window the drawables are drawn into and clipped to
+	pub viewport: (f32, f32, f32, f32),
+	/// Drawables in camera coordinates, relative to the viewport, in drawing order
+	pub drawables: Vec<Drawable>,
+	/// Texture the view is drawn into, the window if `None`
+	pub target: Option<RenderTarget>,
 }
 
 /// ECS system that renders Sprite components from [`RenderQueue`]
+///
+/// Render targets are drawn first, so that the window can show them in the same frame. The
+/// queue is then drawn once by every entity with a [`Camera`] component, in the order of their
+/// priority, or by the [`Camera`] resource if there are no camera entities. The fixed queue and
+/// then the [`DebugOverlay`], if there is one, are drawn last over the whole window.
 pub struct QueuedRenderingSystem;
 
 pub(crate) struct EventGatherSystem;
 
+/// Updates the [`Assets`] progress with the latest report of the graphics thread, and the
+/// [`AssetInfo`] with the metadata of the assets loaded or unloaded since the last tick
+pub(crate) struct AssetSystem;
+
 pub(crate) struct CleanupSystem;
 
 impl<'a> System<'a> for QueuedRenderingSystem {
 	type SystemData = (
 		WriteExpect<'a, RenderQueue>,
 		ReadExpect<'a, Camera>,
-		WriteExpect<'a, Sender<Vec<Drawable>>>,
+		ReadStorage<'a, Camera>,
+		ReadExpect<'a, RenderLayers>,
+		Option<ReadExpect<'a, DebugOverlay>>,
+		ReadExpect<'a, WindowSize>,
+		WriteExpect<'a, Sender<Vec<CameraView>>>,
 	);
 
-	fn run(&mut self, (mut rq, camera, sender): Self::SystemData) {
+	fn run(&mut self, (mut rq, camera, camera_components, layers, debug_overlay, window_size, sender): Self::SystemData) {
 		let mut drained_drawables: Vec<Drawable> = rq.queue.drain(..).collect();
-		for drawable in &mut drained_drawables {
-			match drawable {
-				Drawable::Image(sprite) => {
-					if !sprite.fixed {
-						let (camera_x, camera_y) = camera.world_to_camera((sprite.transform.position.0, sprite.transform.position.1));
-						sprite.transform = Transform::new((camera_x, camera_y), sprite.transform.rotation, sprite.transform.scale);
-					}
-				},
-				Drawable::Rect { w, h, x, y } => {
-					let (camera_x, camera_y) = camera.world_to_camera((*x, *y));
-					*drawable = Drawable::Rect {
-						w: *w,
-						h: *h,
-						x: camera_x,
-						y: camera_y,
-					};
-				},
-				Drawable::Text { font, position, text, color, size } => {
-					let (camera_x, camera_y) = camera.world_to_camera(*position);
-					*drawable = Drawable::Text {
-						font: font.clone(),
-						position: (camera_x, camera_y),
-						text: text.clone(),
-						color: *color,
-						size: *size,
-					};
+		// `sort_by_key` is stable, so drawables sharing a layer and z order keep their queue order
+		drained_drawables.sort_by_key(|drawable| (layers.order(drawable.layer()), drawable.z()));
+
+		// camera entities replace the camera resource if there are any
+		let mut cameras: Vec<&Camera> = camera_components.join().collect();
+		if cameras.is_empty() {
+			cameras.push(&camera);
+		}
+		cameras.sort_by_key(|camera| camera.priority);
+
+		let mut views: Vec<CameraView> = rq.targets
+			.drain(..)
+			.map(|target_queue| {
+				let mut drawables = target_queue.drawables;
+				drawables.sort_by_key(|drawable| (layers.order(drawable.layer()), drawable.z()));
+				if let Some(camera) = &target_queue.camera {
+					drawables.splice(0..0, camera.view(&drained_drawables));
 				}
-			}
+				let (w, h) = target_queue.target.size;
+				CameraView {
+					viewport: (0.0, 0.0, w as f32, h as f32),
+					drawables,
+					target: Some(target_queue.target),
+				}
+			})
+			.collect();
+
+		views.extend(cameras.into_iter().map(|camera| CameraView {
+			viewport: camera.screen_rect(),
+			drawables: camera.view(&drained_drawables),
+			target: None,
+		}));
+
+		let (width, height) = window_size.get();
+		let window = (0.0, 0.0, width, height);
+		let mut fixed: Vec<Drawable> = rq.fixed.drain(..).collect();
+		if !fixed.is_empty() {
+			fixed.sort_by_key(|drawable| (layers.order(drawable.layer()), drawable.z()));
+			views.push(CameraView {
+				viewport: window,
+				drawables: fixed,
+				target: None,
+			});
+		}
+
+		// the debug overlay is in window coordinates and covers every camera
+		if let Some(debug_overlay) = debug_overlay {
+			views.push(CameraView {
+				viewport: window,
+				drawables: debug_overlay.drawables(),
+				target: None,
+			});
 		}
-		sender.send(drained_drawables).unwrap_or_else(|e| error!("failed to send queue: {}", e));
+		sender.send(views).unwrap_or_else(|e| error!("failed to send queue: {}", e));
 	}
 }
 
@@ -74,6 +335,18 @@ impl<'a> System<'a> for EventGatherSystem {
 	}
 }
 
+impl<'a> System<'a> for AssetSystem {
+	type SystemData = (WriteExpect<'a, Assets>, WriteExpect<'a, AssetInfo>, ReadExpect<'a, SharedResourceManager>);
+
+	fn run(&mut self, (mut assets, mut info, manager): Self::SystemData) {
+		// only the latest report matters
+		while let Ok(Some(report)) = assets.receiver.try_recv() {
+			assets.report = Some(report);
+		}
+		info.sync("graphics", &manager.read());
+	}
+}
+
 impl<'a> System<'a> for CleanupSystem {
 	type SystemData = (WriteExpect<'a, Vec<PluginEvent>>,);
 
@@ -85,7 +358,25 @@ impl<'a> System<'a> for CleanupSystem {
 impl RenderQueue {
 	/// Creates a new instance of [`RenderQueue`]
 	pub fn new() -> Self {
-		Self { queue: vec![] }
+		Self { queue: vec![], fixed: vec![], targets: vec![] }
+	}
+
+	/// Get the queue of a render target, creating it if the target isn't queued yet this frame
+	///
+	/// The size and clear color of an already queued target are replaced.
+	pub fn target(&mut self, name: &str, size: (u32, u32), clear_color: Option<(u8, u8, u8, u8)>) -> &mut TargetQueue {
+		let target = RenderTarget { name: name.to_string(), size, clear_color };
+		let index = match self.targets.iter().position(|queue| queue.target.name == name) {
+			Some(index) => {
+				self.targets[index].target = target;
+				index
+			}
+			None => {
+				self.targets.push(TargetQueue { target, camera: None, drawables: Vec::new() });
+				self.targets.len() - 1
+			}
+		};
+		&mut self.targets[index]
 	}
 }
 
@@ -95,27 +386,370 @@ impl Default for RenderQueue {
 	}
 }
 
+impl RenderLayers {
+	/// Creates a new instance of [`RenderLayers`]
+	///
+	/// # Arguments
+	/// * `layers`: layer names, from the bottom to the top
+	pub fn new(layers: Vec<String>) -> Self {
+		Self { layers }
+	}
+
+	/// Get the draw order of a layer
+	pub fn order(&self, layer: &str) -> usize {
+		self.layers
+			.iter()
+			.position(|name| name == layer)
+			.unwrap_or(self.layers.len())
+	}
+}
+
+impl Default for RenderLayers {
+	/// `background`, `world`, `foreground` and `ui` layers
+	fn default() -> Self {
+		Self::new(vec![
+			String::from("background"),
+			String::from(DEFAULT_LAYER),
+			String::from("foreground"),
+			String::from("ui"),
+		])
+	}
+}
+
 /// A struct to represent the camera in the world
+///
+/// `position` is the top-left corner of the view when the camera isn't zoomed or rotated.
+/// Zoom and rotation are applied around the center of the view.
+///
+/// The [`Camera`] resource is used when there are no entities with a [`Camera`] component,
+/// add several camera entities with different `screen_position`s for split-screen or a minimap.
 #[derive(Debug)]
 pub struct Camera {
 	/// Position of the camera in world coordinates
 	pub position: (f32, f32),
 	/// Dimensions of the viewable area
 	pub viewport: (f32, f32),
+	/// Position of the viewable area in the window
+	pub screen_position: (f32, f32),
+	/// Render layers seen by the camera, all of them if `None`
+	pub layer_mask: Option<Vec<String>>,
+	/// Cameras with a higher priority are drawn on top of the others
+	pub priority: i32,
+	/// Zoom factor, `2.0` makes everything twice as big
+	pub zoom: f32,
+	/// Rotation of the camera in degrees, clockwise
+	pub rotation: f64,
+	/// Entity the camera is following, see [`Camera::follow`]
+	pub follow: Option<CameraFollow>,
+	/// World area (x, y, w, h) the view is kept inside of
+	pub bounds: Option<(f32, f32, f32, f32)>,
+	shake: Option<CameraShake>,
+	shake_offset: (f32, f32),
+}
+
+/// Smooth following of an entity by the [`Camera`]
+#[derive(Debug, Clone, Copy)]
+pub struct CameraFollow {
+	/// The followed entity, its position is taken from its [`Sprite`]
+	pub target: Entity,
+	/// Size (w, h) of the area around the view center in which the target can move without
+	/// moving the camera
+	pub deadzone: (f32, f32),
+	/// Fraction of the remaining distance the camera moves every 1/60 of a second, in `(0.0, 1.0]`.
+	/// `1.0` snaps to the target
+	pub lerp: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CameraShake {
+	intensity: f32,
+	duration: f32,
+	remaining: f32,
+	seed: u32,
+}
+
+/// ECS system that moves the [`Camera`]: follows its target, keeps it in bounds and shakes it
+#[derive(Default)]
+pub struct CameraSystem {
+	last_run: Option<Instant>,
 }
 
 impl specs::Component for Camera {
 	type Storage = specs::VecStorage<Self>;
 }
 
+impl<'a> System<'a> for CameraSystem {
+	type SystemData = (WriteExpect<'a, Camera>, WriteStorage<'a, Camera>, ReadStorage<'a, Sprite>);
+
+	fn run(&mut self, (mut camera, mut camera_components, sprites): Self::SystemData) {
+		let now = Instant::now();
+		let delta = self.last_run
+			.map(|last_run| now.duration_since(last_run).as_secs_f32())
+			.unwrap_or(0.0);
+		self.last_run = Some(now);
+
+		for camera in std::iter::once(&mut *camera).chain((&mut camera_components).join()) {
+			let target = camera.follow
+				.and_then(|follow| sprites.get(follow.target))
+				.map(|sprite| sprite.transform.position);
+			camera.update(delta, target);
+		}
+	}
+}
+
 impl Camera {
 	/// Create a new instance of [`Camera`]
 	pub fn new(position: (f32, f32), viewport: (f32, f32)) -> Self {
-		Camera { position, viewport }
+		Camera {
+			position,
+			viewport,
+			screen_position: (0.0, 0.0),
+			layer_mask: None,
+			priority: 0,
+			zoom: 1.0,
+			rotation: 0.0,
+			follow: None,
+			bounds: None,
+			shake: None,
+			shake_offset: (0.0, 0.0),
+		}
+	}
+
+	/// Place the viewable area at `screen_position` in the window
+	pub fn with_screen_position(mut self, screen_position: (f32, f32)) -> Self {
+		self.screen_position = screen_position;
+		self
+	}
+
+	/// See only the given render layers
+	pub fn with_layer_mask(mut self, layers: &[&str]) -> Self {
+		self.layer_mask = Some(layers.iter().map(|layer| layer.to_string()).collect());
+		self
+	}
+
+	/// Set the drawing priority, cameras with a higher priority are drawn on top
+	pub fn with_priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+
+	/// Area (x, y, w, h) of the window the camera draws into
+	pub fn screen_rect(&self) -> (f32, f32, f32, f32) {
+		(self.screen_position.0, self.screen_position.1, self.viewport.0, self.viewport.1)
+	}
+
+	/// Does the camera see the render layer?
+	pub fn sees(&self, layer: &str) -> bool {
+		self.layer_mask
+			.as_ref()
+			.is_none_or(|mask| mask.iter().any(|name| name == layer))
+	}
+
+	/// The drawables on the layers the camera sees, in camera coordinates
+	///
+	/// Drawables whose [`Drawable::bounds`] are outside of the view are culled before being
+	/// transformed.
+	pub fn view(&self, drawables: &[Drawable]) -> Vec<Drawable> {
+		let world_rect = self.view_rect();
+		let screen_rect = (0.0, 0.0, self.viewport.0, self.viewport.1);
+		drawables
+			.iter()
+			.filter(|drawable| self.sees(drawable.layer()))
+			.filter(|drawable| {
+				// fixed sprites are already in camera coordinates
+				let rect = if matches!(drawable, Drawable::Image(sprite) if sprite.fixed) { screen_rect } else { world_rect };
+				drawable.bounds().is_none_or(|bounds| shapes::overlaps(bounds, rect))
+			})
+			.map(|drawable| self.transform_drawable(drawable.clone()))
+			.collect()
+	}
+
+	/// Transform a drawable from world coordinates to camera coordinates
+	pub fn transform_drawable(&self, mut drawable: Drawable) -> Drawable {
+		match &mut drawable {
+			Drawable::Image(sprite) => {
+				if !sprite.fixed {
+					sprite.transform.position = self.world_to_camera(sprite.transform.position);
+					sprite.transform.scale *= self.zoom as f64;
+					sprite.transform.rotation -= self.rotation;
+				}
+			},
+			// rectangles and text are axis-aligned, so the camera rotation only moves them
+			Drawable::Rect { w, h, x, y, thickness, .. } => {
+				(*x, *y) = self.world_to_camera((*x, *y));
+				*w *= self.zoom;
+				*h *= self.zoom;
+				*thickness *= self.zoom;
+			},
+			Drawable::Circle { center, radius, thickness, .. } => {
+				*center = self.world_to_camera(*center);
+				*radius *= self.zoom;
+				*thickness *= self.zoom;
+			},
+			Drawable::Line { from, to, thickness, .. } => {
+				*from = self.world_to_camera(*from);
+				*to = self.world_to_camera(*to);
+				*thickness *= self.zoom;
+			},
+			Drawable::Polyline { points, thickness, .. } | Drawable::Polygon { points, thickness, .. } => {
+				for point in points.iter_mut() {
+					*point = self.world_to_camera(*point);
+				}
+				*thickness *= self.zoom;
+			},
+			Drawable::Text { position, size, layout, .. } => {
+				*position = self.world_to_camera(*position);
+				*size *= self.zoom;
+				layout.scale(self.zoom);
+			}
+		}
+		drawable
+	}
+
+	/// Center of the view in world coordinates
+	pub fn center(&self) -> (f32, f32) {
+		(self.position.0 + self.viewport.0 / 2.0, self.position.1 + self.viewport.1 / 2.0)
+	}
+
+	/// Move the camera so that the view is centered on `center`
+	pub fn set_center(&mut self, center: (f32, f32)) {
+		self.position = (center.0 - self.viewport.0 / 2.0, center.1 - self.viewport.1 / 2.0);
+	}
+
+	/// Start following an entity
+	///
+	/// # Arguments
+	/// * `target`: the entity, it must have a [`Sprite`]
+	/// * `deadzone`: size (w, h) of the area in which the target can move without moving the camera
+	/// * `lerp`: fraction of the remaining distance covered every 1/60 of a second, `1.0` snaps
+	pub fn follow(&mut self, target: Entity, deadzone: (f32, f32), lerp: f32) {
+		self.follow = Some(CameraFollow { target, deadzone, lerp });
+	}
+
+	/// Shake the camera, the shake fades out linearly
+	///
+	/// # Arguments
+	/// * `intensity`: maximum offset in world units
+	/// * `duration`: duration in seconds
+	pub fn shake(&mut self, intensity: f32, duration: f32) {
+		let seed = self.shake.map(|shake| shake.seed).unwrap_or(0x9E37_79B9);
+		self.shake = Some(CameraShake { intensity, duration, remaining: duration, seed });
 	}
 
 	/// Transform world coordinates to camera coordinates
 	pub fn world_to_camera(&self, world_pos: (f32, f32)) -> (f32, f32) {
-		(world_pos.0 - self.position.0, world_pos.1 - self.position.1)
+		let center = self.center();
+		let relative = (
+			world_pos.0 - center.0 - self.shake_offset.0,
+			world_pos.1 - center.1 - self.shake_offset.1,
+		);
+		let (x, y) = rotate(relative, -self.rotation);
+		(x * self.zoom + self.viewport.0 / 2.0, y * self.zoom + self.viewport.1 / 2.0)
+	}
+
+	/// Transform camera (screen) coordinates to world coordinates, e.g. to find what's under
+	/// the mouse cursor
+	pub fn camera_to_world(&self, camera_pos: (f32, f32)) -> (f32, f32) {
+		let center = self.center();
+		let relative = (
+			(camera_pos.0 - self.viewport.0 / 2.0) / self.zoom,
+			(camera_pos.1 - self.viewport.1 / 2.0) / self.zoom,
+		);
+		let (x, y) = rotate(relative, self.rotation);
+		(x + center.0 + self.shake_offset.0, y + center.1 + self.shake_offset.1)
+	}
+
+	/// The world area (x, y, w, h) visible by the camera. If the camera is rotated, this is the
+	/// bounding box of the view
+	pub fn view_rect(&self) -> (f32, f32, f32, f32) {
+		let corners = [
+			(0.0, 0.0),
+			(self.viewport.0, 0.0),
+			(0.0, self.viewport.1),
+			(self.viewport.0, self.viewport.1),
+		].map(|corner| self.camera_to_world(corner));
+		let (mut min, mut max) = (corners[0], corners[0]);
+		for (x, y) in corners {
+			min = (min.0.min(x), min.1.min(y));
+			max = (max.0.max(x), max.1.max(y));
+		}
+		(min.0, min.1, max.0 - min.0, max.1 - min.1)
+	}
+
+	/// Advance following, bounds clamping and shaking by `delta` seconds
+	///
+	/// # Arguments
+	/// * `delta`: elapsed time in seconds
+	/// * `target`: world position of the followed entity, if there is one
+	pub fn update(&mut self, delta: f32, target: Option<(f32, f32)>) {
+		if let (Some(follow), Some(target)) = (self.follow, target) {
+			let center = self.center();
+			let desired = (
+				follow_axis(center.0, target.0, follow.deadzone.0),
+				follow_axis(center.1, target.1, follow.deadzone.1),
+			);
+			let t = 1.0 - (1.0 - follow.lerp.clamp(0.0, 1.0)).powf(delta * 60.0);
+			self.set_center((center.0 + (desired.0 - center.0) * t, center.1 + (desired.1 - center.1) * t));
+		}
+
+		if let Some(bounds) = self.bounds {
+			let center = self.center();
+			let half_view = (self.viewport.0 / self.zoom / 2.0, self.viewport.1 / self.zoom / 2.0);
+			self.set_center((
+				clamp_axis(center.0, half_view.0, bounds.0, bounds.2),
+				clamp_axis(center.1, half_view.1, bounds.1, bounds.3),
+			));
+		}
+
+		self.shake_offset = match self.shake.as_mut() {
+			Some(shake) if shake.remaining > 0.0 => {
+				shake.remaining -= delta;
+				let strength = shake.intensity * (shake.remaining / shake.duration).max(0.0);
+				(strength * next_random(&mut shake.seed), strength * next_random(&mut shake.seed))
+			}
+			_ => {
+				self.shake = None;
+				(0.0, 0.0)
+			}
+		};
+	}
+}
+
+/// Rotate a vector by `degrees` clockwise (with the y axis pointing down)
+fn rotate(vector: (f32, f32), degrees: f64) -> (f32, f32) {
+	if degrees == 0.0 {
+		return vector;
 	}
-}
\ No newline at end of file
+	let (sin, cos) = (degrees.to_radians().sin() as f32, degrees.to_radians().cos() as f32);
+	(vector.0 * cos - vector.1 * sin, vector.0 * sin + vector.1 * cos)
+}
+
+/// Move `center` just enough for `target` to get inside of the deadzone
+fn follow_axis(center: f32, target: f32, deadzone: f32) -> f32 {
+	let half = deadzone / 2.0;
+	if target > center + half {
+		target - half
+	} else if target < center - half {
+		target + half
+	} else {
+		center
+	}
+}
+
+/// Keep a view of `half_view` around `center` inside of `[start, start + length]`, or center
+/// it there if the view is bigger
+fn clamp_axis(center: f32, half_view: f32, start: f32, length: f32) -> f32 {
+	if half_view * 2.0 >= length {
+		start + length / 2.0
+	} else {
+		center.clamp(start + half_view, start + length - half_view)
+	}
+}
+
+/// xorshift, returns a value in `[-1.0, 1.0]`
+fn next_random(seed: &mut u32) -> f32 {
+	*seed ^= *seed << 13;
+	*seed ^= *seed >> 17;
+	*seed ^= *seed << 5;
+	(*seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
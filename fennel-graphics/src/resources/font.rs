@@ -1,10 +1,18 @@
-use anyhow::bail;
-use std::{path::PathBuf, rc::Rc};
+use anyhow::{bail, Context};
+use std::{path::{Path, PathBuf}, rc::Rc};
 use std::any::Any;
+use fennel_resources::info::{AssetMetadata, FontMetrics, GlyphAdvances};
+use fennel_resources::manifest::{FontOptions, Hinting};
 use fennel_resources::resource::Resource;
-use crate::graphics::Graphics;
+use fennel_resources::vfs;
+use sdl3::iostream::IOStream;
+use sdl3::ttf::Sdl3TtfContext;
 
-/// Font asset
+/// Point size of the [`FontMetrics`] of fonts without a default size
+pub(crate) const METRICS_SIZE: f32 = 16.0;
+
+/// A font opened at a size, kept by [`crate::resources::gpu::GpuResources`] on the graphics
+/// thread
 pub struct InternalFont {
     /// Filesystem path to the font.
     pub path: PathBuf,
@@ -16,12 +24,8 @@ pub struct InternalFont {
     pub buffer: Rc<sdl3::ttf::Font<'static>>,
 }
 
-pub struct Font {
-    internal: Rc<InternalFont>,
-    name: String,
-}
-
-/// Font asset to be able to use fonts of various sizes
+/// Font asset to be able to use fonts of various sizes, which are opened on the graphics thread
+/// on first use
 pub struct DummyFont {
     inner: InternalDummyFont
 }
@@ -31,6 +35,12 @@ pub(crate) struct InternalDummyFont {
     pub(crate) path: PathBuf,
     /// Internal name
     pub(crate) name: String,
+    /// Default size and hinting from the manifest
+    pub(crate) options: FontOptions,
+    /// Metrics at the default size, or at [`METRICS_SIZE`]
+    pub(crate) metrics: Option<FontMetrics>,
+    /// Glyph advances at the size of the metrics
+    pub(crate) advances: GlyphAdvances,
 }
 
 impl Resource for DummyFont {
@@ -45,56 +55,88 @@ impl Resource for DummyFont {
     fn name(&self) -> String {
         self.inner.name.clone()
     }
-}
 
-impl Resource for Font {
-    fn data(&self) -> &dyn Any {
-        &self.internal as &dyn Any
+    fn metadata(&self) -> Option<AssetMetadata> {
+        self.inner.metrics.map(|metrics| AssetMetadata::Font { metrics, advances: self.inner.advances.clone() })
     }
+}
 
-    fn data_mut(&mut self) -> &mut dyn Any {
-        &mut self.internal as &mut dyn Any
+impl InternalFont {
+    /// Open a font file at a size, on the thread owning the TTF context
+    pub(crate) fn load(
+        path: PathBuf,
+        ttf_context: &Sdl3TtfContext,
+        size: f32,
+        hinting: Hinting,
+    ) -> anyhow::Result<Self> {
+        let data = Self::read(&path)?;
+        Self::from_data(path, data, ttf_context, size, hinting)
     }
 
-    fn name(&self) -> String {
-        self.name.clone()
+    /// Read a font file, on any thread
+    pub(crate) fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+        vfs::read(path).with_context(|| format!("failed to read font {}", path.display()))
     }
-}
 
-impl Font {
-    pub(crate) fn load(
+    /// Open a font read with [`InternalFont::read`] at a size, on the thread owning the TTF
+    /// context
+    pub(crate) fn from_data(
         path: PathBuf,
-        name: String,
-        graphics: &mut Graphics,
-        size: Option<f32>,
-    ) -> anyhow::Result<Self>
-    where
-        Self: Sized,
-    {
-        let size = match size {
-            Some(size) => size,
-            None => bail!("Font size not provided"),
-        };
-        let font = graphics
-            .ttf_context
-            .load_font(&path, size)?;
+        data: Vec<u8>,
+        ttf_context: &Sdl3TtfContext,
+        size: f32,
+        hinting: Hinting,
+    ) -> anyhow::Result<Self> {
+        let mut font = ttf_context.load_font_from_iostream(IOStream::from_vec(data)?, size)?;
+        font.set_hinting(match hinting {
+            Hinting::Normal => sdl3::ttf::Hinting::NORMAL,
+            Hinting::Light => sdl3::ttf::Hinting::LIGHT,
+            Hinting::Mono => sdl3::ttf::Hinting::MONO,
+            Hinting::None => sdl3::ttf::Hinting::NONE,
+            Hinting::LightSubpixel => sdl3::ttf::Hinting::LIGHT_SUBPIXEL,
+        });
         let family_name = match font.face_family_name() {
             Some(name) => name,
             None => bail!("failed to get font family name"),
         };
 
-        let internal = InternalFont {
+        Ok(Self {
             path,
             family_name,
             size,
             buffer: Rc::new(font),
-        };
-
-        Ok(Self {
-            internal: Rc::new(internal),
-            name
         })
     }
+
+    /// Vertical metrics of the font at its size
+    pub(crate) fn metrics(&self) -> FontMetrics {
+        FontMetrics {
+            size: self.size,
+            height: self.buffer.height() as f32,
+            ascent: self.buffer.ascent() as f32,
+            descent: self.buffer.descent() as f32,
+            line_spacing: self.buffer.recommended_line_spacing() as f32,
+        }
+    }
+
+    /// Horizontal advance of a character, `None` if the font has no metrics for it
+    pub(crate) fn advance(&self, ch: char) -> Option<f32> {
+        Some(self.buffer.find_glyph_metrics(ch)?.advance as f32)
+    }
+
+    /// Advances of the printable Latin-1 characters the font has, the others are counted as
+    /// wide as their average
+    pub(crate) fn advances(&self) -> GlyphAdvances {
+        let advances: std::collections::HashMap<char, f32> = (' '..='~')
+            .chain('\u{a0}'..='\u{ff}')
+            .filter_map(|c| Some((c, self.advance(c)?)))
+            .collect();
+        let fallback = match advances.len() {
+            0 => self.size / 2.0,
+            count => advances.values().sum::<f32>() / count as f32,
+        };
+        GlyphAdvances { advances, fallback }
+    }
 }
 
 impl DummyFont {
@@ -103,7 +145,28 @@ impl DummyFont {
             inner: InternalDummyFont {
                 path,
                 name,
+                options: FontOptions::default(),
+                metrics: None,
+                advances: GlyphAdvances::default(),
             }
         }
     }
+
+    /// Set the default size and the hinting of the font
+    pub fn with_options(mut self, options: FontOptions) -> Self {
+        self.inner.options = options;
+        self
+    }
+
+    /// Set the metrics and the glyph advances of the font, reported in the
+    /// [`fennel_resources::info::AssetInfo`]
+    pub fn with_metrics(mut self, metrics: FontMetrics, advances: GlyphAdvances) -> Self {
+        self.inner.metrics = Some(metrics);
+        self.inner.advances = advances;
+        self
+    }
+
+    pub(crate) fn inner(&self) -> &InternalDummyFont {
+        &self.inner
+    }
 }
\ No newline at end of file
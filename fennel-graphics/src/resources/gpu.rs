@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use sdl3::render::{Texture, TextureCreator};
+use sdl3::video::WindowContext;
+use fennel_resources::handle::Handle;
+use fennel_resources::manager::ResourceManager;
+use fennel_resources::resource::Resource;
+use crate::resources::font::InternalFont;
+use crate::resources::image::InnerImage;
+
+/// Graphics-thread half of the assets: textures and opened fonts, by asset name
+///
+/// Neither can leave the thread owning the renderer, so they're kept here rather than in the
+/// [`ResourceManager`], which only holds what any thread can read. The manager decides what is
+/// loaded: [`GpuResources::prune`] drops what belongs to assets removed from it.
+pub struct GpuResources {
+    texture_creator: Rc<TextureCreator<WindowContext>>,
+    textures: HashMap<String, Rc<InnerImage>>,
+    /// Textures by the index and generation of the handle of their resource
+    by_handle: HashMap<(u32, u32), Rc<InnerImage>>,
+    /// Fonts by `"name|size"`
+    fonts: HashMap<String, Rc<InternalFont>>,
+}
+
+impl GpuResources {
+    pub(crate) fn new(texture_creator: Rc<TextureCreator<WindowContext>>) -> Self {
+        Self {
+            texture_creator,
+            textures: HashMap::new(),
+            by_handle: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// The texture creator of the renderer
+    pub fn texture_creator(&self) -> &TextureCreator<WindowContext> {
+        &self.texture_creator
+    }
+
+    /// Texture of an image or a sprite sheet
+    pub fn texture(&self, name: &str) -> Option<Rc<RefCell<Texture<'static>>>> {
+        self.textures.get(name).map(|image| image.texture.clone())
+    }
+
+    pub(crate) fn image(&self, name: &str) -> Option<Rc<InnerImage>> {
+        self.textures.get(name).cloned()
+    }
+
+    /// Texture of an image or a sprite sheet by the handle of its resource, the name of the
+    /// resource is only looked up the first time
+    pub(crate) fn handle_image<T: Resource>(&mut self, manager: &ResourceManager, handle: Handle<T>) -> Option<Rc<InnerImage>> {
+        let key = (handle.index(), handle.generation());
+        if let Some(image) = self.by_handle.get(&key) {
+            return Some(image.clone());
+        }
+        let image = self.image(&manager.get(handle).ok()?.name())?;
+        self.by_handle.insert(key, image.clone());
+        Some(image)
+    }
+
+    /// Store the texture of an asset. The previous texture of the asset is replaced in place,
+    /// so the images and textures already handed out draw the new one
+    pub(crate) fn insert_texture(&mut self, name: &str, texture: Texture<'static>, width: u32, height: u32) -> Rc<InnerImage> {
+        if let Some(image) = self.textures.get(name) {
+            image.replace(texture, width, height);
+            return image.clone();
+        }
+        let image = Rc::new(InnerImage::new(texture, width, height));
+        self.textures.insert(name.to_string(), image.clone());
+        image
+    }
+
+    pub(crate) fn font(&self, key: &str) -> Option<Rc<InternalFont>> {
+        self.fonts.get(key).cloned()
+    }
+
+    pub(crate) fn insert_font(&mut self, key: String, font: InternalFont) -> Rc<InternalFont> {
+        let font = Rc::new(font);
+        self.fonts.insert(key, font.clone());
+        font
+    }
+
+    /// Close the fonts opened from a font asset at every size
+    pub(crate) fn forget_font_sizes(&mut self, font: &str) {
+        self.fonts.retain(|key, _| key.rsplit_once('|').is_none_or(|(name, _)| name != font));
+    }
+
+    /// Drop the textures and fonts of the assets that are no longer in the manager
+    pub fn prune(&mut self, manager: &ResourceManager) {
+        let count = self.textures.len();
+        self.textures.retain(|name, _| manager.is_cached(name));
+        if self.textures.len() != count {
+            self.by_handle.clear();
+        }
+        self.fonts.retain(|key, _| key.rsplit_once('|').is_some_and(|(name, _)| manager.is_cached(name)));
+    }
+
+    /// Number of stored textures and fonts
+    pub fn len(&self) -> usize {
+        self.textures.len() + self.fonts.len()
+    }
+
+    /// Is nothing stored?
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty() && self.fonts.is_empty()
+    }
+}
@@ -1,46 +1,103 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{cell::{Cell, RefCell}, io::Cursor, path::{Path, PathBuf}, rc::Rc};
 use std::any::Any;
-use image::ImageReader;
-use sdl3::{pixels::PixelFormat, render::Texture, surface::Surface};
+use anyhow::Context;
+use fennel_resources::vfs;
+use image::{ImageReader, RgbaImage};
+use sdl3::{pixels::PixelFormat, render::{ScaleMode, Texture}, surface::Surface};
+use fennel_resources::info::AssetMetadata;
+use fennel_resources::manifest::{Filter, ImageOptions};
 use fennel_resources::resource::Resource;
 use crate::graphics::Graphics;
+use crate::resources::gpu::GpuResources;
 
-unsafe impl Send for Image {}
-unsafe impl Sync for Image {}
-
-/// Image asset that can be created either from sdl3's `Surface` for rendering fonts, or from a
-/// file for rendering pictures
-#[derive(Clone)]
+/// Image asset: the size and options of a texture owned by the graphics thread
+///
+/// This is the part of an image any thread can read from the
+/// [`fennel_resources::manager::SharedResourceManager`], the texture itself is kept in
+/// [`GpuResources`] under the same name.
+#[derive(Debug, Clone)]
 pub struct Image {
-    inner: Rc<InnerImage>,
-    name: String
+    name: String,
+    width: u32,
+    height: u32,
+    options: ImageOptions,
 }
 
-#[derive(Clone)]
+/// Texture of an image, only usable on the graphics thread
+///
+/// A reloaded image replaces the texture and size of its `InnerImage`, so everything holding
+/// it draws the new texture.
 pub(crate) struct InnerImage {
-    /// SDL3 texture for caching
-    pub(crate) texture: Rc<Texture<'static>>,
+    /// SDL3 texture for caching. Behind a [`RefCell`] as color and alpha modulation are set
+    /// on the texture itself right before drawing
+    pub(crate) texture: Rc<RefCell<Texture<'static>>>,
+    /// Image width and height
+    size: Cell<(u32, u32)>,
+}
+
+impl InnerImage {
+    pub(crate) fn new(texture: Texture<'static>, width: u32, height: u32) -> Self {
+        Self {
+            texture: Rc::new(RefCell::new(texture)),
+            size: Cell::new((width, height)),
+        }
+    }
+
     /// Image width
-    pub(crate) width: u32,
+    pub(crate) fn width(&self) -> u32 {
+        self.size.get().0
+    }
+
     /// Image height
-    pub(crate) height: u32,
+    pub(crate) fn height(&self) -> u32 {
+        self.size.get().1
+    }
+
+    /// Replace the texture, keeping the holders of the image and of its texture
+    pub(crate) fn replace(&self, texture: Texture<'static>, width: u32, height: u32) {
+        *self.texture.borrow_mut() = texture;
+        self.size.set((width, height));
+    }
 }
 
 impl Resource for Image {
     fn data(&self) -> &dyn Any {
-        &self.inner as &dyn Any
+        &self.options as &dyn Any
     }
 
     fn data_mut(&mut self) -> &mut dyn Any {
-        &mut self.inner as &mut dyn Any
+        &mut self.options as &mut dyn Any
     }
 
     fn name(&self) -> String {
         self.name.clone()
     }
+
+    fn memory_usage(&self) -> usize {
+        texture_size(self.width, self.height)
+    }
+
+    fn metadata(&self) -> Option<AssetMetadata> {
+        Some(AssetMetadata::Image { width: self.width, height: self.height })
+    }
+}
+
+/// Size of an RGBA texture in bytes
+pub(crate) fn texture_size(width: u32, height: u32) -> usize {
+    width as usize * height as usize * 4
 }
 
 impl Image {
+    /// Describe a texture stored in [`GpuResources`]
+    pub(crate) fn new(name: String, width: u32, height: u32, options: ImageOptions) -> Self {
+        Self {
+            name,
+            width,
+            height,
+            options,
+        }
+    }
+
     pub fn load_from_surface(
         name: String,
         graphics: &mut Graphics,
@@ -53,16 +110,8 @@ impl Image {
                     .create_texture_from_surface(&surface)?,
             )
         };
-        let inner = InnerImage {
-            texture: Rc::new(texture),
-            width: surface.width(),
-            height: surface.height(),
-        };
-
-        Ok(Self {
-            name,
-            inner: Rc::new(inner),
-        })
+        graphics.gpu.insert_texture(&name, texture, surface.width(), surface.height());
+        Ok(Self::new(name, surface.width(), surface.height(), ImageOptions::default()))
     }
 
     pub fn load(
@@ -70,47 +119,88 @@ impl Image {
         name: String,
         graphics: &mut Graphics,
     ) -> anyhow::Result<Self> {
-        let img = ImageReader::open(&path)?.decode()?;
-        let mut buffer = img.to_rgba8().into_raw();
+        Self::from_rgba(name, Self::decode(&path)?, ImageOptions::default(), &mut graphics.gpu)
+    }
+
+    /// Read an image file through the VFS and decode it, without touching SDL so it can run
+    /// on any thread
+    pub fn decode(path: &Path) -> anyhow::Result<RgbaImage> {
+        let data = vfs::read(path)
+            .with_context(|| format!("failed to read image {}", path.display()))?;
+        let image = ImageReader::new(Cursor::new(data))
+            .with_guessed_format()?
+            .decode()
+            .with_context(|| format!("failed to decode image {}", path.display()))?;
+        Ok(image.into_rgba8())
+    }
+
+    /// Multiply the color of every pixel by its alpha, for
+    /// [`ImageOptions::premultiplied`] textures
+    pub fn premultiply(image: &mut RgbaImage) {
+        for pixel in image.pixels_mut() {
+            let alpha = pixel[3] as u16;
+            for channel in &mut pixel.0[..3] {
+                *channel = ((*channel as u16 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+
+    /// Upload decoded pixels to a texture stored in `gpu`, on the thread owning the renderer
+    ///
+    /// The pixels of a `premultiplied` image must have been premultiplied with
+    /// [`Image::premultiply`].
+    pub fn from_rgba(
+        name: String,
+        image: RgbaImage,
+        options: ImageOptions,
+        gpu: &mut GpuResources,
+    ) -> anyhow::Result<Self> {
+        let (width, height) = image.dimensions();
+        let mut buffer = image.into_raw();
         let surface = Surface::from_data(
             &mut buffer,
-            img.width(),
-            img.height(),
-            img.width() * 4,
+            width,
+            height,
+            width * 4,
             PixelFormat::RGBA32,
         )?;
 
-        let texture = unsafe {
+        let mut texture = unsafe {
             std::mem::transmute::<Texture<'_>, Texture<'static>>(
-                graphics
-                    .texture_creator
-                    .create_texture_from_surface(surface)?,
+                gpu.texture_creator().create_texture_from_surface(surface)?,
             )
         };
-        let width = texture.width();
-        let height = texture.height();
+        texture.set_scale_mode(match options.filter {
+            Filter::Linear => ScaleMode::Linear,
+            Filter::Nearest => ScaleMode::Nearest,
+        });
+        if options.premultiplied {
+            // not exposed by `BlendMode`
+            let blended = unsafe {
+                sdl3::sys::render::SDL_SetTextureBlendMode(
+                    texture.raw(),
+                    sdl3::sys::blendmode::SDL_BLENDMODE_BLEND_PREMULTIPLIED,
+                )
+            };
+            if !blended {
+                anyhow::bail!("failed to set the blend mode of {name}: {}", sdl3::get_error());
+            }
+        }
 
-        let inner = InnerImage {
-            texture: Rc::new(texture),
-            width,
-            height,
-        };
-
-        Ok(Self {
-            name,
-            inner: Rc::new(inner),
-        })
+        gpu.insert_texture(&name, texture, width, height);
+        Ok(Self::new(name, width, height, options))
     }
 
     pub fn height(&self) -> u32 {
-        self.inner.height
+        self.height
     }
 
     pub fn width(&self) -> u32 {
-        self.inner.width
+        self.width
     }
-    
-    pub fn texture(&self) -> Rc<Texture<'static>> {
-        self.inner.texture.clone()
+
+    /// Options the image was loaded with
+    pub fn options(&self) -> &ImageOptions {
+        &self.options
     }
-}
\ No newline at end of file
+}
@@ -1,71 +1,403 @@
-use std::{fs, path::PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use anyhow::Context;
-use log::debug;
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info};
+use fennel_resources::loader::{LoadFailure, LoadProgress, DEFAULT_GROUP};
+use fennel_resources::handle::KeepAlive;
+use fennel_resources::info::{FontMetrics, GlyphAdvances};
 use fennel_resources::manager::ResourceManager;
-use fennel_resources::resource::Resource;
+use fennel_resources::manifest::{FontOptions, ImageOptions, Manifest, ManifestAsset, SpriteSheetOptions};
+use fennel_resources::vfs;
+use sdl3::ttf::Sdl3TtfContext;
 use crate::{
     graphics::Graphics,
-    resources::{font::DummyFont, image::Image},
+    resources::{font::{DummyFont, InternalFont, METRICS_SIZE}, gpu::GpuResources, image::Image, sprite_sheet::{Grid, SpriteSheet}},
 };
 
 pub mod font;
+pub mod gpu;
 pub mod image;
+pub mod sprite_sheet;
 
-#[derive(Deserialize, Serialize, Debug)]
-enum AssetType {
-    Image,
+/// An asset of a manifest, with its options read
+#[derive(Debug, Clone)]
+struct Asset {
+    name: String,
+    /// Path of the file
+    path: PathBuf,
+    /// Group the asset is loaded with, assets without a group are loaded at startup
+    group: Option<String>,
+    kind: AssetKind,
+}
+
+#[derive(Debug, Clone)]
+enum AssetKind {
+    Image(ImageOptions),
     Audio,
-    Font,
+    Font(FontOptions),
+    SpriteSheet {
+        /// Grid to slice the sheet with
+        grid: Option<Grid>,
+        /// Path to the Aseprite/TexturePacker JSON atlas
+        atlas: Option<PathBuf>,
+        image: ImageOptions,
+    },
+    /// Loaded by a registered [`fennel_resources::asset_loader::AssetLoader`], looked up when the
+    /// asset is queued
+    Custom(ManifestAsset),
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-/// Manifest structure of an asset presented in manifest
-struct Asset {
-    name: String,
-    path: String,
-    #[serde(rename(deserialize = "type"))]
-    class: AssetType,
+impl Asset {
+    /// Read the options of a manifest asset according to its type, the types other than the
+    /// built-in ones are left to their loader
+    fn new(asset: &ManifestAsset) -> anyhow::Result<Self> {
+        let entry = &asset.entry;
+        let kind = match entry.class.as_str() {
+            "Image" => AssetKind::Image(entry.options()?),
+            // played by `fennel-audio`, which reads the same manifest
+            "Audio" => AssetKind::Audio,
+            "Font" => AssetKind::Font(entry.options()?),
+            "SpriteSheet" => {
+                let options: SpriteSheetOptions = entry.options()?;
+                AssetKind::SpriteSheet {
+                    grid: options.grid,
+                    atlas: options.atlas.as_deref().map(|atlas| asset.resolve(atlas)),
+                    image: options.image(),
+                }
+            }
+            _ => AssetKind::Custom(asset.clone()),
+        };
+        Ok(Self { name: entry.name.clone(), path: asset.path(), group: entry.group.clone(), kind })
+    }
 }
 
-#[derive(Deserialize, Debug)]
-/// Assets package manifest
-struct Manifest {
-    pub assets: Vec<Asset>,
+/// Assets read from manifests by group, with the directory given to [`queue_dir`]
+#[derive(Default)]
+pub(crate) struct Catalog {
+    groups: HashMap<String, Vec<(PathBuf, Asset)>>,
+    /// Directories given to [`queue_dir`] - manifest files read for them, with their modification
+    /// time when they were read
+    manifests: HashMap<PathBuf, Vec<(PathBuf, Option<SystemTime>)>>,
+    /// Groups queued and not unloaded since
+    active: HashSet<String>,
+    /// References keeping the assets of the active groups loaded, by group
+    held: HashMap<String, Vec<KeepAlive>>,
+    /// Custom assets queued before a loader of their type was registered, by name with their
+    /// group
+    waiting: HashMap<String, (String, Asset)>,
 }
 
+impl Catalog {
+    /// Find an asset by name
+    fn asset(&self, name: &str) -> Option<(&str, &Asset)> {
+        self.groups.iter().find_map(|(group, assets)| {
+            assets
+                .iter()
+                .find(|(_, asset)| asset.name == name)
+                .map(|(_, asset)| (group.as_str(), asset))
+        })
+    }
+}
+
+/// Load every asset of the directory's `manifest.toml`, blocking until they're loaded. Grouped
+/// assets are only loaded by [`queue_group`]
 pub fn load_dir(resource_manager: &mut ResourceManager, path_buf: PathBuf, graphics: &mut Graphics) -> anyhow::Result<()> {
-    let manifest_file = fs::read(path_buf.join("manifest.toml"))
-        .context("failed to read manifest.toml")?;
-
-    let manifest: Manifest = toml::from_slice(&manifest_file)
-        .context("failed to parse manifest.toml")?;
-
-    for asset in manifest.assets {
-        debug!("loading asset '{}' with class {:?}", asset.name, asset.class);
-
-        let asset_path = path_buf.join(asset.path);
-
-        match asset.class {
-            AssetType::Image => {
-                let image = Image::load(
-                    asset_path.clone(),
-                    asset.name,
-                    graphics
-                )?;
-                println!("inserting {}", image.name());
-                resource_manager.insert(image);
+    queue_dir(resource_manager, path_buf, graphics)?;
+    let failures = graphics.loader.wait(resource_manager, &mut graphics.gpu);
+    if let Some(failure) = failures.first() {
+        anyhow::bail!("failed to load asset '{}': {}", failure.name, failure.error);
+    }
+    Ok(())
+}
+
+/// Read the directory's `manifest.toml` and start loading its assets in the background
+///
+/// The directory can be a VFS path such as `assets://`, asset paths are relative to it. See
+/// [`fennel_resources::manifest`] for the format.
+///
+/// Assets with a `group` are only registered, to be loaded with [`queue_group`]. The others go
+/// into the [`DEFAULT_GROUP`].
+pub fn queue_dir(resource_manager: &mut ResourceManager, path_buf: PathBuf, graphics: &mut Graphics) -> anyhow::Result<()> {
+    read_manifest(&path_buf, graphics)?;
+    queue_group(resource_manager, DEFAULT_GROUP, graphics)
+}
+
+/// Read the manifest of a directory and its includes into the catalog, replacing what they
+/// previously declared
+fn read_manifest(path_buf: &Path, graphics: &mut Graphics) -> anyhow::Result<()> {
+    let manifest = Manifest::read(path_buf)
+        .with_context(|| format!("failed to read the manifest of {}", path_buf.display()))?;
+    let assets = manifest.assets.iter().map(Asset::new).collect::<anyhow::Result<Vec<_>>>()?;
+
+    for assets in graphics.catalog.groups.values_mut() {
+        assets.retain(|(root, _)| root != path_buf);
+    }
+    for asset in assets {
+        let group = asset.group.clone().unwrap_or_else(|| DEFAULT_GROUP.to_string());
+        graphics.catalog.groups.entry(group).or_default().push((path_buf.to_path_buf(), asset));
+    }
+    let files = manifest.files.into_iter().map(|file| {
+        let modified = vfs::modified(&file);
+        (file, modified)
+    });
+    graphics.catalog.manifests.insert(path_buf.to_path_buf(), files.collect());
+    Ok(())
+}
+
+/// Start loading the assets of a manifest group in the background, skipping the ones that are
+/// already loaded or loading
+///
+/// Assets of the [`DEFAULT_GROUP`] are kept until they're removed. The assets of other groups are
+/// reference counted and kept loaded while the group is, see [`unload_group`].
+///
+/// # Errors
+/// If no manifest has this group
+pub fn queue_group(resource_manager: &mut ResourceManager, group: &str, graphics: &mut Graphics) -> anyhow::Result<()> {
+    let assets = graphics.catalog.groups
+        .get(group)
+        .with_context(|| format!("no asset group named '{group}'"))?
+        .clone();
+    graphics.catalog.active.insert(group.to_string());
+    for (_, asset) in assets {
+        let name = asset.name.clone();
+        if !resource_manager.is_cached(&name) {
+            queue_asset(resource_manager, asset, group, graphics);
+        }
+        hold(resource_manager, &name, group, graphics);
+    }
+    Ok(())
+}
+
+/// Keep an asset of a group other than the [`DEFAULT_GROUP`] loaded while the group is active
+fn hold(resource_manager: &mut ResourceManager, name: &str, group: &str, graphics: &mut Graphics) {
+    if group == DEFAULT_GROUP {
+        return;
+    }
+    // audio isn't stored in the resource manager
+    let Ok(keep_alive) = resource_manager.keep_alive(name) else { return };
+    resource_manager.release(name).ok();
+    graphics.catalog.held.entry(group.to_string()).or_default().push(keep_alive);
+}
+
+/// Stop keeping the assets of a manifest group loaded, and remove the assets nothing else
+/// references anymore. An asset still referenced, for example by a strong handle or by another
+/// group loaded since, stays loaded
+pub fn unload_group(resource_manager: &mut ResourceManager, group: &str, graphics: &mut Graphics) {
+    graphics.loader.forget_group(group);
+    graphics.catalog.active.remove(group);
+    graphics.catalog.held.remove(group);
+    graphics.catalog.waiting.retain(|_, (waiting_group, _)| waiting_group != group);
+    for name in resource_manager.collect_unused() {
+        debug!("unloading asset '{name}'");
+    }
+    graphics.gpu.prune(resource_manager);
+}
+
+fn queue_asset(resource_manager: &mut ResourceManager, asset: Asset, group: &str, graphics: &mut Graphics) {
+    if let AssetKind::Custom(manifest_asset) = &asset.kind {
+        if graphics.asset_loaders.for_asset(manifest_asset).is_none() {
+            // the plugin registering its loader may not be prepared yet
+            debug!("asset '{}' waits for a loader of its type", asset.name);
+            graphics.catalog.waiting.insert(asset.name.clone(), (group.to_string(), asset));
+            return;
+        }
+    }
+    debug!("loading asset '{}' of kind {:?}", asset.name, asset.kind);
+
+    let (name, asset_path) = (asset.name, asset.path);
+    match asset.kind {
+        AssetKind::Image(options) => {
+            request_image(resource_manager, &name, asset_path, options, group, graphics);
+        }
+        AssetKind::Audio => {}
+        AssetKind::Font(options) => {
+            // fonts are opened again on first use at other sizes, but measured once loaded
+            let modified = vfs::modified(&asset_path);
+            let source = asset_path.clone();
+            let ttf_context = graphics.ttf_context.clone();
+            graphics.loader.request(
+                resource_manager,
+                &name.clone(),
+                group,
+                {
+                    let path = asset_path.clone();
+                    move || InternalFont::read(&path)
+                },
+                {
+                    let name = name.clone();
+                    move |data, gpu| {
+                        let (metrics, advances) = measure_font(&name, &asset_path, data, options, &ttf_context, gpu)?;
+                        Ok(DummyFont::new(asset_path, name).with_options(options).with_metrics(metrics, advances))
+                    }
+                },
+            );
+            resource_manager.set_source(&name, source, modified);
+        }
+        AssetKind::SpriteSheet { grid, atlas, image } => {
+            let modified = vfs::modified(&asset_path);
+            let source = asset_path.clone();
+            graphics.loader.request(
+                resource_manager,
+                &name.clone(),
+                group,
+                {
+                    let name = name.clone();
+                    move || SpriteSheet::read(&asset_path, &name, grid, atlas.as_deref(), image)
+                },
+                {
+                    let name = name.clone();
+                    move |sheet, gpu| SpriteSheet::from_data(name, sheet, gpu)
+                },
+            );
+            resource_manager.set_source(&name, source, modified);
+        }
+        AssetKind::Custom(manifest_asset) => {
+            if let Some(loader) = graphics.asset_loaders.for_asset(&manifest_asset) {
+                let modified = vfs::modified(&asset_path);
+                graphics.loader.request_asset(resource_manager, &manifest_asset, group, loader);
+                resource_manager.set_source(&name, asset_path, modified);
             }
-            AssetType::Audio => {
+        }
+    }
+}
+
+/// Queue the custom assets whose loader was registered since they were queued
+pub(crate) fn queue_waiting(resource_manager: &mut ResourceManager, graphics: &mut Graphics) {
+    let ready: Vec<String> = graphics.catalog.waiting
+        .iter()
+        .filter(|(_, (_, asset))| match &asset.kind {
+            AssetKind::Custom(manifest_asset) => graphics.asset_loaders.for_asset(manifest_asset).is_some(),
+            _ => true,
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in ready {
+        let Some((group, asset)) = graphics.catalog.waiting.remove(&name) else { continue };
+        queue_asset(resource_manager, asset, &group, graphics);
+        hold(resource_manager, &name, &group, graphics);
+    }
+}
 
+/// Progress of every group, counting the assets waiting for their loader as requested
+pub(crate) fn progress(graphics: &Graphics) -> HashMap<String, LoadProgress> {
+    let mut groups = graphics.loader.groups().clone();
+    for (group, _) in graphics.catalog.waiting.values() {
+        groups.entry(group.clone()).or_default().total += 1;
+    }
+    groups
+}
+
+/// Open a font read by a loader worker at its default size, or at [`METRICS_SIZE`], and measure
+/// it and its glyphs. The opened font is kept for drawing
+fn measure_font(
+    name: &str,
+    path: &Path,
+    data: Vec<u8>,
+    options: FontOptions,
+    ttf_context: &Sdl3TtfContext,
+    gpu: &mut GpuResources,
+) -> anyhow::Result<(FontMetrics, GlyphAdvances)> {
+    let size = options.size.unwrap_or(METRICS_SIZE);
+    let font = InternalFont::from_data(path.to_path_buf(), data, ttf_context, size, options.hinting)?;
+    let (metrics, advances) = (font.metrics(), font.advances());
+    gpu.insert_font(format!("{name}|{size}"), font);
+    Ok((metrics, advances))
+}
+
+/// Start loading an image file in the background and remember where it comes from
+pub(crate) fn request_image(resource_manager: &mut ResourceManager, name: &str, path: PathBuf, options: ImageOptions, group: &str, graphics: &mut Graphics) {
+    let modified = vfs::modified(&path);
+    let (source, owned_name) = (path.clone(), name.to_string());
+    graphics.loader.request(
+        resource_manager,
+        name,
+        group,
+        move || {
+            let mut image = Image::decode(&path)?;
+            if options.premultiplied {
+                Image::premultiply(&mut image);
+            }
+            Ok(image)
+        },
+        move |image, gpu| Image::from_rgba(owned_name, image, options, gpu),
+    );
+    resource_manager.set_source(name, source, modified);
+}
+
+/// Reload the assets whose files changed since they were loaded, and load the assets added to
+/// the manifests of the active groups
+///
+/// Reloaded resources replace the old ones under the same name, so names and handles pick
+/// them up. The old resource stays in use until the new one is loaded, or if it fails to load.
+/// A manifest that fails to read is logged and its previous assets are kept.
+pub fn reload_changed(resource_manager: &mut ResourceManager, graphics: &mut Graphics) -> anyhow::Result<()> {
+    let changed_manifests: Vec<PathBuf> = graphics.catalog.manifests
+        .iter()
+        .filter(|(_, files)| files.iter().any(|(file, modified)| {
+            let current = vfs::modified(file);
+            current.is_some() && current != *modified
+        }))
+        .map(|(root, _)| root.clone())
+        .collect();
+    for root in changed_manifests {
+        info!("reloading the manifest of {}", root.display());
+        if let Err(e) = read_manifest(&root, graphics) {
+            error!("keeping the previous manifest of {}: {e:#}", root.display());
+            // the broken files are reported once, and read again when they change
+            if let Some(files) = graphics.catalog.manifests.get_mut(&root) {
+                for (file, modified) in files.iter_mut() {
+                    *modified = vfs::modified(file);
+                }
             }
-            AssetType::Font => {
-                println!("{:?} - {}", asset_path.clone(), asset.name.clone());
-                let font = DummyFont::new(asset_path, asset.name);
-                resource_manager.insert(font);
+            continue;
+        }
+        let mut queued = Vec::new();
+        for group in &graphics.catalog.active {
+            for (_, asset) in graphics.catalog.groups.get(group).into_iter().flatten() {
+                let moved = resource_manager.source(&asset.name).is_some_and(|source| source.path != asset.path);
+                if !resource_manager.is_cached(&asset.name) {
+                    queued.push((asset.clone(), group.clone(), true));
+                } else if moved {
+                    queued.push((asset.clone(), group.clone(), false));
+                }
+            }
+        }
+        for (asset, group, added) in queued {
+            let name = asset.name.clone();
+            queue_asset(resource_manager, asset, &group, graphics);
+            if added {
+                hold(resource_manager, &name, &group, graphics);
             }
         }
     }
 
+    for (name, path) in resource_manager.changed_sources(|path| vfs::modified(path)) {
+        info!("reloading asset '{name}' from {}", path.display());
+        match graphics.catalog.asset(&name) {
+            Some((group, asset)) => {
+                let (group, asset) = (group.to_string(), asset.clone());
+                if matches!(asset.kind, AssetKind::Font(_)) {
+                    forget_font_sizes(&name, graphics);
+                }
+                queue_asset(resource_manager, asset, &group, graphics);
+            }
+            // images drawn by path rather than declared in a manifest
+            None => request_image(resource_manager, &name, path, ImageOptions::default(), DEFAULT_GROUP, graphics),
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Remove the fonts opened from a font asset at every size, and the text rendered with them
+fn forget_font_sizes(font: &str, graphics: &mut Graphics) {
+    graphics.gpu.forget_font_sizes(font);
+    graphics.text_cache.clear();
+}
+
+/// Log assets that failed to load
+pub(crate) fn log_failures(failures: &[LoadFailure]) {
+    for failure in failures {
+        error!("failed to load asset '{}' of group '{}': {}", failure.name, failure.group, failure.error);
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::any::Any;
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use image::RgbaImage;
+use fennel_resources::info::AssetMetadata;
+use fennel_resources::manifest::ImageOptions;
+pub use fennel_resources::manifest::Grid;
+use fennel_resources::resource::Resource;
+use fennel_resources::vfs;
+use crate::resources::gpu::GpuResources;
+use crate::resources::image::{texture_size, Image};
+
+/// Sprite sheet asset: one texture sliced into named frames.
+///
+/// A frame is referenced from [`crate::graphics::Sprite::image`] as `"sheet:frame"`. The sheet
+/// texture is kept in [`GpuResources`] under the name of the sheet.
+pub struct SpriteSheet {
+    name: String,
+    /// Size of the whole sheet
+    size: (u32, u32),
+    /// Frame name - (x, y, w, h) rectangle in pixels
+    frames: HashMap<String, (f32, f32, f32, f32)>,
+}
+
+/// Aseprite/TexturePacker JSON atlas, either in the "hash" or the "array" flavor
+#[derive(Deserialize, Debug)]
+struct Atlas {
+    frames: AtlasFrames,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum AtlasFrames {
+    Hash(HashMap<String, AtlasFrame>),
+    Array(Vec<NamedAtlasFrame>),
+}
+
+#[derive(Deserialize, Debug)]
+struct AtlasFrame {
+    frame: AtlasRect,
+}
+
+#[derive(Deserialize, Debug)]
+struct NamedAtlasFrame {
+    filename: String,
+    frame: AtlasRect,
+}
+
+#[derive(Deserialize, Debug)]
+struct AtlasRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl Resource for SpriteSheet {
+    fn data(&self) -> &dyn Any {
+        &self.frames as &dyn Any
+    }
+
+    fn data_mut(&mut self) -> &mut dyn Any {
+        &mut self.frames as &mut dyn Any
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn memory_usage(&self) -> usize {
+        texture_size(self.size.0, self.size.1)
+    }
+
+    fn metadata(&self) -> Option<AssetMetadata> {
+        Some(AssetMetadata::SpriteSheet { width: self.size.0, height: self.size.1, frames: self.frames.clone() })
+    }
+}
+
+impl SpriteSheet {
+    /// Get the rectangle of a frame
+    pub fn frame(&self, name: &str) -> Option<(f32, f32, f32, f32)> {
+        self.frames.get(name).copied()
+    }
+
+    /// Size of the whole sheet in pixels
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+/// Decoded sprite sheet, not uploaded to the GPU yet
+pub(crate) struct SheetData {
+    image: RgbaImage,
+    frames: HashMap<String, (f32, f32, f32, f32)>,
+    options: ImageOptions,
+}
+
+impl SpriteSheet {
+    /// Decode the image and slice it either by a `grid` or by a JSON `atlas`, without touching SDL so it can run on any thread
+    pub(crate) fn read(path: &Path, name: &str, grid: Option<Grid>, atlas: Option<&Path>, options: ImageOptions) -> anyhow::Result<SheetData> {
+        let mut image = Image::decode(path)?;
+        if options.premultiplied {
+            Image::premultiply(&mut image);
+        }
+        let frames = match (atlas, grid) {
+            (Some(atlas), _) => {
+                let atlas = vfs::read(atlas)
+                    .with_context(|| format!("failed to read atlas {}", atlas.display()))?;
+                parse_atlas(&atlas)?
+            }
+            (None, Some(grid)) => slice_grid(image.width(), image.height(), &grid),
+            (None, None) => bail!("sprite sheet '{name}' needs either a grid or an atlas"),
+        };
+        Ok(SheetData { image, frames, options })
+    }
+
+    /// Upload a decoded sprite sheet to a texture stored in `gpu`, on the thread owning the
+    /// renderer
+    pub(crate) fn from_data(
+        name: String,
+        data: SheetData,
+        gpu: &mut GpuResources,
+    ) -> anyhow::Result<Self> {
+        let image = Image::from_rgba(name.clone(), data.image, data.options, gpu)?;
+        Ok(Self {
+            name,
+            size: (image.width(), image.height()),
+            frames: data.frames,
+        })
+    }
+}
+
+/// Slice an image of `width`x`height` pixels into grid cells
+pub(crate) fn slice_grid(width: u32, height: u32, grid: &Grid) -> HashMap<String, (f32, f32, f32, f32)> {
+    let (cell_w, cell_h) = grid.cell_size;
+    let mut frames = HashMap::new();
+    if cell_w == 0 || cell_h == 0 {
+        return frames;
+    }
+
+    let columns = (width.saturating_sub(grid.margin * 2) + grid.spacing) / (cell_w + grid.spacing);
+    let rows = (height.saturating_sub(grid.margin * 2) + grid.spacing) / (cell_h + grid.spacing);
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = grid.margin + column * (cell_w + grid.spacing);
+            let y = grid.margin + row * (cell_h + grid.spacing);
+            frames.insert(
+                (row * columns + column).to_string(),
+                (x as f32, y as f32, cell_w as f32, cell_h as f32),
+            );
+        }
+    }
+    frames
+}
+
+/// Parse the frames of an Aseprite/TexturePacker JSON atlas
+pub(crate) fn parse_atlas(data: &[u8]) -> anyhow::Result<HashMap<String, (f32, f32, f32, f32)>> {
+    let atlas: Atlas = serde_json::from_slice(data).context("failed to parse atlas")?;
+    let frames = match atlas.frames {
+        AtlasFrames::Hash(frames) => frames
+            .into_iter()
+            .map(|(name, frame)| (name, frame.frame))
+            .collect::<Vec<_>>(),
+        AtlasFrames::Array(frames) => frames
+            .into_iter()
+            .map(|frame| (frame.filename, frame.frame))
+            .collect(),
+    };
+    Ok(frames
+        .into_iter()
+        .map(|(name, rect)| (name, (rect.x, rect.y, rect.w, rect.h)))
+        .collect())
+}
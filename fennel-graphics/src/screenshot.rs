@@ -0,0 +1,129 @@
+//! Reading rendered frames back into images
+//!
+//! Combined with a headless [`Graphics`] (see [`crate::graphics::WindowConfig::is_headless`])
+//! this allows golden-image tests without a display:
+//!
+//! ```ignore
+//! graphics.render_views(&views);
+//! let frame = graphics.screenshot()?;
+//! screenshot::assert_golden(&frame, "tests/golden/menu.png", 2)?;
+//! ```
+
+use std::path::Path;
+use anyhow::Context;
+use image::RgbaImage;
+use sdl3::pixels::PixelFormat;
+use crate::graphics::Graphics;
+
+/// Environment variable that makes [`assert_golden`] overwrite the golden images
+pub const UPDATE_GOLDEN_VAR: &str = "FENNEL_UPDATE_GOLDEN";
+
+impl Graphics {
+    /// Read the current frame of the canvas, call it before presenting the canvas
+    pub fn screenshot(&self) -> anyhow::Result<RgbaImage> {
+        let surface = self.canvas
+            .read_pixels(None)
+            .map_err(|e| anyhow::anyhow!("failed to read pixels: {e}"))?
+            .convert_format(PixelFormat::RGBA32)?;
+        let (width, height) = (surface.width(), surface.height());
+        let pitch = surface.pitch() as usize;
+        let row = width as usize * 4;
+        // rows of the surface can be padded, the image rows can't
+        let pixels = surface.with_lock(|pixels| {
+            pixels
+                .chunks(pitch)
+                .take(height as usize)
+                .flat_map(|line| &line[..row])
+                .copied()
+                .collect::<Vec<u8>>()
+        });
+        RgbaImage::from_raw(width, height, pixels).context("pixel buffer doesn't match the canvas size")
+    }
+
+    /// Save the current frame of the canvas, the format is guessed from the extension of `path`
+    pub fn save_screenshot(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        self.screenshot()?.save(path)?;
+        Ok(())
+    }
+}
+
+/// Difference between two images of the same size
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Number of pixels with a channel differing by more than the tolerance
+    pub differing_pixels: usize,
+    /// Largest difference of a channel over all pixels
+    pub max_difference: u8,
+}
+
+/// Compare two images channel by channel, ignoring differences up to `tolerance`
+///
+/// # Errors
+/// If the images don't have the same size
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> anyhow::Result<ImageDiff> {
+    if actual.dimensions() != expected.dimensions() {
+        anyhow::bail!(
+            "image is {:?} but {:?} was expected",
+            actual.dimensions(),
+            expected.dimensions()
+        );
+    }
+    let mut diff = ImageDiff::default();
+    for (a, b) in actual.pixels().zip(expected.pixels()) {
+        let difference = a.0.iter().zip(b.0).map(|(a, b)| a.abs_diff(b)).max().unwrap_or(0);
+        diff.max_difference = diff.max_difference.max(difference);
+        if difference > tolerance {
+            diff.differing_pixels += 1;
+        }
+    }
+    Ok(diff)
+}
+
+/// Check an image against the golden image at `path`
+///
+/// The golden image is written instead if the [`UPDATE_GOLDEN_VAR`] environment variable is
+/// set, which is also how new golden images are created. On a mismatch the image is saved next
+/// to the golden one with an `.actual.png` extension, for inspection.
+///
+/// # Errors
+/// If the images differ, or if the golden image doesn't exist and isn't being updated
+pub fn assert_golden(image: &RgbaImage, path: impl AsRef<Path>, tolerance: u8) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        image.save(path)?;
+        return Ok(());
+    }
+    if !path.exists() {
+        let actual = path.with_extension("actual.png");
+        image.save(&actual)?;
+        anyhow::bail!(
+            "golden image {} doesn't exist, the image was saved to {}. Run with {UPDATE_GOLDEN_VAR}=1 to create it",
+            path.display(),
+            actual.display()
+        );
+    }
+
+    let expected = image::open(path)
+        .with_context(|| format!("failed to open golden image {}", path.display()))?
+        .into_rgba8();
+    let diff = compare_images(image, &expected, tolerance)
+        .with_context(|| format!("image doesn't match {}", path.display()))?;
+    if diff.differing_pixels > 0 {
+        let actual = path.with_extension("actual.png");
+        image.save(&actual)?;
+        anyhow::bail!(
+            "{} pixels differ from {} by up to {}, the image was saved to {}",
+            diff.differing_pixels,
+            path.display(),
+            diff.max_difference,
+            actual.display()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Triangulation of primitive shapes
+//!
+//! Every function returns a triangle list, three points per triangle, ready to be fed to
+//! [`sdl3::render::Canvas::render_geometry`].
+
+use std::f32::consts::TAU;
+
+/// Fewest segments a circle is approximated with
+const MIN_CIRCLE_SEGMENTS: usize = 12;
+/// Most segments a circle is approximated with
+const MAX_CIRCLE_SEGMENTS: usize = 128;
+
+/// Points on the outline of a circle, counter-clockwise starting at angle 0
+///
+/// Bigger circles get more segments so that they stay round.
+pub fn circle_points(center: (f32, f32), radius: f32) -> Vec<(f32, f32)> {
+    let segments = (radius.abs().sqrt() * 4.0) as usize;
+    let segments = segments.clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS);
+    (0..segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * TAU;
+            (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Corners of a rectangle in clockwise order, starting at the top-left one
+pub fn rect_points(x: f32, y: f32, w: f32, h: f32) -> Vec<(f32, f32)> {
+    vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h)]
+}
+
+/// Fill a polygon as a triangle fan around its first point
+///
+/// Exact for convex polygons, concave ones have to be fanned around a point that sees every
+/// other point.
+pub fn triangle_fan(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let Some(&first) = points.first() else { return Vec::new() };
+    points
+        .windows(2)
+        .skip(1)
+        .flat_map(|pair| [first, pair[0], pair[1]])
+        .collect()
+}
+
+/// Outline of a polyline with every segment turned into a quad `thickness` wide
+///
+/// The quads are centered on the segments and a square is put on every joint so that thick
+/// lines don't have gaps on corners.
+pub fn thick_polyline(points: &[(f32, f32)], closed: bool, thickness: f32) -> Vec<(f32, f32)> {
+    let half = thickness / 2.0;
+    let mut segments: Vec<((f32, f32), (f32, f32))> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+    if closed && points.len() > 2 {
+        segments.push((points[points.len() - 1], points[0]));
+    }
+
+    let mut triangles = Vec::with_capacity(segments.len() * 12);
+    for (from, to) in segments {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= f32::EPSILON {
+            continue;
+        }
+        let normal = (-dy / length * half, dx / length * half);
+        let a = (from.0 + normal.0, from.1 + normal.1);
+        let b = (to.0 + normal.0, to.1 + normal.1);
+        let c = (to.0 - normal.0, to.1 - normal.1);
+        let d = (from.0 - normal.0, from.1 - normal.1);
+        triangles.extend([a, b, c, a, c, d]);
+    }
+
+    let joints = if closed { points } else { points.get(1..points.len().saturating_sub(1)).unwrap_or(&[]) };
+    for &(x, y) in joints {
+        triangles.extend(triangle_fan(&rect_points(x - half, y - half, thickness, thickness)));
+    }
+    triangles
+}
+
+/// Corners of a rectangle rotated by `degrees` clockwise around `pivot`, in clockwise order
+/// starting at the corner that is the top-left one before the rotation
+///
+/// Matches how [`sdl3::render::Canvas::copy_ex`] rotates its destination rectangle.
+pub fn rotated_rect(rect: (f32, f32, f32, f32), pivot: (f32, f32), degrees: f64) -> [(f32, f32); 4] {
+    let (x, y, w, h) = rect;
+    let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+    if degrees == 0.0 {
+        return corners;
+    }
+    let (sin, cos) = (degrees as f32).to_radians().sin_cos();
+    corners.map(|(cx, cy)| {
+        let (dx, dy) = (cx - pivot.0, cy - pivot.1);
+        (pivot.0 + dx * cos - dy * sin, pivot.1 + dx * sin + dy * cos)
+    })
+}
+
+/// Axis-aligned bounding box (x, y, w, h) of points, `None` if there are none
+pub fn bounding_box(points: impl IntoIterator<Item = (f32, f32)>) -> Option<(f32, f32, f32, f32)> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let (min, max) = points.fold((first, first), |(min, max), (x, y)| {
+        ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+    });
+    Some((min.0, min.1, max.0 - min.0, max.1 - min.1))
+}
+
+/// Do two rectangles (x, y, w, h) overlap?
+pub fn overlaps(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> bool {
+    a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+}
@@ -0,0 +1,620 @@
+use specs::{Builder, RunNow, World, WorldExt};
+use fennel_resources::info::{AssetInfo, FontMetrics, GlyphAdvances};
+use fennel_resources::manager::{ResourceManager, SharedResourceManager};
+use sdl3::render::FRect;
+use crate::graphics::{sprite_rects, Drawable, GraphicsBuilder, Sprite, SpriteBatch, SpriteImage, Transform, DEFAULT_LAYER};
+use crate::plugin::debug_overlay::{DebugOverlay, DEBUG_LAYER};
+use crate::plugin::system::{AssetReport, AssetRequest, AssetSystem, Assets, Camera, CameraView, QueuedRenderingSystem, RenderLayers, RenderQueue, WindowSize};
+use crate::screenshot::{self, compare_images, ImageDiff};
+use crate::shapes;
+use crate::text::{self, TextAlign, TextLayout};
+use crate::text_cache::{LruCache, ShelfPacker};
+use crate::resources::font::DummyFont;
+use crate::resources::image::Image;
+use crate::resources::sprite_sheet::{parse_atlas, slice_grid, Grid, SpriteSheet};
+
+#[test]
+fn premultiplied_pixels() {
+	let mut image = image::RgbaImage::from_raw(2, 1, vec![200, 100, 50, 128, 10, 20, 30, 255]).unwrap();
+	Image::premultiply(&mut image);
+	assert_eq!(image.into_raw(), vec![100, 50, 25, 128, 10, 20, 30, 255]);
+}
+
+#[test]
+fn sprite_sheet_grid() {
+	let grid = Grid { cell_size: (16, 16), margin: 0, spacing: 0 };
+	let frames = slice_grid(32, 32, &grid);
+
+	assert_eq!(frames.len(), 4);
+	assert_eq!(frames["1"], (16.0, 0.0, 16.0, 16.0));
+	assert_eq!(frames["2"], (0.0, 16.0, 16.0, 16.0));
+}
+
+#[test]
+fn sprite_sheet_grid_margin_spacing() {
+	let grid = Grid { cell_size: (8, 8), margin: 1, spacing: 2 };
+	// 1 + 8 + 2 + 8 + 2 + 8 + 1 = 30
+	let frames = slice_grid(30, 10, &grid);
+
+	assert_eq!(frames.len(), 3);
+	assert_eq!(frames["2"], (21.0, 1.0, 8.0, 8.0));
+}
+
+#[test]
+fn sprite_sheet_atlas() {
+	let hash = br#"{"frames": {"idle 0": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}}}, "meta": {}}"#;
+	let array = br#"{"frames": [{"filename": "run 1", "frame": {"x": 16, "y": 0, "w": 16, "h": 16}}]}"#;
+
+	assert_eq!(parse_atlas(hash).unwrap()["idle 0"], (0.0, 0.0, 16.0, 16.0));
+	assert_eq!(parse_atlas(array).unwrap()["run 1"], (16.0, 0.0, 16.0, 16.0));
+}
+
+fn assert_near(a: (f32, f32), b: (f32, f32)) {
+	assert!((a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3, "{a:?} != {b:?}");
+}
+
+#[test]
+fn camera_transform() {
+	let mut camera = Camera::new((100.0, 50.0), (800.0, 600.0));
+	assert_near(camera.world_to_camera((100.0, 50.0)), (0.0, 0.0));
+
+	camera.zoom = 2.0;
+	// the view center stays in place
+	assert_near(camera.world_to_camera((500.0, 350.0)), (400.0, 300.0));
+	assert_near(camera.world_to_camera((510.0, 350.0)), (420.0, 300.0));
+
+	camera.rotation = 90.0;
+	assert_near(camera.world_to_camera((510.0, 350.0)), (400.0, 280.0));
+	assert_near(camera.camera_to_world(camera.world_to_camera((123.0, 456.0))), (123.0, 456.0));
+}
+
+#[test]
+fn camera_view_rect() {
+	let mut camera = Camera::new((0.0, 0.0), (800.0, 600.0));
+	camera.zoom = 2.0;
+	let (x, y, w, h) = camera.view_rect();
+	assert_near((x, y), (200.0, 150.0));
+	assert_near((w, h), (400.0, 300.0));
+}
+
+#[test]
+fn camera_follow_and_bounds() {
+	let mut world = World::new();
+	let target = world.create_entity().build();
+	let mut camera = Camera::new((0.0, 0.0), (800.0, 600.0));
+	camera.follow(target, (100.0, 100.0), 1.0);
+
+	// inside of the deadzone
+	camera.update(1.0 / 60.0, Some((430.0, 300.0)));
+	assert_near(camera.center(), (400.0, 300.0));
+
+	camera.update(1.0 / 60.0, Some((600.0, 300.0)));
+	assert_near(camera.center(), (550.0, 300.0));
+
+	camera.bounds = Some((0.0, 0.0, 1000.0, 2000.0));
+	camera.update(1.0 / 60.0, Some((5000.0, 300.0)));
+	assert_near(camera.center(), (600.0, 300.0));
+}
+
+#[test]
+fn camera_shake() {
+	let mut camera = Camera::new((0.0, 0.0), (800.0, 600.0));
+	camera.shake(10.0, 1.0);
+	camera.update(0.5, None);
+	let (x, y) = camera.world_to_camera((0.0, 0.0));
+	assert!(x.abs() <= 5.0 && y.abs() <= 5.0 && (x, y) != (0.0, 0.0));
+
+	camera.update(1.0, None);
+	assert_near(camera.world_to_camera((0.0, 0.0)), (0.0, 0.0));
+}
+
+#[test]
+fn camera_viewport_and_layer_mask() {
+	let minimap = Camera::new((0.0, 0.0), (200.0, 150.0))
+		.with_screen_position((600.0, 0.0))
+		.with_layer_mask(&["world"])
+		.with_priority(1);
+	assert_eq!(minimap.screen_rect(), (600.0, 0.0, 200.0, 150.0));
+	assert!(minimap.sees("world"));
+	assert!(!minimap.sees("ui"));
+	assert!(Camera::new((0.0, 0.0), (800.0, 600.0)).sees("ui"));
+
+	let mut camera = Camera::new((100.0, 0.0), (800.0, 600.0));
+	camera.zoom = 2.0;
+	let rect = Drawable::Rect {
+		w: 10.0,
+		h: 20.0,
+		x: 100.0,
+		y: 0.0,
+		color: (255, 255, 255, 255),
+		filled: true,
+		thickness: 1.0,
+		layer: DEFAULT_LAYER.to_string(),
+		z: 0,
+	};
+	let Drawable::Rect { w, h, x, y, .. } = camera.transform_drawable(rect) else { unreachable!() };
+	assert_near((w, h), (20.0, 40.0));
+	assert_near((x, y), (-400.0, -300.0));
+}
+
+#[test]
+fn shapes_triangle_fan() {
+	let square = shapes::rect_points(0.0, 0.0, 2.0, 2.0);
+	let triangles = shapes::triangle_fan(&square);
+	assert_eq!(triangles, vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+	assert!(shapes::triangle_fan(&square[..2]).is_empty());
+}
+
+#[test]
+fn shapes_thick_polyline() {
+	let triangles = shapes::thick_polyline(&[(0.0, 0.0), (10.0, 0.0)], false, 4.0);
+	// one quad and no joints
+	assert_eq!(triangles.len(), 6);
+	assert!(triangles.iter().all(|&(x, y)| (0.0..=10.0).contains(&x) && y.abs() == 2.0));
+
+	// three quads and a square on every corner
+	let triangles = shapes::thick_polyline(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)], true, 2.0);
+	assert_eq!(triangles.len(), 3 * 6 + 3 * 6);
+}
+
+#[test]
+fn shapes_circle() {
+	let points = shapes::circle_points((5.0, 5.0), 100.0);
+	assert!(points.len() >= 12);
+	for (x, y) in points {
+		assert!((((x - 5.0).powi(2) + (y - 5.0).powi(2)).sqrt() - 100.0).abs() < 0.01);
+	}
+}
+
+#[test]
+fn text_wrap() {
+	// every character is 10 pixels wide
+	let measure = |line: &str| Ok(line.chars().count() as f32 * 10.0);
+	let lines = text::wrap_text("the quick brown fox\njumps", Some(100.0), measure).unwrap();
+	assert_eq!(lines, vec!["the quick", "brown fox", "jumps"]);
+
+	let lines = text::wrap_text("a verylongword b", Some(50.0), measure).unwrap();
+	assert_eq!(lines, vec!["a", "verylongword", "b"]);
+
+	let lines = text::wrap_text("one line\n\ntwo", None, measure).unwrap();
+	assert_eq!(lines, vec!["one line", "", "two"]);
+}
+
+#[test]
+fn text_align() {
+	let layout = TextLayout::default().with_align(TextAlign::Center);
+	assert_eq!(layout.align_offset(100.0, 40.0), 30.0);
+	assert_eq!(layout.with_align(TextAlign::Right).align_offset(100.0, 40.0), 60.0);
+	assert_eq!(TextLayout::default().align_offset(100.0, 40.0), 0.0);
+}
+
+#[test]
+fn text_measured_from_asset_info() {
+	let mut manager = ResourceManager::new();
+	let metrics = FontMetrics { size: 10.0, height: 12.0, ascent: 9.0, descent: -3.0, line_spacing: 14.0 };
+	// every character is 10 pixels wide at size 10
+	let advances = GlyphAdvances { advances: Default::default(), fallback: 10.0 };
+	manager.insert(DummyFont::new("font.ttf".into(), "ui".to_string()).with_metrics(metrics, advances));
+	let mut info = AssetInfo::default();
+	info.sync("graphics", &manager);
+
+	let layout = TextLayout::default().with_max_width(100.0);
+	assert_eq!(text::measure_text(&info, "ui", 0.0, "the quick brown fox\njumps", &layout), Some((100.0, 40.0)));
+	assert_eq!(text::measure_text(&info, "ui", 20.0, "hello", &TextLayout::default()), Some((100.0, 24.0)));
+	assert_eq!(text::measure_text(&info, "missing", 0.0, "hello", &layout), None);
+}
+
+#[test]
+fn text_cache_lru_eviction() {
+	let mut cache = LruCache::new(100);
+	assert!(cache.insert("a".to_string(), 1, 40).is_empty());
+	assert!(cache.insert("b".to_string(), 2, 40).is_empty());
+	// `a` becomes the most recently used, so `b` is evicted
+	assert_eq!(cache.get_mut("a"), Some(&mut 1));
+	assert_eq!(cache.insert("c".to_string(), 3, 40), vec![2]);
+	assert_eq!(cache.used(), 80);
+	assert!(cache.get_mut("b").is_none());
+
+	// bigger than the budget, evicts everything but is kept
+	assert_eq!(cache.insert("d".to_string(), 4, 200).len(), 2);
+	assert_eq!(cache.len(), 1);
+	assert_eq!(cache.set_budget(10), vec![4]);
+	assert!(cache.is_empty());
+}
+
+#[test]
+fn text_cache_shelf_packer() {
+	let mut packer = ShelfPacker::new((32, 32));
+	assert_eq!(packer.pack(16, 10), Some((0, 0)));
+	assert_eq!(packer.pack(16, 12), Some((16, 0)));
+	assert_eq!(packer.pack(8, 8), Some((0, 12)));
+	assert_eq!(packer.pack(40, 8), None);
+	assert_eq!(packer.pack(32, 16), None);
+	assert_eq!(packer.pack(24, 12), Some((8, 12)));
+}
+
+#[test]
+fn debug_overlay_windows() {
+	let mut overlay = DebugOverlay::new("Terminus", 14.0);
+	overlay.window("runtime").line("tps", 60).text("systems:");
+	overlay.window("physics").line("bodies", 3);
+	// refilling a window replaces its lines
+	overlay.window("runtime").line("tps", 59);
+	assert_eq!(overlay.get("runtime").unwrap().lines, vec!["tps: 59"]);
+	assert_eq!(overlay.titles().collect::<Vec<_>>(), vec!["physics", "runtime"]);
+
+	// a panel, a title and the lines of every window
+	let drawables = overlay.drawables();
+	assert_eq!(drawables.len(), 2 * 3);
+	assert!(drawables.iter().all(|drawable| drawable.layer() == DEBUG_LAYER));
+
+	overlay.visible = false;
+	assert!(overlay.drawables().is_empty());
+}
+
+#[test]
+fn screenshot_compare_images() {
+	let expected = image::RgbaImage::from_pixel(4, 2, image::Rgba([10, 20, 30, 255]));
+	let mut actual = expected.clone();
+	actual.put_pixel(0, 0, image::Rgba([12, 20, 30, 255]));
+	actual.put_pixel(3, 1, image::Rgba([10, 20, 90, 255]));
+	assert_eq!(
+		compare_images(&actual, &expected, 2).unwrap(),
+		ImageDiff { differing_pixels: 1, max_difference: 60 }
+	);
+	assert_eq!(compare_images(&actual, &expected, 60).unwrap().differing_pixels, 0);
+	assert!(compare_images(&actual, &image::RgbaImage::new(2, 4), 0).is_err());
+}
+
+#[test]
+fn screenshot_missing_golden_fails() {
+	let directory = std::env::temp_dir().join(format!("fennel-golden-{}", std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let image = image::RgbaImage::from_pixel(2, 2, image::Rgba([10, 20, 30, 255]));
+	let golden = directory.join("missing.png");
+
+	let error = screenshot::assert_golden(&image, &golden, 0).unwrap_err();
+	assert!(error.to_string().contains(screenshot::UPDATE_GOLDEN_VAR));
+	assert!(!golden.exists());
+	assert!(directory.join("missing.actual.png").exists());
+	std::fs::remove_dir_all(&directory).unwrap();
+}
+
+fn rect(x: f32, y: f32, w: f32, h: f32, color: (u8, u8, u8, u8)) -> Drawable {
+	Drawable::Rect { w, h, x, y, color, filled: true, thickness: 1.0, layer: DEFAULT_LAYER.to_string(), z: 0 }
+}
+
+#[test]
+fn screenshot_golden_rects() {
+	let mut graphics = GraphicsBuilder::new()
+		.resource_manager(SharedResourceManager::default())
+		.dimensions((64, 48))
+		.initializer(|_| Ok(()))
+		.headless(true)
+		.build()
+		.unwrap();
+	let views = [
+		CameraView {
+			viewport: (0.0, 0.0, 64.0, 48.0),
+			drawables: vec![
+				rect(8.0, 8.0, 16.0, 16.0, (255, 0, 0, 255)),
+				rect(32.0, 8.0, 24.0, 12.0, (0, 255, 0, 255)),
+				rect(4.0, 28.0, 56.0, 12.0, (255, 255, 255, 128)),
+			],
+			target: None,
+		},
+		// drawn relative to and clipped by the viewport
+		CameraView {
+			viewport: (48.0, 32.0, 16.0, 16.0),
+			drawables: vec![rect(8.0, 8.0, 32.0, 32.0, (0, 0, 255, 255))],
+			target: None,
+		},
+	];
+	graphics.render_views(&views);
+	let frame = graphics.screenshot().unwrap();
+	screenshot::assert_golden(&frame, concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/rects.png"), 2).unwrap();
+}
+
+#[test]
+fn render_targets_drawn_before_the_window() {
+	let mut world = World::new();
+	world.register::<Camera>();
+	let (sender, receiver) = kanal::unbounded::<Vec<CameraView>>();
+	world.insert(sender);
+	world.insert(Camera::new((80.0, 0.0), (320.0, 240.0)));
+	world.insert(RenderLayers::default());
+	world.insert(WindowSize::new((320, 240)));
+
+	let mut queue = RenderQueue::new();
+	queue.queue.push(rect(90.0, 10.0, 4.0, 4.0, (255, 0, 0, 255)));
+	let mut minimap_camera = Camera::new((0.0, 0.0), (64.0, 64.0));
+	minimap_camera.zoom = 0.5;
+	let minimap = queue.target("minimap", (64, 64), Some((0, 0, 0, 255)));
+	minimap.camera = Some(minimap_camera);
+	minimap.drawables.push(rect(0.0, 0.0, 64.0, 1.0, (255, 255, 255, 255)));
+	// queueing the same target again reuses its queue
+	queue.target("minimap", (32, 32), None).drawables.push(rect(0.0, 0.0, 1.0, 1.0, (0, 0, 0, 255)));
+	assert_eq!(queue.targets.len(), 1);
+	world.insert(queue);
+
+	QueuedRenderingSystem.run_now(&world);
+	let views = receiver.try_recv().unwrap().unwrap();
+	assert_eq!(views.len(), 2);
+
+	let target = views[0].target.as_ref().unwrap();
+	assert_eq!((target.name.as_str(), target.size, target.clear_color), ("minimap", (32, 32), None));
+	assert_eq!(views[0].viewport, (0.0, 0.0, 32.0, 32.0));
+	// the world as seen by the target camera comes first
+	assert_eq!(views[0].drawables.len(), 3);
+	let Drawable::Rect { x, w, .. } = views[0].drawables[0] else { panic!("expected a rectangle") };
+	assert_near((x, w), (61.0, 2.0));
+
+	assert!(views[1].target.is_none());
+	let Drawable::Rect { x, .. } = views[1].drawables[0] else { panic!("expected a rectangle") };
+	assert_near((x, 0.0), (10.0, 0.0));
+	assert!(world.read_resource::<RenderQueue>().targets.is_empty());
+}
+
+/// A rectangle identified by its `x`
+fn layered_rect(id: f32, layer: &str, z: i32) -> Drawable {
+	Drawable::Rect { w: 1.0, h: 1.0, x: id, y: 0.0, color: (255, 255, 255, 255), filled: true, thickness: 1.0, layer: layer.to_string(), z }
+}
+
+#[test]
+fn queue_sorted_by_layer_then_z() {
+	let mut world = World::new();
+	world.register::<Camera>();
+	let (sender, receiver) = kanal::unbounded::<Vec<CameraView>>();
+	world.insert(sender);
+	world.insert(Camera::new((0.0, 0.0), (320.0, 240.0)));
+	world.insert(RenderLayers::default());
+	world.insert(WindowSize::new((320, 240)));
+	let mut queue = RenderQueue::new();
+	queue.queue.extend([
+		layered_rect(0.0, "ui", 0),
+		layered_rect(1.0, DEFAULT_LAYER, 1),
+		layered_rect(2.0, "unknown", 0),
+		layered_rect(3.0, DEFAULT_LAYER, 0),
+		layered_rect(4.0, "background", 5),
+		layered_rect(5.0, DEFAULT_LAYER, 0),
+		layered_rect(6.0, "unknown", -1),
+		layered_rect(7.0, "debug", 0),
+	]);
+	world.insert(queue);
+
+	QueuedRenderingSystem.run_now(&world);
+	let views = receiver.try_recv().unwrap().unwrap();
+	let order: Vec<f32> = views[0].drawables
+		.iter()
+		.map(|drawable| match drawable {
+			Drawable::Rect { x, .. } => *x,
+			_ => panic!("expected a rectangle"),
+		})
+		.collect();
+	// unknown layers go last, drawables sharing a layer and z keep their queue order
+	assert_eq!(order, [4.0, 3.0, 5.0, 1.0, 0.0, 6.0, 2.0, 7.0]);
+}
+
+#[test]
+fn fixed_queue_and_overlay_cover_the_window() {
+	let mut world = World::new();
+	world.register::<Camera>();
+	let (sender, receiver) = kanal::unbounded::<Vec<CameraView>>();
+	world.insert(sender);
+	let mut camera = Camera::new((0.0, 0.0), (200.0, 100.0));
+	camera.screen_position = (50.0, 50.0);
+	camera.zoom = 2.0;
+	world.insert(camera);
+	world.insert(RenderLayers::default());
+	world.insert(WindowSize::new((640, 480)));
+	let mut overlay = DebugOverlay::new("Terminus", 14.0);
+	overlay.window("runtime").line("tps", 60);
+	world.insert(overlay);
+	let mut queue = RenderQueue::new();
+	queue.fixed.push(rect(0.0, 0.0, 10.0, 10.0, (255, 255, 255, 255)));
+	world.insert(queue);
+
+	QueuedRenderingSystem.run_now(&world);
+	let views = receiver.try_recv().unwrap().unwrap();
+	assert_eq!(views.len(), 3);
+	assert_eq!(views[0].viewport, (50.0, 50.0, 200.0, 100.0));
+	assert_eq!(views[1].viewport, (0.0, 0.0, 640.0, 480.0));
+	assert_eq!(views[2].viewport, (0.0, 0.0, 640.0, 480.0));
+}
+
+#[test]
+fn shapes_rotated_rect_and_bounds() {
+	let rect = (0.0, 0.0, 4.0, 2.0);
+	assert_eq!(shapes::rotated_rect(rect, (0.0, 0.0), 0.0), [(0.0, 0.0), (4.0, 0.0), (4.0, 2.0), (0.0, 2.0)]);
+	// a quarter turn clockwise around the top-left corner, with y pointing down
+	let corners = shapes::rotated_rect(rect, (0.0, 0.0), 90.0);
+	assert_near(corners[1], (0.0, 4.0));
+	assert_near(corners[2], (-2.0, 4.0));
+	let (x, y, w, h) = shapes::bounding_box(corners).unwrap();
+	assert_near((x, y), (-2.0, 0.0));
+	assert_near((w, h), (2.0, 4.0));
+	assert!(shapes::bounding_box([]).is_none());
+
+	assert!(shapes::overlaps((0.0, 0.0, 10.0, 10.0), (5.0, 5.0, 10.0, 10.0)));
+	// touching edges don't overlap
+	assert!(!shapes::overlaps((0.0, 0.0, 10.0, 10.0), (10.0, 0.0, 10.0, 10.0)));
+}
+
+#[test]
+fn assets_are_loading_until_requests_are_reported() {
+	let (request_sender, requests) = kanal::unbounded::<AssetRequest>();
+	let (report_sender, reports) = kanal::unbounded::<AssetReport>();
+	let mut world = World::new();
+	world.insert(Assets::new(request_sender, reports));
+	world.insert(AssetInfo::default());
+	world.insert(SharedResourceManager::default());
+	let mut system = AssetSystem;
+
+	// the startup assets are loading until the graphics thread reports on them
+	assert!(world.read_resource::<Assets>().is_loading());
+	system.run_now(&world);
+	assert!(world.read_resource::<Assets>().is_loading());
+	report_sender.send(AssetReport::default()).unwrap();
+	system.run_now(&world);
+	assert!(!world.read_resource::<Assets>().is_loading());
+
+	world.read_resource::<Assets>().load_group("level");
+	assert_eq!(requests.try_recv().unwrap(), Some(AssetRequest::LoadGroup("level".to_string())));
+	assert!(world.read_resource::<Assets>().is_loading());
+	report_sender.send(AssetReport { handled: 1, ..AssetReport::default() }).unwrap();
+	system.run_now(&world);
+	assert!(!world.read_resource::<Assets>().is_loading());
+}
+
+#[test]
+fn sprite_resolves_and_keeps_its_image() {
+	let mut manager = ResourceManager::new();
+	let tiles = manager.insert(Image::new("tiles".to_string(), 16, 16, Default::default()));
+	let mut sprite = Sprite::new("tiles".to_string(), Transform::new((0.0, 0.0), 1.0, 0.0), false);
+	assert_eq!(sprite.resolved(), None);
+	sprite.resolve(&manager).unwrap();
+	assert_eq!(sprite.resolved(), Some(&SpriteImage::Image(tiles)));
+	// the sprite keeps the image loaded
+	assert_eq!(manager.ref_count("tiles"), 1);
+
+	// frames of sprite sheets aren't resolved until the sheet is loaded
+	sprite.set_image("sheet:0");
+	assert_eq!(sprite.resolved(), None);
+	sprite.resolve(&manager).unwrap();
+	assert_eq!(sprite.resolved(), None);
+
+	// an image that failed to load under the frame's name doesn't hide the frame
+	manager.reserve::<Image>("sheet:0");
+	manager.fail("sheet:0", "no such file".to_string());
+	let sheet = manager.reserve::<SpriteSheet>("sheet");
+	sprite.resolve(&manager).unwrap();
+	assert_eq!(sprite.resolved(), Some(&SpriteImage::Frame(sheet, "0".into())));
+	assert_eq!(SpriteImage::split_frame("assets://tiles.png"), None);
+	assert_eq!(SpriteImage::split_frame("tiles:5"), Some(("tiles", "5")));
+
+	// a removed image is resolved again
+	sprite.set_image("tiles");
+	sprite.resolve(&manager).unwrap();
+	manager.remove("tiles").unwrap();
+	let tiles = manager.insert(Image::new("tiles".to_string(), 8, 8, Default::default()));
+	sprite.resolve(&manager).unwrap();
+	assert_eq!(sprite.resolved(), Some(&SpriteImage::Image(tiles)));
+
+	manager.release("tiles").unwrap();
+	assert!(manager.collect_unused().is_empty());
+	drop(sprite);
+	assert_eq!(manager.collect_unused(), ["tiles"]);
+
+	let logo = manager.insert(Image::new("logo".to_string(), 8, 8, Default::default()));
+	let strong = manager.strong(logo).unwrap();
+	let sprite = Sprite::from_handle(&strong, Transform::new((0.0, 0.0), 1.0, 0.0), true);
+	drop(strong);
+	assert_eq!(manager.ref_count("logo"), 1);
+	drop(sprite);
+	assert_eq!(manager.ref_count("logo"), 0);
+}
+
+#[test]
+fn drawable_bounds() {
+	let sprite = Sprite::new("tiles".to_string(), Transform::new((10.0, 10.0), 2.0, 0.0), false);
+	// the image size is unknown on the ECS side
+	assert_eq!(Drawable::Image(sprite.clone()).bounds(), None);
+	let sprite = sprite.with_source((0.0, 0.0, 8.0, 4.0)).with_origin((0.5, 0.5));
+	assert_eq!(Drawable::Image(sprite).bounds(), Some((2.0, 6.0, 16.0, 8.0)));
+
+	let outline = Drawable::Circle {
+		center: (0.0, 0.0),
+		radius: 5.0,
+		color: (255, 255, 255, 255),
+		filled: false,
+		thickness: 2.0,
+		layer: DEFAULT_LAYER.to_string(),
+		z: 0,
+	};
+	assert_eq!(outline.bounds(), Some((-6.0, -6.0, 12.0, 12.0)));
+}
+
+fn rect_tuple(rect: FRect) -> (f32, f32, f32, f32) {
+	(rect.x, rect.y, rect.w, rect.h)
+}
+
+#[test]
+fn sprite_rects_scale_size_and_origin() {
+	let sprite = Sprite::new("tiles".to_string(), Transform::new((10.0, 20.0), 2.0, 0.0), false);
+	let (src, dst, pivot) = sprite_rects(&sprite, (8.0, 4.0), None);
+	assert_eq!(rect_tuple(src), (0.0, 0.0, 8.0, 4.0));
+	assert_eq!(rect_tuple(dst), (10.0, 20.0, 16.0, 8.0));
+	assert_eq!((pivot.x, pivot.y), (0.0, 0.0));
+
+	// the pivot is the origin of the scaled size, and the position is the pivot
+	let sprite = sprite.with_size((6.0, 3.0)).with_origin((0.5, 1.0));
+	let (src, dst, pivot) = sprite_rects(&sprite, (8.0, 4.0), None);
+	assert_eq!(rect_tuple(src), (0.0, 0.0, 8.0, 4.0));
+	assert_eq!(rect_tuple(dst), (4.0, 14.0, 12.0, 6.0));
+	assert_eq!((pivot.x, pivot.y), (6.0, 6.0));
+}
+
+#[test]
+fn sprite_rects_frame_and_source() {
+	let sprite = Sprite::new("tiles".to_string(), Transform::new((0.0, 0.0), 1.0, 0.0), false).with_source((1.0, 2.0, 3.0, 4.0));
+	let (src, dst, _) = sprite_rects(&sprite, (32.0, 32.0), None);
+	assert_eq!(rect_tuple(src), (1.0, 2.0, 3.0, 4.0));
+	assert_eq!(rect_tuple(dst), (0.0, 0.0, 3.0, 4.0));
+
+	// a sprite sheet frame wins over the source
+	let (src, dst, _) = sprite_rects(&sprite, (32.0, 32.0), Some((16.0, 0.0, 8.0, 8.0)));
+	assert_eq!(rect_tuple(src), (16.0, 0.0, 8.0, 8.0));
+	assert_eq!(rect_tuple(dst), (0.0, 0.0, 8.0, 8.0));
+}
+
+#[test]
+fn sprite_batch_flips_swap_uvs() {
+	let corners = [(0.0, 0.0), (8.0, 0.0), (8.0, 4.0), (0.0, 4.0)];
+	let src = FRect::new(8.0, 0.0, 8.0, 4.0);
+	let uvs = |horizontal: bool, vertical: bool| {
+		let sprite = Sprite::new("tiles".to_string(), Transform::new((0.0, 0.0), 1.0, 0.0), false)
+			.with_tint((255, 0, 0), 51)
+			.with_flip(horizontal, vertical);
+		let mut batch = SpriteBatch::default();
+		batch.push(&sprite, corners, src, (16.0, 8.0));
+		assert_eq!(batch.vertices.len(), 6);
+		let color = batch.vertices[0].color;
+		assert_eq!((color.r, color.g, color.b, color.a), (1.0, 0.0, 0.0, 0.2));
+		// the two triangles are (0, 1, 2) and (0, 2, 3)
+		let positions: Vec<(f32, f32)> = batch.vertices.iter().map(|vertex| (vertex.position.x, vertex.position.y)).collect();
+		assert_eq!(positions, [0, 1, 2, 0, 2, 3].map(|corner| corners[corner]));
+		[0, 1, 2, 5].map(|vertex| (batch.vertices[vertex].tex_coord.x, batch.vertices[vertex].tex_coord.y))
+	};
+	assert_eq!(uvs(false, false), [(0.5, 0.0), (1.0, 0.0), (1.0, 0.5), (0.5, 0.5)]);
+	assert_eq!(uvs(true, false), [(1.0, 0.0), (0.5, 0.0), (0.5, 0.5), (1.0, 0.5)]);
+	assert_eq!(uvs(false, true), [(0.5, 0.5), (1.0, 0.5), (1.0, 0.0), (0.5, 0.0)]);
+	assert_eq!(uvs(true, true), [(1.0, 0.5), (0.5, 0.5), (0.5, 0.0), (1.0, 0.0)]);
+}
+
+#[test]
+fn camera_culls_drawables_outside_of_the_view() {
+	let camera = Camera::new((100.0, 100.0), (200.0, 100.0));
+	let tile = |position: (f32, f32), fixed: bool| {
+		Drawable::Image(Sprite::new("tiles".to_string(), Transform::new(position, 1.0, 0.0), fixed).with_size((16.0, 16.0)))
+	};
+	let drawables = vec![
+		tile((120.0, 150.0), false),
+		tile((500.0, 150.0), false),
+		// fixed sprites are culled in camera coordinates
+		tile((20.0, 50.0), true),
+		tile((20.0, 150.0), true),
+		// unknown bounds are kept
+		Drawable::Image(Sprite::new("tiles".to_string(), Transform::new((500.0, 150.0), 1.0, 0.0), false)),
+		rect(-50.0, 100.0, 10.0, 10.0, (255, 255, 255, 255)),
+	];
+	let view = camera.view(&drawables);
+	assert_eq!(view.len(), 3);
+	let positions: Vec<(f32, f32)> = view
+		.iter()
+		.map(|drawable| match drawable {
+			Drawable::Image(sprite) => sprite.transform.position,
+			_ => panic!("expected a sprite"),
+		})
+		.collect();
+	assert_eq!(positions, vec![(20.0, 50.0), (20.0, 50.0), (400.0, 50.0)]);
+}
@@ -0,0 +1,207 @@
+//! Layout of multi-line text
+//!
+//! Provides:
+//! - [`TextLayout`]: wrapping, alignment, line spacing and effects of a [`crate::graphics::Drawable::Text`]
+//! - [`wrap_text`]: split a text into lines that fit into a width
+//! - [`measure_text`]: size of a text block from the [`AssetInfo`], for systems laying out
+//!   interfaces outside of the graphics thread
+
+use serde::Deserialize;
+use fennel_resources::info::AssetInfo;
+
+/// Horizontal alignment of the lines of a text
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TextAlign {
+    /// Lines start at the text position
+    #[default]
+    Left,
+    /// Lines are centered in the text block
+    Center,
+    /// Lines end at the right edge of the text block
+    Right,
+}
+
+/// How a text is broken into lines and decorated
+///
+/// The text block is as wide as `max_width` when it's set or as its widest line otherwise,
+/// lines are aligned inside of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    /// Width at which lines are wrapped between words, lines are only broken on `\n` if `None`
+    pub max_width: Option<f32>,
+    /// Alignment of the lines inside of the text block
+    pub align: TextAlign,
+    /// Multiplier of the font's line height
+    pub line_spacing: f32,
+    /// Outline drawn around the glyphs
+    pub outline: Option<TextOutline>,
+    /// Shadow drawn behind the text
+    pub shadow: Option<TextShadow>,
+}
+
+/// Outline of a text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    /// Thickness in pixels
+    pub thickness: f32,
+    /// RGB color
+    pub color: (u8, u8, u8),
+}
+
+/// Shadow of a text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    /// Offset from the text in pixels
+    pub offset: (f32, f32),
+    /// RGB color
+    pub color: (u8, u8, u8),
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: TextAlign::Left,
+            line_spacing: 1.0,
+            outline: None,
+            shadow: None,
+        }
+    }
+}
+
+impl TextLayout {
+    /// Wrap lines at `max_width`
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Align lines inside of the text block
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Set the multiplier of the font's line height
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    /// Outline the text
+    pub fn with_outline(mut self, thickness: f32, color: (u8, u8, u8)) -> Self {
+        self.outline = Some(TextOutline { thickness, color });
+        self
+    }
+
+    /// Drop a shadow behind the text
+    pub fn with_shadow(mut self, offset: (f32, f32), color: (u8, u8, u8)) -> Self {
+        self.shadow = Some(TextShadow { offset, color });
+        self
+    }
+
+    /// Horizontal offset of a line `line_width` wide inside of a block `block_width` wide
+    pub fn align_offset(&self, block_width: f32, line_width: f32) -> f32 {
+        match self.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (block_width - line_width) / 2.0,
+            TextAlign::Right => block_width - line_width,
+        }
+    }
+
+    /// Scale every length of the layout, used to follow the camera zoom
+    pub fn scale(&mut self, factor: f32) {
+        self.max_width = self.max_width.map(|width| width * factor);
+        if let Some(outline) = &mut self.outline {
+            outline.thickness *= factor;
+        }
+        if let Some(shadow) = &mut self.shadow {
+            shadow.offset.0 *= factor;
+            shadow.offset.1 *= factor;
+        }
+    }
+}
+
+/// Split a text into lines on `\n` and, if `max_width` is set, between words so that every
+/// line measured with `measure` fits into it
+///
+/// Words wider than `max_width` are kept on their own line rather than being broken.
+pub fn wrap_text(
+    text: &str,
+    max_width: Option<f32>,
+    mut measure: impl FnMut(&str) -> anyhow::Result<f32>,
+) -> anyhow::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let Some(max_width) = max_width else {
+            lines.push(paragraph.to_string());
+            continue;
+        };
+
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if line.is_empty() {
+                line.push_str(word);
+                continue;
+            }
+            let candidate = format!("{line} {word}");
+            if measure(&candidate)? > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Lines of a text with their offsets from the top-left corner of the text block
+pub(crate) type PositionedLines = Vec<(String, (f32, f32))>;
+
+/// Break a text into lines positioned relative to the top-left corner of the text block
+///
+/// # Arguments
+/// * `height`: height of a line of the font
+/// * `line_spacing`: distance between two lines of the font, before the layout's multiplier
+/// * `measure`: width of a line
+///
+/// # Returns
+/// The lines and the size of the block
+pub(crate) fn layout_block(
+    text: &str,
+    layout: &TextLayout,
+    height: f32,
+    line_spacing: f32,
+    mut measure: impl FnMut(&str) -> anyhow::Result<f32>,
+) -> anyhow::Result<(PositionedLines, (f32, f32))> {
+    let lines = wrap_text(text, layout.max_width, &mut measure)?;
+    let widths = lines.iter().map(|line| measure(line)).collect::<anyhow::Result<Vec<f32>>>()?;
+    let block_width = layout.max_width.unwrap_or_else(|| widths.iter().copied().fold(0.0, f32::max));
+    let line_height = line_spacing * layout.line_spacing;
+    let block_height = height + line_height * lines.len().saturating_sub(1) as f32;
+
+    let lines = lines
+        .into_iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(index, (line, width))| {
+            (line, (layout.align_offset(block_width, width), index as f32 * line_height))
+        })
+        .collect();
+    Ok((lines, (block_width, block_height)))
+}
+
+/// Measure the size of a text block like
+/// [`Graphics::measure_text_layout`](crate::graphics::Graphics::measure_text_layout), from the
+/// glyph advances and metrics of the font in the [`AssetInfo`]
+///
+/// Kerning is not applied, so widths can be off by a few pixels.
+///
+/// # Returns
+/// `None` if the font isn't loaded
+pub fn measure_text(info: &AssetInfo, font: &str, size: f32, text: &str, layout: &TextLayout) -> Option<(f32, f32)> {
+    let metrics = info.font_metrics(font, size)?;
+    let measure = |line: &str| Ok(info.text_width(font, size, line).unwrap_or_default());
+    layout_block(text, layout, metrics.height, metrics.line_spacing, measure).ok().map(|(_, size)| size)
+}
@@ -0,0 +1,392 @@
+//! Bounded cache of rendered text
+//!
+//! Text textures are kept out of the [`fennel_resources::manager::ResourceManager`], which only
+//! holds named assets, and are evicted least recently used first once the cache grows over its
+//! memory budget.
+//!
+//! Every texture is rendered white and colored with color modulation when drawn, so the same
+//! text in different colors shares one texture.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use anyhow::Context;
+use log::warn;
+use sdl3::pixels::{Color, PixelFormat};
+use sdl3::rect::Rect;
+use sdl3::render::{BlendMode, FRect, Texture, TextureCreator};
+use sdl3::video::WindowContext;
+use crate::resources::font::InternalFont;
+use crate::resources::image::InnerImage;
+
+/// Bytes per pixel of cached textures
+const BYTES_PER_PIXEL: usize = 4;
+
+/// How text is rendered and cached
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextCacheMode {
+    /// Render every distinct string into its own texture. Looks best, fits static text
+    #[default]
+    Strings,
+    /// Render every glyph once into a per-font atlas and draw strings glyph by glyph.
+    /// Fits text that changes every frame, like counters, at the cost of kerning
+    Glyphs,
+}
+
+/// Configuration of a [`TextCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextCacheConfig {
+    /// Memory budget in bytes
+    pub budget: usize,
+    /// How text is rendered and cached
+    pub mode: TextCacheMode,
+    /// Width and height of a glyph atlas in pixels
+    pub atlas_size: u32,
+}
+
+impl Default for TextCacheConfig {
+    fn default() -> Self {
+        Self {
+            budget: 16 * 1024 * 1024,
+            mode: TextCacheMode::Strings,
+            atlas_size: 512,
+        }
+    }
+}
+
+/// A cache that evicts the least recently used entries once their total size goes over a budget
+pub struct LruCache<V> {
+    budget: usize,
+    used: usize,
+    tick: u64,
+    entries: HashMap<String, LruEntry<V>>,
+    /// Keys by the tick they were last used on, the first one is the least recently used
+    order: BTreeMap<u64, String>,
+}
+
+struct LruEntry<V> {
+    value: V,
+    size: usize,
+    last_used: u64,
+}
+
+impl<V> LruCache<V> {
+    /// Create an empty cache holding up to `budget` worth of entries
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            used: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    /// Get an entry and mark it as the most recently used
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let entry = self.entries.get_mut(key)?;
+        self.tick += 1;
+        let key = self.order.remove(&entry.last_used)?;
+        self.order.insert(self.tick, key);
+        entry.last_used = self.tick;
+        Some(&mut entry.value)
+    }
+
+    /// Insert an entry, evicting the least recently used entries until it fits into the budget
+    ///
+    /// An entry bigger than the whole budget is still inserted, alone, so that it can be used at
+    /// least once.
+    ///
+    /// # Returns
+    /// The evicted values
+    pub fn insert(&mut self, key: String, value: V, size: usize) -> Vec<V> {
+        let mut evicted = self.remove(&key).into_iter().collect::<Vec<_>>();
+        evicted.extend(self.evict(self.budget.saturating_sub(size)));
+
+        self.tick += 1;
+        self.used += size;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, LruEntry { value, size, last_used: self.tick });
+        evicted
+    }
+
+    /// Remove an entry
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.last_used);
+        self.used -= entry.size;
+        Some(entry.value)
+    }
+
+    /// Change the budget, evicting entries that don't fit into the new one
+    pub fn set_budget(&mut self, budget: usize) -> Vec<V> {
+        self.budget = budget;
+        self.evict(budget)
+    }
+
+    /// Total size of the entries
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Is the cache empty?
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.used = 0;
+    }
+
+    fn evict(&mut self, target: usize) -> Vec<V> {
+        let mut evicted = Vec::new();
+        while self.used > target {
+            let Some((_, key)) = self.order.pop_first() else { break };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.used -= entry.size;
+                evicted.push(entry.value);
+            }
+        }
+        evicted
+    }
+}
+
+/// Packs rectangles into a fixed size area row by row
+#[derive(Debug)]
+pub(crate) struct ShelfPacker {
+    size: (u32, u32),
+    /// Position of the next rectangle on the current shelf
+    cursor: (u32, u32),
+    shelf_height: u32,
+}
+
+impl ShelfPacker {
+    pub(crate) fn new(size: (u32, u32)) -> Self {
+        Self { size, cursor: (0, 0), shelf_height: 0 }
+    }
+
+    /// Find a place for a `w`x`h` rectangle, `None` if the area is full
+    pub(crate) fn pack(&mut self, w: u32, h: u32) -> Option<(u32, u32)> {
+        if w > self.size.0 {
+            return None;
+        }
+        let (position, shelf_height) = if self.cursor.0 + w > self.size.0 {
+            ((0, self.cursor.1 + self.shelf_height), 0)
+        } else {
+            (self.cursor, self.shelf_height)
+        };
+        if position.1 + h > self.size.1 {
+            return None;
+        }
+        self.cursor = (position.0 + w, position.1);
+        self.shelf_height = shelf_height.max(h);
+        Some(position)
+    }
+}
+
+/// A character of a text drawn from a glyph atlas
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Glyph {
+    /// Rectangle of the glyph in the atlas, `None` if the font failed to render it
+    pub(crate) rect: Option<FRect>,
+    /// Horizontal distance to the next glyph, from the font metrics
+    pub(crate) advance: f32,
+}
+
+/// Glyphs of a font rendered into a single texture
+struct GlyphAtlas {
+    texture: Rc<RefCell<Texture<'static>>>,
+    packer: ShelfPacker,
+    glyphs: HashMap<char, Glyph>,
+    /// Advances of the characters the font failed to render, which are skipped when drawing
+    failed: HashMap<char, f32>,
+}
+
+enum CachedText {
+    String(Rc<InnerImage>),
+    Atlas(GlyphAtlas),
+}
+
+/// Cache of text textures, see the module documentation
+pub struct TextCache {
+    config: TextCacheConfig,
+    entries: LruCache<CachedText>,
+}
+
+impl Default for TextCache {
+    fn default() -> Self {
+        Self::new(TextCacheConfig::default())
+    }
+}
+
+impl TextCache {
+    /// Create an empty cache
+    pub fn new(config: TextCacheConfig) -> Self {
+        Self {
+            config,
+            entries: LruCache::new(config.budget),
+        }
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> TextCacheConfig {
+        self.config
+    }
+
+    /// Change the configuration. Cached textures are dropped if the mode or the atlas size
+    /// change, otherwise the ones over the new budget are evicted
+    pub fn set_config(&mut self, config: TextCacheConfig) {
+        if config.mode != self.config.mode || config.atlas_size != self.config.atlas_size {
+            self.entries.clear();
+        }
+        self.entries.set_budget(config.budget);
+        self.config = config;
+    }
+
+    /// Memory used by the cached textures in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.entries.used()
+    }
+
+    /// Drop every cached texture
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Get the texture of a whole string, rendering it if it isn't cached
+    pub(crate) fn string_texture(
+        &mut self,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &InternalFont,
+        font_key: &str,
+        text: &str,
+    ) -> anyhow::Result<Rc<InnerImage>> {
+        let key = format!("{font_key}|{text}");
+        if let Some(CachedText::String(image)) = self.entries.get_mut(&key) {
+            return Ok(image.clone());
+        }
+
+        let surface = font.buffer
+            .render(text)
+            .blended(Color::WHITE)
+            .map_err(|e| anyhow::anyhow!("render error: {e}"))?;
+        let texture = unsafe {
+            std::mem::transmute::<Texture<'_>, Texture<'static>>(
+                texture_creator.create_texture_from_surface(&surface)?,
+            )
+        };
+        let image = Rc::new(InnerImage::new(texture, surface.width(), surface.height()));
+        let size = surface.width() as usize * surface.height() as usize * BYTES_PER_PIXEL;
+        self.entries.insert(key, CachedText::String(image.clone()), size);
+        Ok(image)
+    }
+
+    /// Get the atlas of a font and the glyphs of every character of `text`, rendering the missing
+    /// ones into the atlas
+    ///
+    /// A full atlas is emptied and refilled with the glyphs of `text`. Characters the font fails
+    /// to render are logged once and have no rectangle, but still advance the pen.
+    pub(crate) fn glyphs(
+        &mut self,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &InternalFont,
+        font_key: &str,
+        text: &str,
+    ) -> anyhow::Result<(Rc<RefCell<Texture<'static>>>, Vec<Glyph>)> {
+        let key = format!("{font_key}|atlas");
+        let atlas_size = self.config.atlas_size;
+        if !matches!(self.entries.get_mut(&key), Some(CachedText::Atlas(_))) {
+            let mut texture = unsafe {
+                std::mem::transmute::<Texture<'_>, Texture<'static>>(
+                    texture_creator.create_texture_static(PixelFormat::RGBA32, atlas_size, atlas_size)?,
+                )
+            };
+            texture.set_blend_mode(BlendMode::Blend);
+            let atlas = GlyphAtlas {
+                texture: Rc::new(RefCell::new(texture)),
+                packer: ShelfPacker::new((atlas_size, atlas_size)),
+                glyphs: HashMap::new(),
+                failed: HashMap::new(),
+            };
+            let size = atlas_size as usize * atlas_size as usize * BYTES_PER_PIXEL;
+            self.entries.insert(key.clone(), CachedText::Atlas(atlas), size);
+        }
+        let Some(CachedText::Atlas(atlas)) = self.entries.get_mut(&key) else {
+            anyhow::bail!("glyph atlas of {font_key} was evicted right after its creation");
+        };
+
+        if let Some(glyphs) = atlas.glyphs(font, text) {
+            return Ok((atlas.texture.clone(), glyphs));
+        }
+        atlas.packer = ShelfPacker::new((atlas_size, atlas_size));
+        atlas.glyphs.clear();
+        let glyphs = atlas.glyphs(font, text)
+            .with_context(|| format!("glyphs of \"{text}\" don't fit into a {atlas_size}x{atlas_size} atlas"))?;
+        Ok((atlas.texture.clone(), glyphs))
+    }
+}
+
+impl GlyphAtlas {
+    /// Glyphs of the characters of `text`
+    ///
+    /// # Returns
+    /// `None` if the atlas is full
+    fn glyphs(&mut self, font: &InternalFont, text: &str) -> Option<Vec<Glyph>> {
+        let mut glyphs = Vec::new();
+        for ch in text.chars() {
+            if let Some(&advance) = self.failed.get(&ch) {
+                glyphs.push(Glyph { rect: None, advance });
+                continue;
+            }
+            match self.glyph(font, ch) {
+                Ok(Some(glyph)) => glyphs.push(glyph),
+                Ok(None) => return None,
+                Err(e) => {
+                    warn!("failed to render glyph {ch:?} of {}: {e:#}", font.family_name);
+                    let advance = font.advance(ch).unwrap_or(0.0);
+                    self.failed.insert(ch, advance);
+                    glyphs.push(Glyph { rect: None, advance });
+                }
+            }
+        }
+        Some(glyphs)
+    }
+
+    /// Glyph of a character, rendering it into the atlas if it isn't yet
+    ///
+    /// # Returns
+    /// `None` if the atlas is full
+    fn glyph(&mut self, font: &InternalFont, ch: char) -> anyhow::Result<Option<Glyph>> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return Ok(Some(*glyph));
+        }
+        let surface = font.buffer
+            .render_char(ch)
+            .blended(Color::WHITE)
+            .map_err(|e| anyhow::anyhow!("render error: {e}"))?
+            .convert_format(PixelFormat::RGBA32)?;
+        let (w, h) = (surface.width(), surface.height());
+        if w > self.packer.size.0 || h > self.packer.size.1 {
+            anyhow::bail!("glyph is larger than the atlas");
+        }
+        let Some((x, y)) = self.packer.pack(w, h) else { return Ok(None) };
+        let pitch = surface.pitch() as usize;
+        surface.with_lock(|pixels| {
+            self.texture.borrow_mut().update(Rect::new(x as i32, y as i32, w, h), pixels, pitch)
+        })?;
+        let glyph = Glyph {
+            rect: Some(FRect::new(x as f32, y as f32, w as f32, h as f32)),
+            advance: font.advance(ch).unwrap_or(w as f32),
+        };
+        self.glyphs.insert(ch, glyph);
+        Ok(Some(glyph))
+    }
+}
@@ -1,5 +1,6 @@
 use ron::Value;
-use specs::{Entity, LazyUpdate, World};
+use serde::Deserialize;
+use specs::{Component, DenseVecStorage, Entity, LazyUpdate, World};
 use std::collections::HashMap;
 
 /// All components must have a factory implementing this trait to be able created from a scene
@@ -43,6 +44,41 @@ impl Default for ComponentRegistry {
     }
 }
 
+/// Manifest asset groups needed while the entity exists, so a scene can bring its own assets:
+///
+/// ```ron
+/// (
+///     id: "level_1",
+///     components: [
+///         (id: "asset_groups", config: (groups: ["forest", "boss"])),
+///     ],
+/// ),
+/// ```
+///
+/// Scenes insert it for the groups they declare. The groups are loaded and unloaded by
+/// `fennel-2d`, which registers the factory.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AssetGroups {
+    /// Names of the manifest groups
+    pub groups: Vec<String>,
+}
+
+impl Component for AssetGroups {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Entity created by a component of another entity, such as the objects of a tilemap, deleted
+/// along with its owner when the scene is switched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnedBy {
+    /// Entity the component spawning this one belongs to
+    pub owner: Entity,
+}
+
+impl Component for SpawnedBy {
+    type Storage = DenseVecStorage<Self>;
+}
+
 #[macro_export]
 /// Create and implement a component factory for a component.
 ///
@@ -0,0 +1,16 @@
+//! Pack an assets directory into a single archive for release builds
+//!
+//! `cargo run -p fennel-resources --example pack -- assets` writes `assets.pak`, which
+//! `Vfs::mount_dir_or_pack` then mounts instead of the directory.
+
+use std::path::PathBuf;
+use fennel_resources::vfs::{pack_dir, PACK_EXTENSION};
+
+fn main() -> anyhow::Result<()> {
+	let mut args = std::env::args().skip(1);
+	let dir = PathBuf::from(args.next().ok_or_else(|| anyhow::anyhow!("usage: pack <directory> [archive]"))?);
+	let archive = args.next().map(PathBuf::from).unwrap_or_else(|| dir.with_extension(PACK_EXTENSION));
+	pack_dir(&dir, &archive)?;
+	println!("packed {} into {}", dir.display(), archive.display());
+	Ok(())
+}
@@ -0,0 +1,33 @@
+//! Check an assets directory for manifest mistakes without starting the game
+//!
+//! `cargo run -p fennel-resources --example validate -- assets` reports missing files,
+//! duplicate names, unknown types and invalid options of every manifest, and fails if there
+//! is any. Extra asset types can be allowed with `--type <name>`.
+
+use std::path::PathBuf;
+use fennel_resources::manifest::{validate, AssetTypes};
+
+fn main() -> anyhow::Result<()> {
+	let mut dir = None;
+	let mut types = AssetTypes::builtin();
+	let mut args = std::env::args().skip(1);
+	while let Some(arg) = args.next() {
+		if arg == "--type" {
+			let class = args.next().ok_or_else(|| anyhow::anyhow!("--type needs a type name"))?;
+			types = types.with::<toml::Table>(&class);
+		} else {
+			dir = Some(PathBuf::from(arg));
+		}
+	}
+	let dir = dir.ok_or_else(|| anyhow::anyhow!("usage: validate <directory> [--type <name>]..."))?;
+
+	let issues = validate(&dir, &types);
+	for issue in &issues {
+		println!("{issue}");
+	}
+	if !issues.is_empty() {
+		anyhow::bail!("{} issue(s) in {}", issues.len(), dir.display());
+	}
+	println!("{} is valid", dir.display());
+	Ok(())
+}
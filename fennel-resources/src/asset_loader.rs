@@ -0,0 +1,140 @@
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use crate::manager::ResourceManager;
+use crate::manifest::{AssetEntry, ManifestAsset};
+use crate::resource::Resource;
+
+/// Loads the manifest assets of a custom type into resources
+///
+/// A loader handles the entries whose `type` is its [`AssetLoader::type_name`], and the entries
+/// without a `type` whose file has one of its [`AssetLoader::extensions`]:
+///
+/// ```toml
+/// [[assets]]
+/// name = "intro"
+/// path = "dialogue/intro.json"
+/// type = "Dialogue"
+/// ```
+///
+/// Plugins register their loaders into the [`AssetLoaders`] of the world during `prepare`, the
+/// assets are then loaded, grouped and hot-reloaded like the built-in ones.
+pub trait AssetLoader: Send + Sync + 'static {
+	/// Resource made from an asset
+	type Asset: Resource;
+
+	/// Manifest `type` of the assets, such as `"Dialogue"`
+	fn type_name(&self) -> &str;
+
+	/// Extensions of the files loaded when an entry has no `type`, lowercase and without the dot
+	fn extensions(&self) -> &[&str] {
+		&[]
+	}
+
+	/// Check the options of an entry without loading it, see [`crate::manifest::validate`]
+	fn check(&self, _entry: &AssetEntry) -> anyhow::Result<()> {
+		Ok(())
+	}
+
+	/// Make the resource of an asset, on a worker thread. Read its file through [`crate::vfs`]
+	/// and its options with [`AssetEntry::options`]. The resource must be named after the entry,
+	/// else it fails to load
+	fn load(&self, asset: &ManifestAsset) -> anyhow::Result<Self::Asset>;
+}
+
+/// Second half of loading a custom asset, inserting it into the manager
+pub type InsertAsset = Box<dyn FnOnce(&mut ResourceManager) + Send>;
+
+/// [`AssetLoader`] with its resource type erased, so loaders of different types can be stored
+/// together
+pub trait DynAssetLoader: Send + Sync {
+	/// See [`AssetLoader::type_name`]
+	fn type_name(&self) -> &str;
+
+	/// See [`AssetLoader::extensions`]
+	fn extensions(&self) -> &[&str];
+
+	/// See [`AssetLoader::check`]
+	fn check(&self, entry: &AssetEntry) -> anyhow::Result<()>;
+
+	/// Reserve a handle of the resource type for an asset being loaded
+	fn reserve(&self, manager: &mut ResourceManager, name: &str);
+
+	/// Load an asset, returning what inserts it into the manager
+	///
+	/// # Errors
+	/// If the loader fails, or makes a resource named other than the entry
+	fn load(&self, asset: &ManifestAsset) -> anyhow::Result<InsertAsset>;
+}
+
+impl<L: AssetLoader> DynAssetLoader for L {
+	fn type_name(&self) -> &str {
+		AssetLoader::type_name(self)
+	}
+
+	fn extensions(&self) -> &[&str] {
+		AssetLoader::extensions(self)
+	}
+
+	fn check(&self, entry: &AssetEntry) -> anyhow::Result<()> {
+		AssetLoader::check(self, entry)
+	}
+
+	fn reserve(&self, manager: &mut ResourceManager, name: &str) {
+		manager.reserve::<L::Asset>(name);
+	}
+
+	fn load(&self, asset: &ManifestAsset) -> anyhow::Result<InsertAsset> {
+		let resource = AssetLoader::load(self, asset)?;
+		// a resource under another name would leave the reserved one loading forever
+		let name = resource.name();
+		if name != asset.entry.name {
+			anyhow::bail!("the {} loader made a resource named '{name}' instead of '{}'", self.type_name(), asset.entry.name);
+		}
+		Ok(Box::new(move |manager: &mut ResourceManager| {
+			manager.insert(resource);
+		}))
+	}
+}
+
+/// Registry of the [`AssetLoader`]s, shared by the plugins registering them and the thread
+/// loading the assets
+///
+/// Clones share the same registry. A loader registered for a type replaces the previous one.
+#[derive(Clone, Default)]
+pub struct AssetLoaders {
+	loaders: Arc<RwLock<Vec<Arc<dyn DynAssetLoader>>>>,
+}
+
+impl AssetLoaders {
+	/// Register a loader
+	pub fn register<L: AssetLoader>(&self, loader: L) {
+		let mut loaders = self.loaders.write().unwrap_or_else(PoisonError::into_inner);
+		loaders.retain(|registered| registered.type_name() != AssetLoader::type_name(&loader));
+		loaders.push(Arc::new(loader));
+	}
+
+	/// Loader of a type
+	pub fn get(&self, type_name: &str) -> Option<Arc<dyn DynAssetLoader>> {
+		self.all().into_iter().find(|loader| loader.type_name() == type_name)
+	}
+
+	/// Loader of the files with the extension of `path`
+	pub fn for_extension(&self, path: &Path) -> Option<Arc<dyn DynAssetLoader>> {
+		let extension = path.extension()?.to_str()?.to_lowercase();
+		self.all().into_iter().find(|loader| loader.extensions().contains(&extension.as_str()))
+	}
+
+	/// Loader of a manifest asset, by its `type`, or by its extension if it has no `type`
+	pub fn for_asset(&self, asset: &ManifestAsset) -> Option<Arc<dyn DynAssetLoader>> {
+		if asset.entry.class.is_empty() {
+			self.for_extension(&asset.path())
+		} else {
+			self.get(&asset.entry.class)
+		}
+	}
+
+	/// Every registered loader
+	pub fn all(&self) -> Vec<Arc<dyn DynAssetLoader>> {
+		self.loaders.read().unwrap_or_else(PoisonError::into_inner).clone()
+	}
+}
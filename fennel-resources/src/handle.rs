@@ -0,0 +1,137 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Typed reference to a resource stored in a [`crate::manager::ResourceManager`]
+///
+/// A handle is the index of a slot in the manager plus the generation of that slot. Removing a
+/// resource bumps the generation, so handles to it become stale instead of silently pointing
+/// at whatever is inserted into the slot next. Handles are `Copy` and cheap to compare and hash.
+///
+/// A `Handle` is weak: it doesn't keep the resource alive. See [`StrongHandle`] for resources
+/// that are unloaded once they're no longer used.
+pub struct Handle<T> {
+	index: u32,
+	generation: u32,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+	pub(crate) fn new(index: u32, generation: u32) -> Self {
+		Self { index, generation, marker: PhantomData }
+	}
+
+	/// Index of the slot in the manager
+	pub fn index(&self) -> u32 {
+		self.index
+	}
+
+	/// Generation of the slot when the handle was created
+	pub fn generation(&self) -> u32 {
+		self.generation
+	}
+}
+
+// implemented by hand as deriving would require `T` itself to implement these traits
+
+impl<T> Clone for Handle<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.index == other.index && self.generation == other.generation
+	}
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.index.hash(state);
+		self.generation.hash(state);
+	}
+}
+
+impl<T> fmt::Debug for Handle<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Handle<{}>({}v{})", std::any::type_name::<T>(), self.index, self.generation)
+	}
+}
+
+/// Handle keeping its resource alive, see [`crate::manager::ResourceManager::strong`]
+///
+/// A resource released with [`crate::manager::ResourceManager::release`] is removed by
+/// [`crate::manager::ResourceManager::collect_unused`] once its last strong handle or
+/// [`KeepAlive`] is dropped.
+pub struct StrongHandle<T> {
+	handle: Handle<T>,
+	keep_alive: KeepAlive,
+}
+
+impl<T> StrongHandle<T> {
+	pub(crate) fn new(handle: Handle<T>, keep_alive: KeepAlive) -> Self {
+		Self { handle, keep_alive }
+	}
+
+	/// The weak handle, to access the resource
+	pub fn handle(&self) -> Handle<T> {
+		self.handle
+	}
+
+	/// Keep the resource alive without the type of the handle, for example to hold resources of
+	/// several types in one collection
+	pub fn keep_alive(&self) -> KeepAlive {
+		self.keep_alive.clone()
+	}
+}
+
+impl<T> Clone for StrongHandle<T> {
+	fn clone(&self) -> Self {
+		Self { handle: self.handle, keep_alive: self.keep_alive.clone() }
+	}
+}
+
+impl<T> PartialEq for StrongHandle<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.handle == other.handle
+	}
+}
+
+impl<T> Eq for StrongHandle<T> {}
+
+impl<T> fmt::Debug for StrongHandle<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Strong{:?}", self.handle)
+	}
+}
+
+impl<T> From<&StrongHandle<T>> for Handle<T> {
+	fn from(strong: &StrongHandle<T>) -> Self {
+		strong.handle
+	}
+}
+
+/// Untyped reference keeping a resource alive, counted along with its [`StrongHandle`]s
+#[derive(Clone)]
+pub struct KeepAlive {
+	/// Only held, the manager counts the clones of its side
+	_refs: Arc<()>,
+}
+
+impl KeepAlive {
+	pub(crate) fn new(refs: &Arc<()>) -> Self {
+		Self { _refs: Arc::clone(refs) }
+	}
+}
+
+impl fmt::Debug for KeepAlive {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("KeepAlive")
+	}
+}
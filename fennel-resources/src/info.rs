@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::manager::ResourceManager;
+
+/// Metadata of a loaded asset, see [`crate::resource::Resource::metadata`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetMetadata {
+	/// An image of `width`x`height` pixels
+	Image { width: u32, height: u32 },
+	/// A sprite sheet of `width`x`height` pixels and its frames as (x, y, w, h) rectangles
+	SpriteSheet { width: u32, height: u32, frames: HashMap<String, (f32, f32, f32, f32)> },
+	/// A font, measured at one size
+	Font { metrics: FontMetrics, advances: GlyphAdvances },
+	/// A sound, `None` if its duration can't be known without decoding it
+	Audio { duration: Option<Duration> },
+}
+
+/// Vertical metrics of a font at a point size, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+	/// Point size the font was measured at
+	pub size: f32,
+	/// Height of a line of text
+	pub height: f32,
+	/// Distance from the baseline to the top of the tallest glyph
+	pub ascent: f32,
+	/// Distance from the baseline to the bottom of the lowest glyph, negative below it
+	pub descent: f32,
+	/// Distance between the tops of two consecutive lines
+	pub line_spacing: f32,
+}
+
+impl FontMetrics {
+	/// Metrics at another point size. Outlines scale linearly, so this is exact up to the
+	/// rounding and hinting of the real font
+	pub fn scaled(&self, size: f32) -> Self {
+		let factor = if self.size > 0.0 { size / self.size } else { 1.0 };
+		Self {
+			size,
+			height: self.height * factor,
+			ascent: self.ascent * factor,
+			descent: self.descent * factor,
+			line_spacing: self.line_spacing * factor,
+		}
+	}
+}
+
+/// Horizontal advances of the glyphs of a font, at the size of its [`FontMetrics`], to measure
+/// text off the graphics thread
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlyphAdvances {
+	/// Advance of each measured character
+	pub advances: HashMap<char, f32>,
+	/// Advance of the characters that weren't measured
+	pub fallback: f32,
+}
+
+impl GlyphAdvances {
+	/// Width of a line of text, without kerning
+	pub fn width(&self, text: &str) -> f32 {
+		text.chars().map(|c| self.advances.get(&c).copied().unwrap_or(self.fallback)).sum()
+	}
+}
+
+/// Metadata of the loaded assets, readable from any thread without the resources themselves
+///
+/// Filled from one or more [`ResourceManager`]s with [`AssetInfo::sync`], for example by the
+/// graphics and audio plugins, which keep it in the ECS world up to date as assets load and
+/// unload. Systems use it for sprite sizes, text layout and the like.
+#[derive(Debug, Default, Clone)]
+pub struct AssetInfo {
+	/// Metadata by the name of the manager it comes from
+	sources: HashMap<&'static str, SourceInfo>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct SourceInfo {
+	/// [`ResourceManager::revision`] the metadata was read at
+	revision: Option<u64>,
+	assets: HashMap<String, AssetMetadata>,
+}
+
+impl AssetInfo {
+	/// Update the metadata read from the manager named `source` with the resources inserted or
+	/// removed since the last sync, see [`ResourceManager::changes_since`]. Everything is read
+	/// again the first time, or if the manager forgot the changes
+	///
+	/// # Returns
+	/// Did anything change?
+	pub fn sync(&mut self, source: &'static str, manager: &ResourceManager) -> bool {
+		let info = self.sources.entry(source).or_default();
+		if info.revision == Some(manager.revision()) {
+			return false;
+		}
+		let metadata = |name: &str| manager.get_named(name).ok()?.metadata();
+		match info.revision.and_then(|revision| manager.changes_since(revision)) {
+			Some(changes) => {
+				for name in changes {
+					match metadata(name) {
+						Some(metadata) => {
+							info.assets.insert(name.to_string(), metadata);
+						}
+						None => {
+							info.assets.remove(name);
+						}
+					}
+				}
+			}
+			None => {
+				info.assets = manager
+					.names()
+					.filter_map(|name| Some((name.to_string(), metadata(name)?)))
+					.collect();
+			}
+		}
+		info.revision = Some(manager.revision());
+		true
+	}
+
+	/// Metadata of an asset
+	pub fn get(&self, name: &str) -> Option<&AssetMetadata> {
+		self.sources.values().find_map(|info| info.assets.get(name))
+	}
+
+	/// Size in pixels of an image or a whole sprite sheet
+	pub fn image_size(&self, name: &str) -> Option<(u32, u32)> {
+		match self.get(name)? {
+			AssetMetadata::Image { width, height } | AssetMetadata::SpriteSheet { width, height, .. } => Some((*width, *height)),
+			_ => None,
+		}
+	}
+
+	/// Rectangle (x, y, w, h) of a frame of a sprite sheet
+	pub fn frame(&self, sheet: &str, frame: &str) -> Option<(f32, f32, f32, f32)> {
+		match self.get(sheet)? {
+			AssetMetadata::SpriteSheet { frames, .. } => frames.get(frame).copied(),
+			_ => None,
+		}
+	}
+
+	/// Size in pixels of what a sprite draws: an image, or a frame for a `"sheet:frame"`
+	/// reference
+	pub fn sprite_size(&self, image: &str) -> Option<(f32, f32)> {
+		if let Some((width, height)) = self.image_size(image) {
+			return Some((width as f32, height as f32));
+		}
+		let (sheet, frame) = image.split_once(':')?;
+		self.frame(sheet, frame).map(|(_, _, w, h)| (w, h))
+	}
+
+	/// Metrics of a font at a point size, 0 for the size it was measured at, which is its
+	/// default size if it has one
+	pub fn font_metrics(&self, font: &str, size: f32) -> Option<FontMetrics> {
+		match self.get(font)? {
+			AssetMetadata::Font { metrics, .. } if size > 0.0 => Some(metrics.scaled(size)),
+			AssetMetadata::Font { metrics, .. } => Some(*metrics),
+			_ => None,
+		}
+	}
+
+	/// Width of a line of text drawn with a font at a point size, see
+	/// [`AssetInfo::font_metrics`] for the size
+	pub fn text_width(&self, font: &str, size: f32, text: &str) -> Option<f32> {
+		match self.get(font)? {
+			AssetMetadata::Font { metrics, advances } => {
+				let width = advances.width(text);
+				Some(if size > 0.0 && metrics.size > 0.0 { width * size / metrics.size } else { width })
+			}
+			_ => None,
+		}
+	}
+
+	/// Distance between two lines of text of a font at a point size, see
+	/// [`AssetInfo::font_metrics`]
+	pub fn line_height(&self, font: &str, size: f32) -> Option<f32> {
+		self.font_metrics(font, size).map(|metrics| metrics.line_spacing)
+	}
+
+	/// Duration of a sound, `None` if it isn't loaded or its duration is unknown
+	pub fn audio_duration(&self, name: &str) -> Option<Duration> {
+		match self.get(name)? {
+			AssetMetadata::Audio { duration } => *duration,
+			_ => None,
+		}
+	}
+
+	/// Number of assets with metadata
+	pub fn len(&self) -> usize {
+		self.sources.values().map(|info| info.assets.len()).sum()
+	}
+
+	/// Is there no metadata?
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
@@ -6,6 +6,12 @@
 //! This library consists of the following modules:
 //! - [`manager`] - Implements the functionality of the resource manager, including caching, insertion, and fetching resources
 //! - [`resource`] - Defines the traits for resources to implement to be compatible with the resource manager
+//! - [`handle`] - Typed handles to resources stored in the resource manager
+//! - [`info`] - Metadata of the loaded assets, readable without the resources themselves
+//! - [`loader`] - Loads resources on worker threads and tracks the progress
+//! - [`asset_loader`] - Loaders of custom asset types, registered by plugins
+//! - [`manifest`] - Schema of the asset manifests, with includes and validation
+//! - [`vfs`] - Virtual filesystem with mount points, overlays and archive packs
 
 use thiserror::Error;
 
@@ -14,11 +20,31 @@ use thiserror::Error;
 pub enum ResourceError {
 	#[error("requested resource was not found in cache")]
 	ResourceDoesNotExist,
+	#[error("resource {name} is not a {expected}")]
+	WrongType { name: String, expected: &'static str },
+	#[error("handle refers to a resource that was removed or replaced")]
+	StaleHandle,
+	#[error("resource is still loading")]
+	NotLoaded,
+	#[error("resource failed to load: {0}")]
+	LoadFailed(String),
 }
 
+/// Custom asset types
+pub mod asset_loader;
+/// Typed handles to resources
+pub mod handle;
+/// Asset metadata
+pub mod info;
+/// Background loading of resources
+pub mod loader;
 /// The resource manager itself
 pub mod manager;
+/// Asset manifests
+pub mod manifest;
 /// All the things to represent a resource type
 pub mod resource;
+/// Virtual filesystem the loaders read from
+pub mod vfs;
 #[cfg(test)]
 mod tests;
\ No newline at end of file
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::asset_loader::DynAssetLoader;
+use crate::handle::Handle;
+use crate::manager::ResourceManager;
+use crate::manifest::ManifestAsset;
+use crate::resource::Resource;
+
+/// Group of the resources requested without one
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Work sent to a worker thread, returning what's left to do on the loading thread
+type Job<C> = Box<dyn FnOnce() -> Finished<C> + Send>;
+/// Request id, resource name and the finishing half of the request
+type Finished<C> = (u64, String, Finish<C>);
+/// Second half of a request, inserting the resource into the manager
+type Finish<C> = Box<dyn FnOnce(&mut ResourceManager, &mut C) -> anyhow::Result<()> + Send>;
+
+/// Background resource loader
+///
+/// Loading a resource is split in two: `work` runs on a pool of worker threads and does the
+/// file IO and decoding, `finish` runs on the thread calling [`Loader::poll`] and turns the
+/// result into a resource with the help of a context `C`, typically the thread-local store
+/// the GPU textures are uploaded to. A handle is reserved as soon as a resource is requested, its
+/// [`crate::manager::LoadState`] tells when it can be used.
+///
+/// Requests are counted per group, so a loading screen can show the [`LoadProgress`] of the
+/// group it waits for.
+pub struct Loader<C> {
+	jobs: Option<Sender<Job<C>>>,
+	finished: Receiver<Finished<C>>,
+	workers: Vec<JoinHandle<()>>,
+	/// Requests in flight by resource name, with their id and group
+	pending: HashMap<String, (u64, String)>,
+	groups: HashMap<String, LoadProgress>,
+	next_id: u64,
+}
+
+/// Progress of the requests of a group
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+	/// Number of requested resources, without the ones removed from the manager while they
+	/// were loading
+	pub total: usize,
+	/// Number of resources loaded successfully
+	pub loaded: usize,
+	/// Number of resources that failed to load
+	pub failed: usize,
+}
+
+impl LoadProgress {
+	/// Fraction of the requests that are done, 1 if nothing was requested
+	pub fn fraction(&self) -> f32 {
+		if self.total == 0 {
+			1.0
+		} else {
+			(self.loaded + self.failed) as f32 / self.total as f32
+		}
+	}
+
+	/// Are all requests done, successfully or not?
+	pub fn is_done(&self) -> bool {
+		self.loaded + self.failed >= self.total
+	}
+}
+
+impl std::ops::Add for LoadProgress {
+	type Output = Self;
+
+	fn add(self, other: Self) -> Self {
+		Self {
+			total: self.total + other.total,
+			loaded: self.loaded + other.loaded,
+			failed: self.failed + other.failed,
+		}
+	}
+}
+
+/// A resource that failed to load
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadFailure {
+	/// Name of the resource
+	pub name: String,
+	/// Group the resource was requested in
+	pub group: String,
+	/// The error
+	pub error: String,
+}
+
+impl<C: 'static> Loader<C> {
+	/// Create a loader with `threads` worker threads, at least one
+	pub fn new(threads: usize) -> Self {
+		let (jobs, job_receiver) = channel::<Job<C>>();
+		let (finished_sender, finished) = channel();
+		let job_receiver = Arc::new(Mutex::new(job_receiver));
+		let workers = (0..threads.max(1))
+			.map(|i| {
+				let jobs = Arc::clone(&job_receiver);
+				let finished = finished_sender.clone();
+				std::thread::Builder::new()
+					.name(format!("fennel-loader-{i}"))
+					.spawn(move || worker(jobs, finished))
+					.expect("failed to spawn a loader thread")
+			})
+			.collect();
+
+		Self {
+			jobs: Some(jobs),
+			finished,
+			workers,
+			pending: HashMap::new(),
+			groups: HashMap::new(),
+			next_id: 0,
+		}
+	}
+
+	/// Request a resource to be loaded in the background
+	///
+	/// `work` runs on a worker thread, `finish` gets its result on the next [`Loader::poll`]
+	/// and creates the resource, which must be named `name`, else it fails to load. A request for
+	/// a resource that is already being loaded only returns its handle.
+	pub fn request<T, D, W, F>(&mut self, manager: &mut ResourceManager, name: &str, group: &str, work: W, finish: F) -> Handle<T>
+	where
+		T: Resource,
+		D: Send + 'static,
+		W: FnOnce() -> anyhow::Result<D> + Send + 'static,
+		F: FnOnce(D, &mut C) -> anyhow::Result<T> + Send + 'static,
+	{
+		if self.pending.contains_key(name) {
+			return manager.reserve(name);
+		}
+		let handle = manager.reserve(name);
+		let expected = name.to_string();
+		self.submit(name, group, move || {
+			let data = work()?;
+			let finish: Finish<C> = Box::new(move |manager: &mut ResourceManager, context: &mut C| {
+				let resource = finish(data, context)?;
+				// a resource under another name would leave the reserved one loading forever
+				let name = resource.name();
+				if name != expected {
+					anyhow::bail!("loaded a resource named '{name}' instead of '{expected}'");
+				}
+				manager.insert(resource);
+				Ok(())
+			});
+			Ok(finish)
+		});
+		handle
+	}
+
+	/// Request a manifest asset to be loaded in the background by the [`AssetLoader`] of its
+	/// type, which doesn't need the context
+	///
+	/// [`AssetLoader`]: crate::asset_loader::AssetLoader
+	pub fn request_asset(&mut self, manager: &mut ResourceManager, asset: &ManifestAsset, group: &str, loader: Arc<dyn DynAssetLoader>) {
+		let name = asset.entry.name.clone();
+		if self.pending.contains_key(&name) {
+			return;
+		}
+		loader.reserve(manager, &name);
+		let asset = asset.clone();
+		self.submit(&name, group, move || {
+			let insert = loader.load(&asset)?;
+			let finish: Finish<C> = Box::new(move |manager: &mut ResourceManager, _: &mut C| {
+				insert(manager);
+				Ok(())
+			});
+			Ok(finish)
+		});
+	}
+
+	/// Send `work` to the workers, counting the request in its group
+	fn submit<W>(&mut self, name: &str, group: &str, work: W)
+	where
+		W: FnOnce() -> anyhow::Result<Finish<C>> + Send + 'static,
+	{
+		let id = self.next_id;
+		self.next_id += 1;
+		self.pending.insert(name.to_string(), (id, group.to_string()));
+		self.groups.entry(group.to_string()).or_default().total += 1;
+
+		let name = name.to_string();
+		let job: Job<C> = Box::new(move || {
+			let finish = catch_unwind(AssertUnwindSafe(work))
+				.unwrap_or_else(|_| Err(anyhow::anyhow!("loading panicked")))
+				.unwrap_or_else(|e| Box::new(move |_: &mut ResourceManager, _: &mut C| Err(e)));
+			(id, name, finish)
+		});
+		self.jobs
+			.as_ref()
+			.expect("loader is shutting down")
+			.send(job)
+			.expect("loader threads are gone");
+	}
+
+	/// Finish the resources loaded by the workers, for up to `budget`
+	///
+	/// At least one resource is finished if there is one ready, so loading always goes on.
+	pub fn poll(&mut self, manager: &mut ResourceManager, context: &mut C, budget: Duration) -> Vec<LoadFailure> {
+		let start = Instant::now();
+		let mut failures = Vec::new();
+		while let Ok(finished) = self.finished.try_recv() {
+			failures.extend(self.finish(manager, context, finished));
+			if start.elapsed() >= budget {
+				break;
+			}
+		}
+		failures
+	}
+
+	/// Block until every requested resource is finished
+	pub fn wait(&mut self, manager: &mut ResourceManager, context: &mut C) -> Vec<LoadFailure> {
+		let mut failures = Vec::new();
+		while !self.pending.is_empty() {
+			match self.finished.recv_timeout(Duration::from_millis(100)) {
+				Ok(finished) => failures.extend(self.finish(manager, context, finished)),
+				Err(RecvTimeoutError::Timeout) => continue,
+				Err(RecvTimeoutError::Disconnected) => break,
+			}
+		}
+		failures
+	}
+
+	/// Progress of a group, empty if nothing was requested in it
+	pub fn progress(&self, group: &str) -> LoadProgress {
+		self.groups.get(group).copied().unwrap_or_default()
+	}
+
+	/// Progress of every group
+	pub fn groups(&self) -> &HashMap<String, LoadProgress> {
+		&self.groups
+	}
+
+	/// Progress of all groups together
+	pub fn total_progress(&self) -> LoadProgress {
+		self.groups.values().fold(LoadProgress::default(), |total, &progress| total + progress)
+	}
+
+	/// Is anything still being loaded?
+	pub fn is_loading(&self) -> bool {
+		!self.pending.is_empty()
+	}
+
+	/// Forget the progress of a group and drop its requests in flight, for example when its
+	/// resources are removed from the manager
+	pub fn forget_group(&mut self, group: &str) {
+		self.groups.remove(group);
+		self.pending.retain(|_, (_, pending_group)| pending_group != group);
+	}
+
+	fn finish(&mut self, manager: &mut ResourceManager, context: &mut C, (id, name, finish): Finished<C>) -> Option<LoadFailure> {
+		// the request could have been forgotten, or forgotten and requested again since
+		if self.pending.get(&name).is_none_or(|(pending_id, _)| *pending_id != id) {
+			return None;
+		}
+		let (_, group) = self.pending.remove(&name)?;
+		let progress = self.groups.entry(group.clone()).or_default();
+		// the reserved resource was removed while it was loading, so it's no longer requested
+		if !manager.is_cached(&name) {
+			progress.total = progress.total.saturating_sub(1);
+			return None;
+		}
+		match finish(manager, context) {
+			Ok(()) => {
+				progress.loaded += 1;
+				None
+			}
+			Err(e) => {
+				progress.failed += 1;
+				let error = format!("{e:#}");
+				manager.fail(&name, error.clone());
+				Some(LoadFailure { name, group, error })
+			}
+		}
+	}
+}
+
+impl<C: 'static> Default for Loader<C> {
+	/// A loader with a worker thread per core, up to 4
+	fn default() -> Self {
+		let threads = std::thread::available_parallelism().map_or(1, |n| n.get().min(4));
+		Self::new(threads)
+	}
+}
+
+impl<C> Drop for Loader<C> {
+	fn drop(&mut self) {
+		// closing the job channel stops the workers once they're done with their current job
+		self.jobs.take();
+		for worker in self.workers.drain(..) {
+			let _ = worker.join();
+		}
+	}
+}
+
+fn worker<C>(jobs: Arc<Mutex<Receiver<Job<C>>>>, finished: Sender<Finished<C>>) {
+	loop {
+		// the lock is released before running the job, so the other workers can take one
+		let job = match jobs.lock() {
+			Ok(jobs) => jobs.recv(),
+			Err(_) => return,
+		};
+		let Ok(job) = job else { return };
+		if finished.send(job()).is_err() {
+			return;
+		}
+	}
+}
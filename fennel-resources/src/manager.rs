@@ -1,14 +1,110 @@
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
 use anyhow::Context;
+use crate::handle::{Handle, KeepAlive, StrongHandle};
 use crate::resource::Resource;
+use crate::ResourceError;
+
+/// Number of changes remembered for [`ResourceManager::changes_since`]
+const MAX_CHANGES: usize = 4096;
 
 /// A struct that represents the resource manager, which stores the resources
+///
+/// Resources live in slots addressed by typed [`Handle`]s. Names are only needed to find a
+/// handle once with [`ResourceManager::handle`], after which access is a bounds-checked index
+/// with no string hashing and no downcasting left to the caller.
+///
+/// Resources are kept until they're removed. A resource can instead be
+/// [released](ResourceManager::release), after which it's reference counted: it's removed by
+/// [`ResourceManager::collect_unused`] once no [`StrongHandle`], [`KeepAlive`] or dependent
+/// resource refers to it.
 pub struct ResourceManager {
-	cache: HashMap<String, Box<dyn Resource>>,
+	slots: Vec<Slot>,
+	/// Indices of the empty slots
+	free: Vec<u32>,
+	names: HashMap<String, u32>,
+	/// Bumped whenever a resource is inserted or removed
+	revision: u64,
+	/// Revisions and names of the latest inserted or removed resources, oldest first
+	changes: VecDeque<(u64, String)>,
+}
+
+struct Slot {
+	generation: u32,
+	/// Type of the resource, known before it's loaded
+	type_id: Option<TypeId>,
+	type_name: &'static str,
+	resource: Option<Box<dyn Resource>>,
+	state: LoadState,
+	source: Option<Source>,
+	/// Shared with the strong references to the resource, counting them
+	refs: Arc<()>,
+	/// Is the resource removed once it's no longer referenced?
+	released: bool,
+	/// Resources kept alive by this one
+	dependencies: Vec<KeepAlive>,
+}
+
+impl Slot {
+	fn new() -> Self {
+		Self {
+			generation: 0,
+			type_id: None,
+			type_name: "",
+			resource: None,
+			state: LoadState::Loading,
+			source: None,
+			refs: Arc::new(()),
+			released: false,
+			dependencies: Vec::new(),
+		}
+	}
+
+	/// Forget about the references to the previous resource of the slot
+	fn reset_refs(&mut self) {
+		self.refs = Arc::new(());
+		self.released = false;
+		self.dependencies.clear();
+	}
+
+	fn ref_count(&self) -> usize {
+		Arc::strong_count(&self.refs) - 1
+	}
+}
+
+/// Memory used by the resources of a type, see [`ResourceManager::memory_report`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+	/// Name of the resource type
+	pub type_name: &'static str,
+	/// Number of loaded resources of this type
+	pub count: usize,
+	/// Sum of their [`Resource::memory_usage`]
+	pub bytes: usize,
+}
+
+/// File a resource was loaded from, to reload it when the file changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+	/// Path of the file, possibly a VFS path
+	pub path: PathBuf,
+	/// Modification time of the file when it was loaded
+	pub modified: Option<SystemTime>,
 }
 
-unsafe impl Send for ResourceManager {}
-unsafe impl Sync for ResourceManager {}
+/// Loading state of a resource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadState {
+	/// The resource was reserved and is being loaded
+	Loading,
+	/// The resource can be used
+	Loaded,
+	/// Loading failed with this error
+	Failed(String),
+}
 
 impl ResourceManager {
 	/// Create a new instance of [`ResourceManager`]
@@ -17,27 +113,122 @@ impl ResourceManager {
 	/// A new empty instance of [`ResourceManager`]
 	pub fn new() -> Self {
 		Self {
-			cache: HashMap::new(),
+			slots: Vec::new(),
+			free: Vec::new(),
+			names: HashMap::new(),
+			revision: 0,
+			changes: VecDeque::new(),
 		}
 	}
 
 	/// Insert a type implementing [`Resource`] into [`ResourceManager`]
 	///
+	/// A resource with the same name is replaced. If it has the same type its handles stay
+	/// valid and now refer to the new resource, otherwise they become stale.
+	///
 	/// # Arguments
 	/// * `resource`: type implementing [`Resource`] trait
 	///
+	/// # Returns
+	/// A handle to the resource
+	///
 	/// # Examples
 	/// ```ignore
 	/// use fennel_resources::manager::ResourceManager;
 	/// let manager = ResourceManager::new();
-	/// manager.insert(MyResource { name: "my_resource" });
+	/// let handle = manager.insert(MyResource { name: "my_resource" });
 	/// ```
-	pub fn insert<T: Resource + 'static>(&mut self, resource: T) {
-		let name = resource.name().clone().to_string();
-		self.cache.insert(name, Box::new(resource));
+	pub fn insert<T: Resource>(&mut self, resource: T) -> Handle<T> {
+		let name = resource.name();
+		let handle = self.slot_for::<T>(name.clone());
+		let slot = &mut self.slots[handle.index() as usize];
+		slot.resource = Some(Box::new(resource));
+		slot.state = LoadState::Loaded;
+		self.changed(name);
+		handle
+	}
+
+	/// Reserve a handle for a resource that is still being loaded
+	///
+	/// [`ResourceManager::get`] fails with [`ResourceError::NotLoaded`] until the resource is
+	/// inserted, and [`ResourceManager::load_state`] tells how loading goes. A loaded resource
+	/// of the same type under this name stays usable until it's replaced.
+	pub fn reserve<T: Resource>(&mut self, name: &str) -> Handle<T> {
+		let handle = self.slot_for::<T>(name.to_string());
+		self.slots[handle.index() as usize].state = LoadState::Loading;
+		handle
+	}
+
+	/// Mark a reserved resource as failed to load
+	pub fn fail(&mut self, name: &str, error: String) {
+		if let Some(&index) = self.names.get(name) {
+			self.slots[index as usize].state = LoadState::Failed(error);
+		}
+	}
+
+	/// Remember the file a resource is loaded from, along with its current modification time
+	pub fn set_source<P: Into<PathBuf>>(&mut self, name: &str, path: P, modified: Option<SystemTime>) {
+		if let Some(&index) = self.names.get(name) {
+			self.slots[index as usize].source = Some(Source { path: path.into(), modified });
+		}
+	}
+
+	/// File a resource was loaded from, if it was recorded with [`ResourceManager::set_source`]
+	pub fn source(&self, name: &str) -> Option<&Source> {
+		self.slots[*self.names.get(name)? as usize].source.as_ref()
+	}
+
+	/// Names and sources of the resources whose file was modified since it was loaded, according
+	/// to `modified`. The new modification times are recorded, so a change is reported once
+	pub fn changed_sources(&mut self, modified: impl Fn(&Path) -> Option<SystemTime>) -> Vec<(String, PathBuf)> {
+		let mut changed = Vec::new();
+		for (name, &index) in &self.names {
+			let Some(source) = &mut self.slots[index as usize].source else { continue };
+			let current = modified(&source.path);
+			if current.is_some() && current != source.modified {
+				source.modified = current;
+				changed.push((name.clone(), source.path.clone()));
+			}
+		}
+		changed.sort();
+		changed
+	}
+
+	/// Loading state of a resource
+	pub fn load_state<T: Resource>(&self, handle: Handle<T>) -> Result<LoadState, ResourceError> {
+		Ok(self.slot(handle)?.state.clone())
+	}
+
+	/// Find the slot of `name`, or allocate one. The generation is bumped if the slot held
+	/// another type
+	fn slot_for<T: Resource>(&mut self, name: String) -> Handle<T> {
+		if let Some(&index) = self.names.get(&name) {
+			let slot = &mut self.slots[index as usize];
+			if slot.type_id != Some(TypeId::of::<T>()) {
+				slot.generation += 1;
+				slot.type_id = Some(TypeId::of::<T>());
+				slot.type_name = std::any::type_name::<T>();
+				slot.resource = None;
+				slot.reset_refs();
+			}
+			return Handle::new(index, slot.generation);
+		}
+
+		let index = match self.free.pop() {
+			Some(index) => index,
+			None => {
+				self.slots.push(Slot::new());
+				(self.slots.len() - 1) as u32
+			}
+		};
+		let slot = &mut self.slots[index as usize];
+		slot.type_id = Some(TypeId::of::<T>());
+		slot.type_name = std::any::type_name::<T>();
+		self.names.insert(name, index);
+		Handle::new(index, slot.generation)
 	}
 
-	/// Remove a type implementing [`Resource`] from [`ResourceManager`]
+	/// Remove a type implementing [`Resource`] from [`ResourceManager`], its handles become stale
 	///
 	/// # Arguments
 	/// * `name`: the unique name of the resource
@@ -54,10 +245,151 @@ impl ResourceManager {
 	/// let same_resource = manager.remove("my_resource");
 	/// ```
 	pub fn remove(&mut self, name: &str) -> anyhow::Result<Box<dyn Resource>> {
-		self.cache.remove(name).context("resource does not exist")
+		let index = self.names.remove(name).context("resource does not exist")?;
+		let slot = &mut self.slots[index as usize];
+		slot.generation += 1;
+		slot.type_id = None;
+		slot.state = LoadState::Loading;
+		slot.source = None;
+		slot.reset_refs();
+		let resource = slot.resource.take();
+		self.free.push(index);
+		self.changed(name.to_string());
+		resource.context("resource is not loaded")
+	}
+
+	/// Get a strong handle to a resource, keeping it alive once it's released
+	pub fn strong<T: Resource>(&self, handle: Handle<T>) -> Result<StrongHandle<T>, ResourceError> {
+		let slot = self.slot(handle)?;
+		Ok(StrongHandle::new(handle, KeepAlive::new(&slot.refs)))
+	}
+
+	/// Keep a resource alive by name, whatever its type
+	pub fn keep_alive(&self, name: &str) -> Result<KeepAlive, ResourceError> {
+		let index = *self.names.get(name).ok_or(ResourceError::ResourceDoesNotExist)?;
+		Ok(KeepAlive::new(&self.slots[index as usize].refs))
+	}
+
+	/// Let a resource be removed by [`ResourceManager::collect_unused`] once it's no longer
+	/// referenced, instead of being kept until it's removed by hand
+	pub fn release(&mut self, name: &str) -> Result<(), ResourceError> {
+		let index = *self.names.get(name).ok_or(ResourceError::ResourceDoesNotExist)?;
+		self.slots[index as usize].released = true;
+		Ok(())
+	}
+
+	/// Keep a released resource again until it's removed by hand
+	pub fn retain(&mut self, name: &str) -> Result<(), ResourceError> {
+		let index = *self.names.get(name).ok_or(ResourceError::ResourceDoesNotExist)?;
+		self.slots[index as usize].released = false;
+		Ok(())
+	}
+
+	/// Keep `dependency` alive as long as `name` is stored, for example the texture of a
+	/// resource made from it
+	pub fn add_dependency(&mut self, name: &str, dependency: &str) -> Result<(), ResourceError> {
+		let keep_alive = self.keep_alive(dependency)?;
+		let index = *self.names.get(name).ok_or(ResourceError::ResourceDoesNotExist)?;
+		self.slots[index as usize].dependencies.push(keep_alive);
+		Ok(())
+	}
+
+	/// Number of strong references to a resource, 0 if it doesn't exist
+	pub fn ref_count(&self, name: &str) -> usize {
+		self.names.get(name).map_or(0, |&index| self.slots[index as usize].ref_count())
+	}
+
+	/// Remove the released resources that are no longer referenced, along with the dependencies
+	/// only they referenced
+	///
+	/// # Returns
+	/// Names of the removed resources, sorted
+	pub fn collect_unused(&mut self) -> Vec<String> {
+		let mut removed = Vec::new();
+		loop {
+			let unused: Vec<String> = self.names
+				.iter()
+				.filter(|(_, index)| {
+					let slot = &self.slots[**index as usize];
+					slot.released && slot.ref_count() == 0
+				})
+				.map(|(name, _)| name.clone())
+				.collect();
+			if unused.is_empty() {
+				break;
+			}
+			for name in unused {
+				self.remove(&name).ok();
+				removed.push(name);
+			}
+		}
+		removed.sort();
+		removed
+	}
+
+	/// Number and memory usage of the loaded resources of each type, the biggest first
+	pub fn memory_report(&self) -> Vec<MemoryUsage> {
+		let mut usage: HashMap<&'static str, MemoryUsage> = HashMap::new();
+		for &index in self.names.values() {
+			let slot = &self.slots[index as usize];
+			let Some(resource) = &slot.resource else { continue };
+			let entry = usage.entry(slot.type_name).or_insert(MemoryUsage { type_name: slot.type_name, count: 0, bytes: 0 });
+			entry.count += 1;
+			entry.bytes += resource.memory_usage();
+		}
+		let mut report: Vec<MemoryUsage> = usage.into_values().collect();
+		report.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.type_name.cmp(b.type_name)));
+		report
+	}
+
+	/// Find the handle of a resource by its name
+	///
+	/// # Errors
+	/// [`ResourceError::ResourceDoesNotExist`] if there is no resource with this name,
+	/// [`ResourceError::WrongType`] if the resource isn't a `T`
+	pub fn handle<T: Resource>(&self, name: &str) -> Result<Handle<T>, ResourceError> {
+		let index = *self.names.get(name).ok_or(ResourceError::ResourceDoesNotExist)?;
+		let slot = &self.slots[index as usize];
+		if slot.type_id == Some(TypeId::of::<T>()) {
+			Ok(Handle::new(index, slot.generation))
+		} else {
+			Err(ResourceError::WrongType {
+				name: name.to_string(),
+				expected: std::any::type_name::<T>(),
+			})
+		}
 	}
 
-	/// Get a type implementing [`Resource`] from [`ResourceManager`]
+	/// Get a resource by its handle
+	///
+	/// # Errors
+	/// [`ResourceError::StaleHandle`] if the resource was removed or replaced by one of
+	/// another type, [`ResourceError::ResourceDoesNotExist`] if the handle comes from another
+	/// manager, [`ResourceError::NotLoaded`] or [`ResourceError::LoadFailed`] if the resource
+	/// was reserved and isn't loaded
+	pub fn get<T: Resource>(&self, handle: Handle<T>) -> Result<&T, ResourceError> {
+		let slot = self.slot(handle)?;
+		let resource: &dyn Any = slot.resource.as_deref().ok_or_else(|| not_loaded(&slot.state))?;
+		resource.downcast_ref::<T>().ok_or(ResourceError::StaleHandle)
+	}
+
+	/// Get a resource by its handle, mutably. See [`ResourceManager::get`]
+	pub fn get_mut<T: Resource>(&mut self, handle: Handle<T>) -> Result<&mut T, ResourceError> {
+		let slot = self.slots.get_mut(handle.index() as usize).ok_or(ResourceError::ResourceDoesNotExist)?;
+		if slot.generation != handle.generation() {
+			return Err(ResourceError::StaleHandle);
+		}
+		let resource: &mut dyn Any = slot.resource.as_deref_mut().ok_or_else(|| not_loaded(&slot.state))?;
+		resource.downcast_mut::<T>().ok_or(ResourceError::StaleHandle)
+	}
+
+	/// Does the handle still refer to a resource?
+	pub fn contains<T: Resource>(&self, handle: Handle<T>) -> bool {
+		self.get(handle).is_ok()
+	}
+
+	/// Get a type implementing [`Resource`] from [`ResourceManager`] by name, without knowing its
+	/// type. Prefer resolving a [`Handle`] once and using [`ResourceManager::get`]
 	///
 	/// # Arguments
 	/// * `name`: the unique name of the resource
@@ -70,14 +402,15 @@ impl ResourceManager {
 	/// ```ignore
 	/// use fennel_resources::manager::ResourceManager;
 	/// let manager = ResourceManager::new();
-	/// let resource = manager.get("my_resource");
+	/// let resource = manager.get_named("my_resource");
 	/// ```
-	pub fn get(&self, name: &str) -> anyhow::Result<&dyn Resource> {
-		Ok(self.cache.get(name).context("resource does not exist")?.as_ref())
+	pub fn get_named(&self, name: &str) -> anyhow::Result<&dyn Resource> {
+		let index = *self.names.get(name).context("resource does not exist")?;
+		self.slots[index as usize].resource.as_deref().context("resource is not loaded")
 	}
 
-	/// Get a type implementing [`Resource`] from [`ResourceManager`]. This function follows Rust's
-	/// borrow rules, you can only have one mutable reference to a resource.
+	/// Get a type implementing [`Resource`] from [`ResourceManager`] by name. This function
+	/// follows Rust's borrow rules, you can only have one mutable reference to a resource.
 	///
 	/// # Arguments
 	/// * `name`: the unique name of the resource
@@ -90,15 +423,76 @@ impl ResourceManager {
 	/// ```ignore
 	/// use fennel_resources::manager::ResourceManager;
 	/// let manager = ResourceManager::new();
-	/// let resource = manager.get_mut("my_resource");
+	/// let resource = manager.get_named_mut("my_resource");
 	/// ```
-	pub fn get_mut(&mut self, name: &str) -> anyhow::Result<&mut dyn Resource> {
-		Ok(self.cache.get_mut(name).context("resource does not exist")?.as_mut())
+	pub fn get_named_mut(&mut self, name: &str) -> anyhow::Result<&mut dyn Resource> {
+		let index = *self.names.get(name).context("resource does not exist")?;
+		self.slots[index as usize].resource.as_deref_mut().context("resource is not loaded")
 	}
 
-	/// Determines whether a resource exists in the cache and returns a boolean
+	/// Determines whether a resource exists in the cache, loaded or not, and returns a boolean
 	pub fn is_cached(&self, name: &str) -> bool {
-		self.cache.contains_key(name)
+		self.names.contains_key(name)
+	}
+
+	/// Names of the stored resources, in no particular order
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.names.keys().map(String::as_str)
+	}
+
+	/// Counter changing whenever a resource is inserted or removed, to tell when something
+	/// derived from the resources, such as [`crate::info::AssetInfo`], is out of date
+	pub fn revision(&self) -> u64 {
+		self.revision
+	}
+
+	/// Names of the resources inserted or removed after `revision`, oldest first and possibly
+	/// repeated
+	///
+	/// # Returns
+	/// `None` if `revision` is older than the remembered changes, or newer than the manager, in
+	/// which case any resource may have changed
+	pub fn changes_since(&self, revision: u64) -> Option<impl Iterator<Item = &str>> {
+		let oldest = self.changes.front().map_or(self.revision, |(oldest, _)| oldest - 1);
+		if revision < oldest || revision > self.revision {
+			return None;
+		}
+		let start = self.changes.partition_point(|(changed, _)| *changed <= revision);
+		Some(self.changes.range(start..).map(|(_, name)| name.as_str()))
+	}
+
+	/// Bump the revision, remembering that `name` was inserted or removed
+	fn changed(&mut self, name: String) {
+		self.revision += 1;
+		if self.changes.len() == MAX_CHANGES {
+			self.changes.pop_front();
+		}
+		self.changes.push_back((self.revision, name));
+	}
+
+	/// Number of stored resources
+	pub fn len(&self) -> usize {
+		self.names.len()
+	}
+
+	/// Is the manager empty?
+	pub fn is_empty(&self) -> bool {
+		self.names.is_empty()
+	}
+
+	fn slot<T>(&self, handle: Handle<T>) -> Result<&Slot, ResourceError> {
+		let slot = self.slots.get(handle.index() as usize).ok_or(ResourceError::ResourceDoesNotExist)?;
+		if slot.generation != handle.generation() {
+			return Err(ResourceError::StaleHandle);
+		}
+		Ok(slot)
+	}
+}
+
+fn not_loaded(state: &LoadState) -> ResourceError {
+	match state {
+		LoadState::Failed(error) => ResourceError::LoadFailed(error.clone()),
+		_ => ResourceError::NotLoaded,
 	}
 }
 
@@ -106,4 +500,30 @@ impl Default for ResourceManager {
 	fn default() -> Self {
 		Self::new()
 	}
-}
\ No newline at end of file
+}
+
+/// A [`ResourceManager`] shared between threads, for example written by the graphics thread as
+/// assets load and read by ECS systems
+///
+/// Locks block until the manager is available instead of failing. A lock poisoned by a thread
+/// that panicked while holding it is taken over, as the manager is consistent between calls of
+/// its methods.
+#[derive(Clone, Default)]
+pub struct SharedResourceManager(Arc<RwLock<ResourceManager>>);
+
+impl SharedResourceManager {
+	/// Share a resource manager
+	pub fn new(manager: ResourceManager) -> Self {
+		Self(Arc::new(RwLock::new(manager)))
+	}
+
+	/// Lock the manager for reading, blocking while it's written to
+	pub fn read(&self) -> RwLockReadGuard<'_, ResourceManager> {
+		self.0.read().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Lock the manager for writing, blocking while it's read or written to
+	pub fn write(&self) -> RwLockWriteGuard<'_, ResourceManager> {
+		self.0.write().unwrap_or_else(PoisonError::into_inner)
+	}
+}
@@ -0,0 +1,384 @@
+//! Schema of the `manifest.toml` files declaring the assets of a directory
+//!
+//! ```toml
+//! include = ["ui/manifest.toml"]
+//!
+//! [[assets]]
+//! name = "player"
+//! path = "player.png"
+//! type = "Image"
+//! filter = "nearest"
+//!
+//! [[assets]]
+//! name = "music"
+//! path = "music.ogg"
+//! type = "Audio"
+//! stream = true
+//! group = "forest"
+//! ```
+//!
+//! Every asset has a `name`, a `path` relative to its manifest, a `type` and an optional
+//! `group`. The other keys are options of the type, such as [`ImageOptions`], read by the
+//! loader of the type with [`AssetEntry::options`]. Types other than the built-in ones are
+//! loaded by an [`AssetLoader`](crate::asset_loader::AssetLoader), which also lets entries
+//! omit the `type` of the files with its extensions. The assets of included manifests come after
+//! the ones of the including manifest, in the order of `include`, with paths relative to the
+//! included manifest.
+//!
+//! [`validate`] checks manifests without loading anything, see the `validate` example.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::asset_loader::AssetLoaders;
+use crate::vfs;
+
+/// File name of the manifests
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+#[derive(Deserialize, Debug, Default)]
+struct RawManifest {
+	#[serde(default)]
+	include: Vec<String>,
+	#[serde(default)]
+	assets: Vec<toml::Value>,
+}
+
+/// An asset declared in a manifest
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AssetEntry {
+	/// Unique name of the asset
+	pub name: String,
+	/// Path of the file, relative to the manifest
+	pub path: String,
+	/// Type of the asset, such as `"Image"`, empty if it's given by the extension of the file
+	#[serde(rename = "type", default)]
+	pub class: String,
+	/// Group the asset is loaded with, assets without a group are loaded at startup
+	#[serde(default)]
+	pub group: Option<String>,
+	/// The other keys, options of the type
+	#[serde(flatten)]
+	pub options: toml::Table,
+}
+
+impl AssetEntry {
+	/// Read the options of the asset as `T`
+	pub fn options<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+		toml::Value::Table(self.options.clone())
+			.try_into()
+			.with_context(|| format!("invalid options for {} asset '{}'", self.class, self.name))
+	}
+}
+
+/// An asset along with the manifest declaring it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestAsset {
+	/// Manifest file declaring the asset, which may be an included file of any name
+	pub manifest: PathBuf,
+	/// Directory of the manifest, the asset path is relative to it
+	pub dir: PathBuf,
+	/// The asset
+	pub entry: AssetEntry,
+}
+
+impl ManifestAsset {
+	/// Path of the asset file
+	pub fn path(&self) -> PathBuf {
+		self.dir.join(&self.entry.path)
+	}
+
+	/// Resolve another path of the asset, such as an atlas, relative to its manifest
+	pub fn resolve(&self, path: &str) -> PathBuf {
+		self.dir.join(path)
+	}
+}
+
+/// Assets of a manifest and of the manifests it includes
+#[derive(Debug, Default, Clone)]
+pub struct Manifest {
+	/// Assets in declaration order, the assets of a manifest before the ones it includes
+	pub assets: Vec<ManifestAsset>,
+	/// Manifest files that were read
+	pub files: Vec<PathBuf>,
+}
+
+impl Manifest {
+	/// Read the `manifest.toml` of a directory and the manifests it includes
+	///
+	/// # Errors
+	/// If a manifest can't be read or parsed, or an asset lacks a name or a path
+	pub fn read<P: AsRef<Path>>(dir: P) -> anyhow::Result<Self> {
+		let (manifest, issues) = Self::read_lenient(dir.as_ref());
+		match issues.into_iter().next() {
+			Some(issue) => Err(anyhow::anyhow!("{issue}")),
+			None => Ok(manifest),
+		}
+	}
+
+	/// Read what can be read, reporting the rest as issues
+	fn read_lenient(dir: &Path) -> (Self, Vec<Issue>) {
+		let mut manifest = Self::default();
+		let mut issues = Vec::new();
+		manifest.read_file(&dir.join(MANIFEST_FILE), &mut HashSet::new(), &mut issues);
+		(manifest, issues)
+	}
+
+	fn read_file(&mut self, path: &Path, visited: &mut HashSet<PathBuf>, issues: &mut Vec<Issue>) {
+		// a manifest included twice declares its assets once
+		if !visited.insert(path.to_path_buf()) {
+			return;
+		}
+		let raw = vfs::read(path)
+			.map_err(anyhow::Error::from)
+			.and_then(|data| Ok(toml::from_slice::<RawManifest>(&data)?));
+		let raw = match raw {
+			Ok(raw) => raw,
+			Err(e) => {
+				issues.push(Issue::new(path, None, format!("{e:#}")));
+				return;
+			}
+		};
+		self.files.push(path.to_path_buf());
+		let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+		for (index, asset) in raw.assets.into_iter().enumerate() {
+			match asset.try_into::<AssetEntry>() {
+				Ok(entry) => self.assets.push(ManifestAsset { manifest: path.to_path_buf(), dir: dir.clone(), entry }),
+				Err(e) => issues.push(Issue::new(path, None, format!("asset #{}: {}", index + 1, e.message()))),
+			}
+		}
+		for include in raw.include {
+			self.read_file(&dir.join(include), visited, issues);
+		}
+	}
+}
+
+/// Something wrong with a manifest, found by [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+	/// Manifest file the issue is in
+	pub manifest: PathBuf,
+	/// Name of the asset concerned
+	pub asset: Option<String>,
+	/// What's wrong
+	pub message: String,
+}
+
+impl Issue {
+	fn new(manifest: &Path, asset: Option<&str>, message: String) -> Self {
+		Self { manifest: manifest.to_path_buf(), asset: asset.map(str::to_string), message }
+	}
+}
+
+impl fmt::Display for Issue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.asset {
+			Some(asset) => write!(f, "{}: asset '{asset}': {}", self.manifest.display(), self.message),
+			None => write!(f, "{}: {}", self.manifest.display(), self.message),
+		}
+	}
+}
+
+/// Asset types known to the loaders, along with their options
+#[derive(Clone)]
+pub struct AssetTypes {
+	types: HashMap<String, OptionsCheck>,
+	/// Type of the entries without one, by file extension
+	extensions: HashMap<String, String>,
+}
+
+/// Checks the options of an entry
+type OptionsCheck = Arc<dyn Fn(&AssetEntry) -> anyhow::Result<()> + Send + Sync>;
+
+impl AssetTypes {
+	/// No known type
+	pub fn new() -> Self {
+		Self { types: HashMap::new(), extensions: HashMap::new() }
+	}
+
+	/// The types loaded by the engine: `Image`, `SpriteSheet`, `Font` and `Audio`
+	pub fn builtin() -> Self {
+		Self::new()
+			.with::<ImageOptions>("Image")
+			.with::<SpriteSheetOptions>("SpriteSheet")
+			.with::<FontOptions>("Font")
+			.with::<AudioOptions>("Audio")
+	}
+
+	/// Add a type whose options are read as `T`
+	pub fn with<T: DeserializeOwned>(mut self, class: &str) -> Self {
+		self.types.insert(class.to_string(), Arc::new(check_options::<T> as fn(&AssetEntry) -> anyhow::Result<()>));
+		self
+	}
+
+	/// Add the types of the registered [`AssetLoader`](crate::asset_loader::AssetLoader)s
+	pub fn with_loaders(mut self, loaders: &AssetLoaders) -> Self {
+		for loader in loaders.all() {
+			let class = loader.type_name().to_string();
+			for extension in loader.extensions() {
+				self.extensions.insert(extension.to_string(), class.clone());
+			}
+			self.types.insert(class, Arc::new(move |entry: &AssetEntry| loader.check(entry)));
+		}
+		self
+	}
+
+	/// Is the type known?
+	pub fn contains(&self, class: &str) -> bool {
+		self.types.contains_key(class)
+	}
+
+	/// Type of an entry, the one given by the extension of its file if it has none
+	fn class_of<'a>(&'a self, asset: &'a ManifestAsset) -> Option<&'a str> {
+		if !asset.entry.class.is_empty() {
+			return Some(&asset.entry.class);
+		}
+		let extension = asset.path().extension()?.to_str()?.to_lowercase();
+		self.extensions.get(&extension).map(String::as_str)
+	}
+}
+
+impl Default for AssetTypes {
+	fn default() -> Self {
+		Self::builtin()
+	}
+}
+
+fn check_options<T: DeserializeOwned>(entry: &AssetEntry) -> anyhow::Result<()> {
+	entry.options::<T>().map(drop)
+}
+
+/// Check the manifest of a directory and the manifests it includes, without loading anything
+///
+/// Reports manifests that can't be read, invalid entries, unknown types, invalid options,
+/// missing files and duplicate names.
+pub fn validate<P: AsRef<Path>>(dir: P, types: &AssetTypes) -> Vec<Issue> {
+	let (manifest, mut issues) = Manifest::read_lenient(dir.as_ref());
+	let mut names: HashMap<&str, &Path> = HashMap::new();
+	for asset in &manifest.assets {
+		let entry = &asset.entry;
+		let mut issue = |message: String| issues.push(Issue::new(&asset.manifest, Some(&entry.name), message));
+
+		match types.class_of(asset).and_then(|class| types.types.get(class)) {
+			None if entry.class.is_empty() => issue("no type, and no loader for the extension of the file".to_string()),
+			None => issue(format!("unknown type '{}'", entry.class)),
+			Some(check) => {
+				if let Err(e) = check(entry) {
+					issue(e.root_cause().to_string().trim().replace('\n', " "));
+				}
+			}
+		}
+		if !vfs::exists(asset.path()) {
+			issue(format!("missing file {}", asset.path().display()));
+		}
+		match names.get(entry.name.as_str()) {
+			Some(first) => issue(format!("duplicate name, first declared in {}", first.display())),
+			None => {
+				names.insert(&entry.name, &asset.manifest);
+			}
+		}
+	}
+	issues
+}
+
+/// Texture filtering of an image when it's scaled
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+	/// Smooth, for most images
+	#[default]
+	Linear,
+	/// Blocky, for pixel art
+	Nearest,
+}
+
+/// Options of `Image` assets
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ImageOptions {
+	/// Texture filtering
+	#[serde(default)]
+	pub filter: Filter,
+	/// Premultiply the colors by the alpha when loading, and blend accordingly, which avoids dark
+	/// fringes around filtered transparent edges
+	#[serde(default)]
+	pub premultiplied: bool,
+}
+
+/// Grid layout of a sprite sheet, frames are named by their index (`"0"`, `"1"`, ...) going
+/// row by row from the top-left cell
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+	/// Size of a single cell (w, h) in pixels
+	pub cell_size: (u32, u32),
+	/// Empty space around the whole grid in pixels
+	#[serde(default)]
+	pub margin: u32,
+	/// Empty space between two cells in pixels
+	#[serde(default)]
+	pub spacing: u32,
+}
+
+/// Options of `SpriteSheet` assets, sliced either by a `grid` or by an `atlas`
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteSheetOptions {
+	/// Grid to slice the sheet with
+	#[serde(default)]
+	pub grid: Option<Grid>,
+	/// Path to the Aseprite/TexturePacker JSON atlas, relative to the manifest
+	#[serde(default)]
+	pub atlas: Option<String>,
+	/// Texture filtering
+	#[serde(default)]
+	pub filter: Filter,
+	/// See [`ImageOptions::premultiplied`]
+	#[serde(default)]
+	pub premultiplied: bool,
+}
+
+impl SpriteSheetOptions {
+	/// Options of the sheet texture
+	pub fn image(&self) -> ImageOptions {
+		ImageOptions { filter: self.filter, premultiplied: self.premultiplied }
+	}
+}
+
+/// Font hinting, how glyph outlines are fitted to the pixel grid
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Hinting {
+	#[default]
+	Normal,
+	Light,
+	/// For monochrome rendering, small pixel fonts
+	Mono,
+	None,
+	LightSubpixel,
+}
+
+/// Options of `Font` assets
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FontOptions {
+	/// Size used when text is drawn without one
+	#[serde(default)]
+	pub size: Option<f32>,
+	/// Hinting of the glyphs
+	#[serde(default)]
+	pub hinting: Hinting,
+}
+
+/// Options of `Audio` assets
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct AudioOptions {
+	/// Decode the sound while it plays instead of when it's loaded, for long music
+	#[serde(default)]
+	pub stream: bool,
+}
@@ -1,7 +1,13 @@
 use std::any::Any;
+use crate::info::AssetMetadata;
 
 /// Trait that types representing a resource must implement
-pub trait Resource {
+///
+/// Resources are [`Any`], so that [`crate::manager::ResourceManager::get`] can hand out the
+/// resource type itself from a typed handle. They're also [`Send`] and [`Sync`], as the manager
+/// is shared between threads, see [`crate::manager::SharedResourceManager`]. Data that can't
+/// leave its thread, such as GPU textures, is kept by that thread next to the manager.
+pub trait Resource: Any + Send + Sync {
 	/// Return immutable resource data reference
 	fn data(&self) -> &dyn Any;
 
@@ -10,4 +16,15 @@ pub trait Resource {
 
 	/// Return the resource name which should be unique
 	fn name(&self) -> String;
+
+	/// Approximate memory used by the resource in bytes, for
+	/// [`crate::manager::ResourceManager::memory_report`]
+	fn memory_usage(&self) -> usize {
+		0
+	}
+
+	/// Metadata of the resource for [`crate::info::AssetInfo`], such as the size of an image
+	fn metadata(&self) -> Option<AssetMetadata> {
+		None
+	}
 }
\ No newline at end of file
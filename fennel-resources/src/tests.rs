@@ -1,6 +1,18 @@
 use std::any::Any;
-use crate::manager::ResourceManager;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use serde::Deserialize;
+use crate::asset_loader::{AssetLoader, AssetLoaders};
+use crate::info::{AssetInfo, AssetMetadata, FontMetrics, GlyphAdvances};
+use crate::loader::{LoadProgress, Loader, DEFAULT_GROUP};
+use crate::manager::{LoadState, MemoryUsage, ResourceManager, SharedResourceManager};
+use crate::manifest::{validate, AssetEntry, AssetTypes, Filter, FontOptions, ImageOptions, Manifest, ManifestAsset};
+use crate::vfs::{pack_dir, DirBackend, Vfs, ZipBackend};
 use crate::resource::Resource;
+use crate::ResourceError;
 
 struct MyResource {
 	name: String,
@@ -19,6 +31,10 @@ impl Resource for MyResource {
 	fn name(&self) -> String {
 		self.name.clone()
 	}
+
+	fn memory_usage(&self) -> usize {
+		size_of::<u32>()
+	}
 }
 
 #[test]
@@ -38,7 +54,7 @@ fn resource_ref() {
 	let mut manager = ResourceManager::new();
 	manager.insert(resource);
 	
-	let resource_ref = manager.get("my_resource").unwrap();
+	let resource_ref = manager.get_named("my_resource").unwrap();
 	
 	assert_eq!(resource_ref.data().downcast_ref::<u32>().unwrap(), &42);
 }
@@ -48,7 +64,7 @@ fn resource_ref_mut() {
 	let mut manager = ResourceManager::new();
 	manager.insert(MyResource { name: "my_resource".to_string(), data: 42 });
 
-	let resource_ref = manager.get_mut("my_resource").unwrap();
+	let resource_ref = manager.get_named_mut("my_resource").unwrap();
 	assert_eq!(resource_ref.data().downcast_ref::<u32>().unwrap(), &42);
 
 	*resource_ref.data_mut().downcast_mut::<u32>().unwrap() = 0x42;
@@ -60,8 +76,689 @@ fn multiple_resources() {
 	let mut manager = ResourceManager::new();
 	manager.insert(MyResource { name: "my_resource_1".to_string(), data: 42 });
 	manager.insert(MyResource { name: "my_resource_2".to_string(), data: 0xDEADBEEF });
-	let resource_ref1 = manager.get("my_resource_1").unwrap();
-	let resource_ref2 = manager.get("my_resource_2").unwrap();
+	let resource_ref1 = manager.get_named("my_resource_1").unwrap();
+	let resource_ref2 = manager.get_named("my_resource_2").unwrap();
 	assert_eq!(resource_ref1.data().downcast_ref::<u32>().unwrap(), &42);
 	assert_eq!(resource_ref2.data().downcast_ref::<u32>().unwrap(), &0xDEADBEEF);
-}
\ No newline at end of file
+}
+
+struct OtherResource {
+	name: String,
+}
+
+impl Resource for OtherResource {
+	fn data(&self) -> &dyn Any {
+		&()
+	}
+
+	fn data_mut(&mut self) -> &mut dyn Any {
+		&mut self.name
+	}
+
+	fn name(&self) -> String {
+		self.name.clone()
+	}
+}
+
+#[test]
+fn typed_handles() {
+	let mut manager = ResourceManager::new();
+	let handle = manager.insert(MyResource { name: "my_resource".to_string(), data: 42 });
+	assert_eq!(manager.handle::<MyResource>("my_resource").unwrap(), handle);
+	assert_eq!(manager.get(handle).unwrap().data, 42);
+
+	manager.get_mut(handle).unwrap().data = 7;
+	assert_eq!(manager.get(handle).unwrap().data, 7);
+
+	assert!(matches!(manager.handle::<MyResource>("missing"), Err(ResourceError::ResourceDoesNotExist)));
+	assert!(matches!(manager.handle::<OtherResource>("my_resource"), Err(ResourceError::WrongType { .. })));
+}
+
+#[test]
+fn handles_survive_replacement_by_the_same_type() {
+	let mut manager = ResourceManager::new();
+	let handle = manager.insert(MyResource { name: "my_resource".to_string(), data: 1 });
+	assert_eq!(manager.insert(MyResource { name: "my_resource".to_string(), data: 2 }), handle);
+	assert_eq!(manager.get(handle).unwrap().data, 2);
+	assert_eq!(manager.len(), 1);
+
+	// another type under the same name makes the old handles stale
+	manager.insert(OtherResource { name: "my_resource".to_string() });
+	assert!(matches!(manager.get(handle), Err(ResourceError::StaleHandle)));
+}
+
+#[test]
+fn stale_handles_after_remove() {
+	let mut manager = ResourceManager::new();
+	let first = manager.insert(MyResource { name: "first".to_string(), data: 1 });
+	manager.remove("first").unwrap();
+	assert!(!manager.contains(first));
+
+	// the slot is reused with a new generation
+	let second = manager.insert(MyResource { name: "second".to_string(), data: 2 });
+	assert_eq!(second.index(), first.index());
+	assert_ne!(second, first);
+	assert!(matches!(manager.get(first), Err(ResourceError::StaleHandle)));
+	assert_eq!(manager.get(second).unwrap().data, 2);
+	assert!(!manager.is_cached("first"));
+}
+
+#[test]
+fn reserved_resources_are_not_loaded() {
+	let mut manager = ResourceManager::new();
+	let handle = manager.reserve::<MyResource>("my_resource");
+	assert_eq!(manager.handle::<MyResource>("my_resource").unwrap(), handle);
+	assert_eq!(manager.load_state(handle).unwrap(), LoadState::Loading);
+	assert!(matches!(manager.get(handle), Err(ResourceError::NotLoaded)));
+
+	manager.fail("my_resource", "broken".to_string());
+	assert!(matches!(manager.get(handle), Err(ResourceError::LoadFailed(_))));
+
+	assert_eq!(manager.insert(MyResource { name: "my_resource".to_string(), data: 42 }), handle);
+	assert_eq!(manager.load_state(handle).unwrap(), LoadState::Loaded);
+	assert_eq!(manager.get(handle).unwrap().data, 42);
+}
+
+#[test]
+fn loader_finishes_on_poll() {
+	let mut manager = ResourceManager::new();
+	let mut loader = Loader::<u32>::new(2);
+	let ok = loader.request(&mut manager, "ok", "level", || Ok(40), |data, offset: &mut u32| {
+		Ok(MyResource { name: "ok".to_string(), data: data + *offset })
+	});
+	let broken = loader.request(&mut manager, "broken", "level", || -> anyhow::Result<u32> { anyhow::bail!("no such file") }, |data, _: &mut u32| {
+		Ok(MyResource { name: "broken".to_string(), data })
+	});
+	assert_eq!(manager.load_state(ok).unwrap(), LoadState::Loading);
+	assert_eq!(loader.progress("level"), LoadProgress { total: 2, loaded: 0, failed: 0 });
+
+	let failures = loader.wait(&mut manager, &mut 2);
+	assert_eq!(failures.len(), 1);
+	assert_eq!(failures[0].name, "broken");
+	assert_eq!(manager.get(ok).unwrap().data, 42);
+	assert!(matches!(manager.load_state(broken).unwrap(), LoadState::Failed(_)));
+
+	let progress = loader.progress("level");
+	assert_eq!(progress, LoadProgress { total: 2, loaded: 1, failed: 1 });
+	assert!(progress.is_done());
+	assert_eq!(progress.fraction(), 1.0);
+	assert!(!loader.is_loading());
+	assert!(loader.poll(&mut manager, &mut 2, Duration::from_millis(1)).is_empty());
+}
+
+#[test]
+fn misnamed_resources_fail_to_load() {
+	let mut manager = ResourceManager::new();
+	let mut loader = Loader::<()>::new(1);
+	let handle = loader.request(&mut manager, "expected", DEFAULT_GROUP, || Ok(1), |data, _| Ok(MyResource { name: "other".to_string(), data }));
+
+	let failures = loader.wait(&mut manager, &mut ());
+	assert_eq!(failures.len(), 1);
+	assert!(matches!(manager.load_state(handle).unwrap(), LoadState::Failed(_)));
+	assert!(!manager.is_cached("other"));
+
+	// custom loaders are held to the name of the entry too
+	let asset = ManifestAsset {
+		manifest: PathBuf::from("manifest.toml"),
+		dir: PathBuf::new(),
+		entry: AssetEntry { name: "intro".to_string(), path: "intro.txt".to_string(), class: "Dialogue".to_string(), group: None, options: toml::Table::new() },
+	};
+	loader.request_asset(&mut manager, &asset, DEFAULT_GROUP, Arc::new(MisnamedLoader));
+	let failures = loader.wait(&mut manager, &mut ());
+	assert!(failures[0].error.contains("instead of 'intro'"), "{failures:?}");
+	assert!(matches!(manager.load_state(manager.handle::<Dialogue>("intro").unwrap()).unwrap(), LoadState::Failed(_)));
+}
+
+#[test]
+fn forgotten_groups_are_not_finished() {
+	let mut manager = ResourceManager::new();
+	let mut loader = Loader::<()>::new(1);
+	loader.request(&mut manager, "kept", DEFAULT_GROUP, || Ok(1), |data, _| Ok(MyResource { name: "kept".to_string(), data }));
+	let dropped = loader.request(&mut manager, "dropped", "level", || Ok(2), |data, _| Ok(MyResource { name: "dropped".to_string(), data }));
+	loader.forget_group("level");
+	manager.remove("dropped").ok();
+
+	loader.wait(&mut manager, &mut ());
+	assert!(!manager.contains(dropped));
+	assert!(!manager.is_cached("dropped"));
+	assert_eq!(loader.total_progress(), LoadProgress { total: 1, loaded: 1, failed: 0 });
+}
+
+#[test]
+fn removed_requests_are_not_counted() {
+	let mut manager = ResourceManager::new();
+	let mut loader = Loader::<()>::new(1);
+	loader.request(&mut manager, "kept", DEFAULT_GROUP, || Ok(1), |data, _| Ok(MyResource { name: "kept".to_string(), data }));
+	loader.request(&mut manager, "removed", DEFAULT_GROUP, || Ok(2), |data, _| Ok(MyResource { name: "removed".to_string(), data }));
+	loader.request(&mut manager, "broken", DEFAULT_GROUP, || -> anyhow::Result<u32> { anyhow::bail!("no such file") }, |data, _| {
+		Ok(MyResource { name: "broken".to_string(), data })
+	});
+	let asset = ManifestAsset {
+		manifest: PathBuf::from("manifest.toml"),
+		dir: PathBuf::new(),
+		entry: AssetEntry { name: "intro".to_string(), path: "intro.txt".to_string(), class: "Dialogue".to_string(), group: None, options: toml::Table::new() },
+	};
+	loader.request_asset(&mut manager, &asset, DEFAULT_GROUP, Arc::new(DialogueLoader));
+	manager.remove("removed").ok();
+	manager.remove("broken").ok();
+	manager.remove("intro").ok();
+
+	assert!(loader.wait(&mut manager, &mut ()).is_empty());
+	assert!(manager.is_cached("kept"));
+	assert!(!manager.is_cached("removed"));
+	assert!(!manager.is_cached("intro"));
+	assert_eq!(loader.progress(DEFAULT_GROUP), LoadProgress { total: 1, loaded: 1, failed: 0 });
+}
+
+/// Fresh directory in the system temporary directory
+fn temp_dir(name: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("fennel-vfs-{}-{name}", std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+fn write_files(dir: &Path, files: &[(&str, &str)]) {
+	for (path, content) in files {
+		let path = dir.join(path);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, content).unwrap();
+	}
+}
+
+#[test]
+fn vfs_overlays_mounts() {
+	let base = temp_dir("base");
+	let mod_dir = temp_dir("mod");
+	write_files(&base, &[("manifest.toml", "base"), ("sprites/player.png", "base player"), ("sprites/enemy.png", "base enemy")]);
+	write_files(&mod_dir, &[("sprites/player.png", "mod player")]);
+
+	let mut vfs = Vfs::new();
+	vfs.mount("assets", DirBackend::new(&base));
+	vfs.mount("assets", DirBackend::new(&mod_dir));
+
+	assert_eq!(vfs.read_to_string("assets://sprites/player.png").unwrap(), "mod player");
+	assert_eq!(vfs.read_to_string("assets://sprites/enemy.png").unwrap(), "base enemy");
+	assert_eq!(vfs.read_to_string("assets://sprites/../manifest.toml").unwrap(), "base");
+	let mut opened = String::new();
+	vfs.open("assets://sprites/player.png").unwrap().read_to_string(&mut opened).unwrap();
+	assert_eq!(opened, "mod player");
+	assert!(vfs.open("assets://missing.png").is_err());
+	assert!(vfs.read("assets://../outside").is_err());
+	assert!(vfs.read("assets://missing.png").is_err());
+	assert!(vfs.read("user://save.ron").is_err());
+
+	// `Path::parent` collapses the double slash
+	let level = PathBuf::from("assets://manifest.toml");
+	assert_eq!(vfs.read_to_string(level.parent().unwrap().join("sprites/enemy.png")).unwrap(), "base enemy");
+
+	let listed = vfs.list("assets://sprites").unwrap();
+	assert_eq!(listed, vec![PathBuf::from("assets://sprites/enemy.png"), PathBuf::from("assets://sprites/player.png")]);
+
+	// plain paths bypass the mounts
+	assert_eq!(vfs.read_to_string(base.join("manifest.toml")).unwrap(), "base");
+	fs::remove_dir_all(&base).unwrap();
+	fs::remove_dir_all(&mod_dir).unwrap();
+}
+
+#[test]
+fn vfs_writes_to_writable_mounts() {
+	let user = temp_dir("user");
+	let read_only = temp_dir("read-only");
+	let mut vfs = Vfs::new();
+	vfs.mount("user", DirBackend::writable(&user));
+	vfs.mount("user", DirBackend::new(&read_only));
+
+	vfs.write("user://saves/slot1.ron", b"(level: 2)").unwrap();
+	assert_eq!(fs::read_to_string(user.join("saves/slot1.ron")).unwrap(), "(level: 2)");
+	assert!(vfs.exists("user://saves/slot1.ron"));
+	assert!(vfs.modified("user://saves/slot1.ron").is_some());
+	fs::remove_dir_all(&user).unwrap();
+	fs::remove_dir_all(&read_only).unwrap();
+}
+
+#[test]
+fn vfs_reads_packs() {
+	let assets = temp_dir("pack-source");
+	write_files(&assets, &[("manifest.toml", "packed"), ("scenes/main.scn.ron", "main"), ("scenes/test.scn.ron", "test")]);
+	let pack = assets.with_extension("pak");
+	pack_dir(&assets, &pack).unwrap();
+
+	let mut vfs = Vfs::new();
+	vfs.mount("assets", ZipBackend::open(&pack).unwrap());
+	assert_eq!(vfs.read_to_string("assets://manifest.toml").unwrap(), "packed");
+	assert_eq!(vfs.list("assets://scenes").unwrap().len(), 2);
+	assert!(vfs.write("assets://manifest.toml", b"").is_err());
+
+	// the pack next to the directory is mounted in its place
+	let mut vfs = Vfs::new();
+	fs::write(assets.join("manifest.toml"), "loose").unwrap();
+	vfs.mount_dir_or_pack("assets", &assets).unwrap();
+	assert_eq!(vfs.read_to_string("assets://manifest.toml").unwrap(), "packed");
+	fs::remove_file(&pack).unwrap();
+	fs::remove_dir_all(&assets).unwrap();
+}
+
+#[test]
+fn changed_sources_are_reported_once() {
+	let mut manager = ResourceManager::new();
+	manager.insert(MyResource { name: "player".to_string(), data: 1 });
+	manager.insert(MyResource { name: "untracked".to_string(), data: 2 });
+	let loaded = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+	manager.set_source("player", "assets://player.png", Some(loaded));
+	assert_eq!(manager.source("player").unwrap().modified, Some(loaded));
+	assert!(manager.source("untracked").is_none());
+
+	assert!(manager.changed_sources(|_| Some(loaded)).is_empty());
+	let edited = loaded + Duration::from_secs(5);
+	assert_eq!(manager.changed_sources(|_| Some(edited)), vec![("player".to_string(), PathBuf::from("assets://player.png"))]);
+	assert!(manager.changed_sources(|_| Some(edited)).is_empty());
+	// a file that can't be checked right now, e.g. while being saved, isn't a change
+	assert!(manager.changed_sources(|_| None).is_empty());
+
+	// reloading keeps the source, removing forgets it
+	manager.insert(MyResource { name: "player".to_string(), data: 3 });
+	assert!(manager.source("player").is_some());
+	manager.remove("player").unwrap();
+	assert!(manager.source("player").is_none());
+}
+
+#[test]
+fn released_resources_are_collected_when_unreferenced() {
+	let mut manager = ResourceManager::new();
+	let kept = manager.insert(MyResource { name: "kept".to_string(), data: 1 });
+	let level = manager.insert(MyResource { name: "level".to_string(), data: 2 });
+	let strong = manager.strong(level).unwrap();
+	let copy = strong.clone();
+	manager.release("level").unwrap();
+	assert_eq!(manager.ref_count("level"), 2);
+
+	// resources that aren't released are kept without references
+	assert!(manager.collect_unused().is_empty());
+	drop(strong);
+	assert!(manager.collect_unused().is_empty());
+	assert_eq!(manager.get(copy.handle()).unwrap().data, 2);
+
+	drop(copy);
+	assert_eq!(manager.collect_unused(), vec!["level".to_string()]);
+	assert!(!manager.contains(level));
+	assert!(manager.contains(kept));
+
+	// strong handles to a removed resource don't keep the next one in its slot alive
+	let old = manager.strong(kept).unwrap();
+	manager.remove("kept").unwrap();
+	manager.insert(MyResource { name: "next".to_string(), data: 3 });
+	manager.release("next").unwrap();
+	assert_eq!(manager.collect_unused(), vec!["next".to_string()]);
+	drop(old);
+}
+
+#[test]
+fn dependencies_are_collected_after_their_dependents() {
+	let mut manager = ResourceManager::new();
+	manager.insert(MyResource { name: "sheet".to_string(), data: 1 });
+	manager.insert(MyResource { name: "texture".to_string(), data: 2 });
+	manager.add_dependency("sheet", "texture").unwrap();
+	manager.release("sheet").unwrap();
+	manager.release("texture").unwrap();
+
+	let keep_alive = manager.keep_alive("sheet").unwrap();
+	assert!(manager.collect_unused().is_empty());
+	drop(keep_alive);
+	assert_eq!(manager.collect_unused(), vec!["sheet".to_string(), "texture".to_string()]);
+	assert!(manager.is_empty());
+}
+
+#[test]
+fn shared_manager_survives_a_panicking_writer() {
+	let shared = SharedResourceManager::default();
+	let writer = shared.clone();
+	std::thread::spawn(move || {
+		writer.write().insert(MyResource { name: "a".to_string(), data: 1 });
+		let _guard = writer.write();
+		panic!("the lock is poisoned");
+	})
+	.join()
+	.unwrap_err();
+
+	let handle = shared.read().handle::<MyResource>("a").unwrap();
+	assert_eq!(shared.read().get(handle).unwrap().data, 1);
+	shared.write().remove("a").unwrap();
+	assert!(shared.read().is_empty());
+}
+
+#[test]
+fn asset_info_follows_the_managers() {
+	let mut images = ResourceManager::new();
+	images.insert(Picture { name: "player".to_string(), width: 16, height: 32 });
+	images.insert(OtherResource { name: "no_metadata".to_string() });
+	let mut fonts = ResourceManager::new();
+	let metrics = FontMetrics { size: 10.0, height: 12.0, ascent: 9.0, descent: -3.0, line_spacing: 13.0 };
+	let advances = GlyphAdvances { advances: HashMap::from([('i', 3.0), ('m', 9.0)]), fallback: 6.0 };
+	fonts.insert(Font { name: "mono".to_string(), metrics, advances });
+
+	let mut info = AssetInfo::default();
+	assert!(info.sync("images", &images));
+	assert!(info.sync("fonts", &fonts));
+	assert!(!info.sync("images", &images));
+	assert_eq!(info.len(), 2);
+	assert_eq!(info.image_size("player"), Some((16, 32)));
+	assert_eq!(info.sprite_size("player"), Some((16.0, 32.0)));
+	assert_eq!(info.sprite_size("player:0"), None);
+	assert_eq!(info.line_height("mono", 0.0), Some(13.0));
+	assert_eq!(info.line_height("mono", 20.0), Some(26.0));
+	assert_eq!(info.line_height("player", 20.0), None);
+	assert_eq!(info.text_width("mono", 0.0, "mix"), Some(18.0));
+	assert_eq!(info.text_width("mono", 20.0, "mix"), Some(36.0));
+
+	images.remove("player").unwrap();
+	assert!(info.sync("images", &images));
+	assert_eq!(info.image_size("player"), None);
+	assert_eq!(info.line_height("mono", 10.0), Some(13.0));
+
+	// only the inserted and removed names are read again
+	images.insert(Picture { name: "enemy".to_string(), width: 8, height: 8 });
+	images.insert(Picture { name: "enemy".to_string(), width: 24, height: 8 });
+	images.insert(Picture { name: "tree".to_string(), width: 4, height: 4 });
+	images.remove("tree").unwrap();
+	assert!(info.sync("images", &images));
+	assert_eq!(info.image_size("enemy"), Some((24, 8)));
+	assert_eq!(info.image_size("tree"), None);
+	assert_eq!(info.len(), 2);
+}
+
+#[test]
+fn manager_remembers_the_latest_changes() {
+	let mut manager = ResourceManager::new();
+	assert_eq!(manager.changes_since(0).map(Iterator::count), Some(0));
+	manager.insert(Picture { name: "a".to_string(), width: 1, height: 1 });
+	manager.insert(Picture { name: "b".to_string(), width: 1, height: 1 });
+	manager.remove("a").unwrap();
+	let revision = manager.revision();
+	assert_eq!(manager.changes_since(0).unwrap().collect::<Vec<_>>(), ["a", "b", "a"]);
+	assert_eq!(manager.changes_since(1).unwrap().collect::<Vec<_>>(), ["b", "a"]);
+	assert_eq!(manager.changes_since(revision).map(Iterator::count), Some(0));
+	assert!(manager.changes_since(revision + 1).is_none());
+
+	// reserving or failing isn't a change
+	manager.reserve::<Picture>("c");
+	manager.fail("c", "missing".to_string());
+	assert_eq!(manager.revision(), revision);
+
+	for _ in 0..5000 {
+		manager.insert(Picture { name: "b".to_string(), width: 1, height: 1 });
+	}
+	assert!(manager.changes_since(0).is_none());
+	assert_eq!(manager.changes_since(manager.revision() - 2).unwrap().collect::<Vec<_>>(), ["b", "b"]);
+}
+
+struct Picture {
+	name: String,
+	width: u32,
+	height: u32,
+}
+
+impl Resource for Picture {
+	fn data(&self) -> &dyn Any {
+		&self.width
+	}
+
+	fn data_mut(&mut self) -> &mut dyn Any {
+		&mut self.width
+	}
+
+	fn name(&self) -> String {
+		self.name.clone()
+	}
+
+	fn metadata(&self) -> Option<AssetMetadata> {
+		Some(AssetMetadata::Image { width: self.width, height: self.height })
+	}
+}
+
+struct Font {
+	name: String,
+	metrics: FontMetrics,
+	advances: GlyphAdvances,
+}
+
+impl Resource for Font {
+	fn data(&self) -> &dyn Any {
+		&self.metrics
+	}
+
+	fn data_mut(&mut self) -> &mut dyn Any {
+		&mut self.metrics
+	}
+
+	fn name(&self) -> String {
+		self.name.clone()
+	}
+
+	fn metadata(&self) -> Option<AssetMetadata> {
+		Some(AssetMetadata::Font { metrics: self.metrics, advances: self.advances.clone() })
+	}
+}
+
+#[test]
+fn memory_report_by_type() {
+	let mut manager = ResourceManager::new();
+	manager.insert(MyResource { name: "a".to_string(), data: 1 });
+	manager.insert(MyResource { name: "b".to_string(), data: 2 });
+	manager.insert(OtherResource { name: "c".to_string() });
+	manager.reserve::<MyResource>("loading");
+	assert_eq!(manager.memory_report(), vec![
+		MemoryUsage { type_name: std::any::type_name::<MyResource>(), count: 2, bytes: 8 },
+		MemoryUsage { type_name: std::any::type_name::<OtherResource>(), count: 1, bytes: 0 },
+	]);
+}
+
+#[test]
+fn manifests_include_other_manifests() {
+	let dir = temp_dir("manifest");
+	write_files(&dir, &[
+		("manifest.toml", r#"
+			include = ["ui/menu.toml"]
+
+			[[assets]]
+			name = "player"
+			path = "player.png"
+			type = "Image"
+			filter = "nearest"
+		"#),
+		("ui/menu.toml", r#"
+			[[assets]]
+			name = "title"
+			path = "title.ttf"
+			type = "Font"
+			size = 24
+			group = "menu"
+		"#),
+	]);
+	let manifest = Manifest::read(&dir).unwrap();
+	assert_eq!(manifest.files, [dir.join("manifest.toml"), dir.join("ui/menu.toml")]);
+
+	let player = &manifest.assets[0];
+	assert_eq!(player.path(), dir.join("player.png"));
+	assert_eq!(player.entry.options::<ImageOptions>().unwrap(), ImageOptions { filter: Filter::Nearest, premultiplied: false });
+
+	// included assets are relative to their own manifest
+	let title = &manifest.assets[1];
+	assert_eq!(title.path(), dir.join("ui/title.ttf"));
+	assert_eq!(title.entry.group.as_deref(), Some("menu"));
+	assert_eq!(title.entry.options::<FontOptions>().unwrap().size, Some(24.0));
+	assert_eq!(title.manifest, dir.join("ui/menu.toml"));
+
+	// issues are located in the manifest declaring the asset
+	let issues = validate(&dir, &AssetTypes::builtin());
+	let missing = issues.iter().find(|issue| issue.asset.as_deref() == Some("title")).unwrap();
+	assert_eq!(missing.manifest, dir.join("ui/menu.toml"));
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn validation_reports_every_issue() {
+	let dir = temp_dir("validate");
+	write_files(&dir, &[
+		("manifest.toml", r#"
+			include = ["missing/manifest.toml"]
+
+			[[assets]]
+			name = "player"
+			path = "player.png"
+			type = "Image"
+
+			[[assets]]
+			name = "player"
+			path = "player.png"
+			type = "Image"
+			filter = "blurry"
+
+			[[assets]]
+			name = "level"
+			path = "level.tmj"
+			type = "Tilemap"
+
+			[[assets]]
+			name = "nameless"
+		"#),
+		("player.png", ""),
+	]);
+	let issues: Vec<String> = validate(&dir, &AssetTypes::builtin())
+		.into_iter()
+		.map(|issue| issue.to_string())
+		.collect();
+	assert_eq!(issues.len(), 6, "{issues:#?}");
+	assert!(issues[0].contains("asset #4: missing field `path`"));
+	assert!(issues[1].contains("missing/manifest.toml"));
+	assert!(issues[2].contains("'player'") && issues[2].contains("blurry"));
+	assert!(issues[3].contains("'player': duplicate name"));
+	assert!(issues[4].contains("'level': unknown type 'Tilemap'"));
+	assert!(issues[5].contains("'level': missing file"));
+
+	// manifests with issues can't be read
+	assert!(Manifest::read(&dir).is_err());
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Lines of a dialogue, one per line of its file
+struct Dialogue {
+	name: String,
+	lines: Vec<String>,
+}
+
+impl Resource for Dialogue {
+	fn data(&self) -> &dyn Any {
+		&self.lines
+	}
+
+	fn data_mut(&mut self) -> &mut dyn Any {
+		&mut self.lines
+	}
+
+	fn name(&self) -> String {
+		self.name.clone()
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialogueOptions {
+	#[serde(default)]
+	speaker: Option<String>,
+}
+
+struct DialogueLoader;
+
+impl AssetLoader for DialogueLoader {
+	type Asset = Dialogue;
+
+	fn type_name(&self) -> &str {
+		"Dialogue"
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["dlg"]
+	}
+
+	fn check(&self, entry: &AssetEntry) -> anyhow::Result<()> {
+		entry.options::<DialogueOptions>().map(drop)
+	}
+
+	fn load(&self, asset: &ManifestAsset) -> anyhow::Result<Dialogue> {
+		let speaker = asset.entry.options::<DialogueOptions>()?.speaker.unwrap_or_default();
+		let text = String::from_utf8(crate::vfs::read(asset.path())?)?;
+		let lines = text.lines().map(|line| format!("{speaker}: {line}")).collect();
+		Ok(Dialogue { name: asset.entry.name.clone(), lines })
+	}
+}
+
+/// Loader naming its dialogues after their file rather than their entry
+struct MisnamedLoader;
+
+impl AssetLoader for MisnamedLoader {
+	type Asset = Dialogue;
+
+	fn type_name(&self) -> &str {
+		"Dialogue"
+	}
+
+	fn load(&self, asset: &ManifestAsset) -> anyhow::Result<Dialogue> {
+		Ok(Dialogue { name: asset.entry.path.clone(), lines: Vec::new() })
+	}
+}
+
+#[test]
+fn custom_asset_types_are_loaded_by_their_loader() {
+	let dir = temp_dir("asset_loader");
+	write_files(&dir, &[
+		("manifest.toml", r#"
+			[[assets]]
+			name = "intro"
+			path = "intro.txt"
+			type = "Dialogue"
+			speaker = "Fox"
+
+			[[assets]]
+			name = "outro"
+			path = "outro.dlg"
+
+			[[assets]]
+			name = "credits"
+			path = "credits.txt"
+
+			[[assets]]
+			name = "broken"
+			path = "intro.txt"
+			type = "Dialogue"
+			mood = "grumpy"
+		"#),
+		("intro.txt", "Hello\nBye"),
+		("outro.dlg", "The end"),
+		("credits.txt", ""),
+	]);
+	let loaders = AssetLoaders::default();
+	loaders.clone().register(DialogueLoader);
+	let manifest = Manifest::read(&dir).unwrap();
+	assert!(loaders.for_asset(&manifest.assets[1]).is_some());
+	assert!(loaders.for_asset(&manifest.assets[2]).is_none());
+
+	let mut manager = ResourceManager::new();
+	let mut loader = Loader::<()>::new(1);
+	for asset in &manifest.assets[..2] {
+		loader.request_asset(&mut manager, asset, DEFAULT_GROUP, loaders.for_asset(asset).unwrap());
+	}
+	assert!(loader.wait(&mut manager, &mut ()).is_empty());
+	let intro = manager.get(manager.handle::<Dialogue>("intro").unwrap()).unwrap();
+	assert_eq!(intro.lines, vec!["Fox: Hello".to_string(), "Fox: Bye".to_string()]);
+	assert_eq!(manager.get(manager.handle::<Dialogue>("outro").unwrap()).unwrap().lines, vec![": The end".to_string()]);
+
+	let issues: Vec<String> = validate(&dir, &AssetTypes::builtin().with_loaders(&loaders))
+		.into_iter()
+		.map(|issue| issue.to_string())
+		.collect();
+	assert_eq!(issues.len(), 2, "{issues:#?}");
+	assert!(issues[0].contains("'credits': no type"));
+	assert!(issues[1].contains("'broken'") && issues[1].contains("mood"));
+	fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,478 @@
+//! Virtual filesystem
+//!
+//! Paths of the form `scheme://path/to/file` are looked up in the backends mounted on `scheme`,
+//! the last mounted backend first, so a mod mounted over the base assets overrides the files
+//! it contains and falls back to the base assets for the others. Paths without a scheme are
+//! plain filesystem paths.
+//!
+//! ```ignore
+//! use fennel_resources::vfs::{self, DirBackend, ZipBackend};
+//! vfs::mount("assets", ZipBackend::open("assets.pak")?);
+//! vfs::mount("assets", DirBackend::new("mods/big_heads"));
+//! let manifest = vfs::read("assets://manifest.toml")?;
+//! ```
+//!
+//! Release builds can ship a single archive made by [`pack_dir`], mounted by
+//! [`Vfs::mount_dir_or_pack`] in place of the directory it was made from.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError, RwLock};
+use std::time::SystemTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Extension of the archives made by [`pack_dir`]
+pub const PACK_EXTENSION: &str = "pak";
+
+/// Storage a scheme can be mounted on
+///
+/// Paths given to a backend are relative, `/`-separated and normalized: no `.`, `..` or empty
+/// components.
+pub trait VfsBackend: Send + Sync {
+	/// Read a whole file
+	fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+
+	/// Open a file to read it progressively, by default it's read whole into memory
+	fn open(&self, path: &str) -> io::Result<VfsReader> {
+		Ok(Box::new(Cursor::new(self.read(path)?)))
+	}
+
+	/// Is there a file at `path`?
+	fn exists(&self, path: &str) -> bool;
+
+	/// Names of the files directly inside of the directory `path`, `""` being the root
+	fn list(&self, path: &str) -> io::Result<Vec<String>>;
+
+	/// Create or replace a file, read-only backends fail with [`io::ErrorKind::Unsupported`]
+	fn write(&self, _path: &str, _data: &[u8]) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "backend is read-only"))
+	}
+
+	/// Last modification time of a file, if the backend knows it
+	fn modified(&self, _path: &str) -> Option<SystemTime> {
+		None
+	}
+}
+
+/// Backend reading from a directory
+pub struct DirBackend {
+	root: PathBuf,
+	writable: bool,
+}
+
+impl DirBackend {
+	/// Read-only view of a directory
+	pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+		Self { root: root.into(), writable: false }
+	}
+
+	/// Directory that can also be written to, e.g. for save games and settings
+	pub fn writable<P: Into<PathBuf>>(root: P) -> Self {
+		Self { root: root.into(), writable: true }
+	}
+}
+
+impl VfsBackend for DirBackend {
+	fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+		fs::read(self.root.join(path))
+	}
+
+	fn open(&self, path: &str) -> io::Result<VfsReader> {
+		Ok(Box::new(File::open(self.root.join(path))?))
+	}
+
+	fn exists(&self, path: &str) -> bool {
+		self.root.join(path).is_file()
+	}
+
+	fn list(&self, path: &str) -> io::Result<Vec<String>> {
+		let mut names = Vec::new();
+		for entry in fs::read_dir(self.root.join(path))? {
+			let entry = entry?;
+			if entry.file_type()?.is_file() {
+				names.push(entry.file_name().to_string_lossy().into_owned());
+			}
+		}
+		Ok(names)
+	}
+
+	fn write(&self, path: &str, data: &[u8]) -> io::Result<()> {
+		if !self.writable {
+			return Err(io::Error::new(io::ErrorKind::Unsupported, "directory is mounted read-only"));
+		}
+		let path = self.root.join(path);
+		if let Some(directory) = path.parent() {
+			fs::create_dir_all(directory)?;
+		}
+		fs::write(path, data)
+	}
+
+	fn modified(&self, path: &str) -> Option<SystemTime> {
+		fs::metadata(self.root.join(path)).and_then(|metadata| metadata.modified()).ok()
+	}
+}
+
+/// A file opened with [`Vfs::open`]
+pub trait ReadSeek: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> ReadSeek for T {}
+
+/// Reader of a file opened with [`Vfs::open`]
+pub type VfsReader = Box<dyn ReadSeek>;
+
+/// Read-only backend reading from a zip archive, such as the packs made by [`pack_dir`]
+pub struct ZipBackend {
+	archive: Mutex<ZipArchive<Box<dyn ReadSeek>>>,
+	/// Paths of the files in the archive
+	files: HashSet<String>,
+}
+
+impl ZipBackend {
+	/// Open an archive file, its entries are read when needed
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		Self::new(Box::new(File::open(path)?))
+	}
+
+	/// Read an archive from memory, e.g. one embedded with `include_bytes!`
+	pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+		Self::new(Box::new(Cursor::new(bytes)))
+	}
+
+	fn new(reader: Box<dyn ReadSeek>) -> io::Result<Self> {
+		let archive = ZipArchive::new(reader)?;
+		let files = archive
+			.file_names()
+			.filter(|name| !name.ends_with('/'))
+			.map(str::to_string)
+			.collect();
+		Ok(Self { archive: Mutex::new(archive), files })
+	}
+}
+
+impl VfsBackend for ZipBackend {
+	fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+		let mut archive = self.archive.lock().unwrap_or_else(PoisonError::into_inner);
+		let mut file = archive.by_name(path)?;
+		let mut data = Vec::with_capacity(file.size() as usize);
+		file.read_to_end(&mut data)?;
+		Ok(data)
+	}
+
+	fn exists(&self, path: &str) -> bool {
+		self.files.contains(path)
+	}
+
+	fn list(&self, path: &str) -> io::Result<Vec<String>> {
+		let prefix = if path.is_empty() { String::new() } else { format!("{path}/") };
+		Ok(self.files
+			.iter()
+			.filter_map(|file| file.strip_prefix(&prefix))
+			.filter(|name| !name.contains('/'))
+			.map(str::to_string)
+			.collect())
+	}
+}
+
+struct Mount {
+	scheme: String,
+	backend: Box<dyn VfsBackend>,
+}
+
+/// Mount points and their backends, see the [module documentation](self)
+#[derive(Default)]
+pub struct Vfs {
+	/// Mounts in mounting order, searched from the last one
+	mounts: Vec<Mount>,
+}
+
+impl Vfs {
+	/// Create a virtual filesystem with nothing mounted
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Mount a backend on `scheme`, over the backends already mounted there
+	pub fn mount<B: VfsBackend + 'static>(&mut self, scheme: &str, backend: B) {
+		self.mount_boxed(scheme, Box::new(backend));
+	}
+
+	/// Mount an already boxed backend, see [`Vfs::mount`]
+	pub fn mount_boxed(&mut self, scheme: &str, backend: Box<dyn VfsBackend>) {
+		self.mounts.push(Mount { scheme: scheme.to_string(), backend });
+	}
+
+	/// Mount `<dir>.pak` on `scheme` if it exists, the directory itself otherwise
+	pub fn mount_dir_or_pack<P: AsRef<Path>>(&mut self, scheme: &str, dir: P) -> io::Result<()> {
+		let dir = dir.as_ref();
+		let pack = dir.with_extension(PACK_EXTENSION);
+		if pack.is_file() {
+			self.mount(scheme, ZipBackend::open(pack)?);
+		} else {
+			self.mount(scheme, DirBackend::new(dir));
+		}
+		Ok(())
+	}
+
+	/// Remove every backend mounted on `scheme`
+	pub fn unmount(&mut self, scheme: &str) {
+		self.mounts.retain(|mount| mount.scheme != scheme);
+	}
+
+	/// Is anything mounted on `scheme`?
+	pub fn is_mounted(&self, scheme: &str) -> bool {
+		self.mounts.iter().any(|mount| mount.scheme == scheme)
+	}
+
+	/// Read a whole file from the topmost backend having it
+	pub fn read<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<u8>> {
+		let path = path.as_ref();
+		let Some((scheme, relative)) = split(path)? else {
+			return fs::read(path);
+		};
+		self.backends(scheme)
+			.find(|backend| backend.exists(&relative))
+			.ok_or_else(|| not_found(path))?
+			.read(&relative)
+	}
+
+	/// Open a file of the topmost backend having it, to read it progressively, for example to
+	/// stream music. Files of archives are read whole
+	pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<VfsReader> {
+		let path = path.as_ref();
+		let Some((scheme, relative)) = split(path)? else {
+			return Ok(Box::new(File::open(path)?));
+		};
+		self.backends(scheme)
+			.find(|backend| backend.exists(&relative))
+			.ok_or_else(|| not_found(path))?
+			.open(&relative)
+	}
+
+	/// Read a whole UTF-8 file
+	pub fn read_to_string<P: AsRef<Path>>(&self, path: P) -> io::Result<String> {
+		String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	/// Is there a file at `path`?
+	pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+		let path = path.as_ref();
+		match split(path) {
+			Ok(Some((scheme, relative))) => self.backends(scheme).any(|backend| backend.exists(&relative)),
+			Ok(None) => path.is_file(),
+			Err(_) => false,
+		}
+	}
+
+	/// Paths of the files directly inside of a directory, merged over all the backends of its
+	/// scheme and sorted
+	pub fn list<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<PathBuf>> {
+		let path = path.as_ref();
+		let Some((scheme, relative)) = split(path)? else {
+			let mut files = Vec::new();
+			for entry in fs::read_dir(path)? {
+				let entry = entry?;
+				if entry.file_type()?.is_file() {
+					files.push(entry.path());
+				}
+			}
+			files.sort();
+			return Ok(files);
+		};
+
+		let mut names = Vec::new();
+		let mut found = false;
+		for backend in self.backends(scheme) {
+			if let Ok(listed) = backend.list(&relative) {
+				found = true;
+				names.extend(listed);
+			}
+		}
+		if !found {
+			return Err(not_found(path));
+		}
+		names.sort();
+		names.dedup();
+		Ok(names
+			.into_iter()
+			.map(|name| join(scheme, &relative, &name))
+			.collect())
+	}
+
+	/// Create or replace a file in the topmost writable backend of its scheme
+	pub fn write<P: AsRef<Path>>(&self, path: P, data: &[u8]) -> io::Result<()> {
+		let path = path.as_ref();
+		let Some((scheme, relative)) = split(path)? else {
+			return fs::write(path, data);
+		};
+		let mut result = Err(not_found(path));
+		for backend in self.backends(scheme) {
+			result = backend.write(&relative, data);
+			if !matches!(&result, Err(e) if e.kind() == io::ErrorKind::Unsupported) {
+				break;
+			}
+		}
+		result
+	}
+
+	/// Last modification time of a file, if its backend knows it
+	pub fn modified<P: AsRef<Path>>(&self, path: P) -> Option<SystemTime> {
+		let path = path.as_ref();
+		match split(path).ok()? {
+			Some((scheme, relative)) => self.backends(scheme)
+				.find(|backend| backend.exists(&relative))?
+				.modified(&relative),
+			None => fs::metadata(path).and_then(|metadata| metadata.modified()).ok(),
+		}
+	}
+
+	/// Backends mounted on `scheme`, the last mounted first
+	fn backends<'a>(&'a self, scheme: &'a str) -> impl Iterator<Item = &'a dyn VfsBackend> {
+		self.mounts
+			.iter()
+			.rev()
+			.filter(move |mount| mount.scheme == scheme)
+			.map(|mount| mount.backend.as_ref())
+	}
+}
+
+/// Split `scheme://path` into the scheme and the normalized relative path, `None` for a plain
+/// filesystem path
+///
+/// `scheme:path` and `scheme:` are accepted too, as `Path::parent` and `Path::join` collapse the
+/// double slash. Single letter schemes are left alone for Windows drive letters.
+fn split(path: &Path) -> io::Result<Option<(&str, String)>> {
+	let Some((scheme, rest)) = path.to_str().and_then(|path| path.split_once(':')) else {
+		return Ok(None);
+	};
+	let is_scheme = scheme.len() > 1
+		&& scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+	if !is_scheme {
+		return Ok(None);
+	}
+	let mut components: Vec<&str> = Vec::new();
+	for component in rest.split(['/', '\\']) {
+		match component {
+			"" | "." => {}
+			".." => {
+				if components.pop().is_none() {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidInput,
+						format!("{} goes above the root of {scheme}://", path.display()),
+					));
+				}
+			}
+			component => components.push(component),
+		}
+	}
+	Ok(Some((scheme, components.join("/"))))
+}
+
+fn join(scheme: &str, directory: &str, name: &str) -> PathBuf {
+	if directory.is_empty() {
+		PathBuf::from(format!("{scheme}://{name}"))
+	} else {
+		PathBuf::from(format!("{scheme}://{directory}/{name}"))
+	}
+}
+
+fn not_found(path: &Path) -> io::Error {
+	io::Error::new(io::ErrorKind::NotFound, format!("{} was not found", path.display()))
+}
+
+/// Pack every file of a directory, recursively, into a zip archive that a [`ZipBackend`] can
+/// read
+pub fn pack_dir<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, archive: Q) -> io::Result<()> {
+	let dir = dir.as_ref();
+	let mut writer = ZipWriter::new(File::create(archive)?);
+	let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+	let mut pending = vec![dir.to_path_buf()];
+	let mut files = Vec::new();
+	while let Some(directory) = pending.pop() {
+		for entry in fs::read_dir(directory)? {
+			let path = entry?.path();
+			if path.is_dir() {
+				pending.push(path);
+			} else {
+				files.push(path);
+			}
+		}
+	}
+	// sorted so packing the same directory twice gives the same archive
+	files.sort();
+	for path in files {
+		let name = path
+			.strip_prefix(dir)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+			.components()
+			.map(|component| component.as_os_str().to_string_lossy())
+			.collect::<Vec<_>>()
+			.join("/");
+		writer.start_file(name, options)?;
+		writer.write_all(&fs::read(&path)?)?;
+	}
+	writer.finish()?;
+	Ok(())
+}
+
+/// Platform directory for the files of a game, such as save games: `%APPDATA%` on Windows,
+/// `~/Library/Application Support` on macOS and `$XDG_DATA_HOME` or `~/.local/share` elsewhere
+pub fn user_dir(game: &str) -> Option<PathBuf> {
+	let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+	let base = if cfg!(windows) {
+		env("APPDATA")?
+	} else if cfg!(target_os = "macos") {
+		env("HOME")?.join("Library/Application Support")
+	} else {
+		env("XDG_DATA_HOME").or_else(|| Some(env("HOME")?.join(".local/share")))?
+	};
+	Some(base.join(game))
+}
+
+/// The process-wide virtual filesystem used by the engine's loaders
+pub fn global() -> &'static RwLock<Vfs> {
+	static GLOBAL: OnceLock<RwLock<Vfs>> = OnceLock::new();
+	GLOBAL.get_or_init(RwLock::default)
+}
+
+/// Mount a backend on the [`global`] filesystem, see [`Vfs::mount`]
+pub fn mount<B: VfsBackend + 'static>(scheme: &str, backend: B) {
+	global().write().unwrap_or_else(PoisonError::into_inner).mount(scheme, backend);
+}
+
+/// Read a file from the [`global`] filesystem, see [`Vfs::read`]
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+	global().read().unwrap_or_else(PoisonError::into_inner).read(path)
+}
+
+/// Open a file of the [`global`] filesystem, see [`Vfs::open`]
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<VfsReader> {
+	global().read().unwrap_or_else(PoisonError::into_inner).open(path)
+}
+
+/// Read a UTF-8 file from the [`global`] filesystem, see [`Vfs::read_to_string`]
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+	global().read().unwrap_or_else(PoisonError::into_inner).read_to_string(path)
+}
+
+/// Does a file exist in the [`global`] filesystem? See [`Vfs::exists`]
+pub fn exists<P: AsRef<Path>>(path: P) -> bool {
+	global().read().unwrap_or_else(PoisonError::into_inner).exists(path)
+}
+
+/// List a directory of the [`global`] filesystem, see [`Vfs::list`]
+pub fn list<P: AsRef<Path>>(path: P) -> io::Result<Vec<PathBuf>> {
+	global().read().unwrap_or_else(PoisonError::into_inner).list(path)
+}
+
+/// Write a file to the [`global`] filesystem, see [`Vfs::write`]
+pub fn write<P: AsRef<Path>>(path: P, data: &[u8]) -> io::Result<()> {
+	global().read().unwrap_or_else(PoisonError::into_inner).write(path, data)
+}
+
+/// Last modification time of a file of the [`global`] filesystem, see [`Vfs::modified`]
+pub fn modified<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
+	global().read().unwrap_or_else(PoisonError::into_inner).modified(path)
+}
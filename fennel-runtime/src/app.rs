@@ -1,10 +1,12 @@
-use std::fs;
+use std::collections::BTreeMap;
+use std::sync::PoisonError;
 use std::time::{Duration, Instant};
 use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 use specs::{Builder, Component, Dispatcher, DispatcherBuilder, World, WorldExt};
 use fennel_plugins::Plugin;
-use fennel_registry::{ComponentFactory, ComponentRegistry};
+use fennel_registry::{AssetGroups, ComponentFactory, ComponentRegistry, SpawnedBy};
+use fennel_resources::vfs::{self, DirBackend, VfsBackend};
 use crate::scenes::{ActiveScene, Scene, SceneSystem};
 use crate::time::{Tick, TickSystem};
 
@@ -31,7 +33,22 @@ pub struct AppBuilder {
     component_registry: ComponentRegistry,
     dispatcher_builder: DispatcherBuilder<'static, 'static>,
     dispatcher_config: Vec<SystemRegistration>,
+    system_names: Vec<&'static str>,
     plugins: Vec<Box<dyn Plugin + Send + Sync>>,
+    /// Backends mounted over the ones of the config
+    mounts: Vec<(String, Box<dyn VfsBackend>)>,
+}
+
+/// Names of the systems registered through [`AppBuilder::register_system`] and by the runtime
+/// itself, and of the plugins
+///
+/// Systems added by plugins directly to the dispatcher aren't listed.
+#[derive(Debug, Default, Clone)]
+pub struct RegisteredSystems {
+    /// System names
+    pub systems: Vec<&'static str>,
+    /// Plugin names
+    pub plugins: Vec<&'static str>,
 }
 
 /// Application config defined by user
@@ -41,6 +58,12 @@ struct Config {
     scenes_path: String,
     /// First scene to display
     initial_scene: String,
+    /// Name of the game, `user://` is mounted on its platform data directory if set
+    #[serde(default)]
+    name: Option<String>,
+    /// VFS scheme - directory mounted on it, or the `.pak` archive next to it if there is one
+    #[serde(default)]
+    mounts: BTreeMap<String, String>,
 }
 
 impl App {
@@ -88,7 +111,9 @@ impl AppBuilder {
             component_registry: ComponentRegistry::new(),
             dispatcher_builder: DispatcherBuilder::new(),
             dispatcher_config: Vec::new(),
+            system_names: Vec::new(),
             plugins: Vec::new(),
+            mounts: Vec::new(),
         }
     }
 
@@ -98,6 +123,13 @@ impl AppBuilder {
         self
     }
 
+    /// Mount a VFS backend on `scheme` when the app is built, over the mounts of the config,
+    /// e.g. a mod directory overriding some of the assets
+    pub fn mount<B: VfsBackend + 'static>(mut self, scheme: &str, backend: B) -> AppBuilder {
+        self.mounts.push((scheme.to_string(), Box::new(backend)));
+        self
+    }
+
     /// Register a system to insert into the dispatcher
     pub fn register_system<S>(
         mut self,
@@ -115,6 +147,7 @@ impl AppBuilder {
         });
 
         self.dispatcher_config.push(reg);
+        self.system_names.push(name);
         self
     }
 
@@ -141,13 +174,22 @@ impl AppBuilder {
 
     /// Builds an [`App`]
     pub fn build(mut self) -> anyhow::Result<App> {
-        let config_reader = fs::read(self.config)?;
+        let config_reader = vfs::read(self.config)?;
         let config: Config = toml::from_slice(&config_reader)?;
-        self.dispatcher_builder.add(SceneSystem, "scene_system", &[]);
+        self.mount_config(&config)?;
+        self.dispatcher_builder.add(SceneSystem::default(), "scene_system", &[]);
         //self.dispatcher_builder.add(SpriteRenderingSystem, "sprite_rendering_system", &[]);
         self.dispatcher_builder.add(TickSystem, "tick_system", &[]);
+        self.system_names.extend(["scene_system", "tick_system"]);
+        #[cfg(feature = "debug-overlay")]
+        {
+            self.dispatcher_builder.add(crate::debug::RuntimeDebugSystem, "runtime_debug_system", &[]);
+            self.system_names.push("runtime_debug_system");
+        }
 
         self.world.register::<Scene>();
+        self.world.register::<SpawnedBy>();
+        self.world.register::<AssetGroups>();
         self.world.insert(Tick {
             ticks: 0,
             tick_rate: 16_000_000,
@@ -157,9 +199,8 @@ impl AppBuilder {
 
         let mut scenes: Vec<Scene> = vec![];
 
-        for entry in fs::read_dir(config.scenes_path)? {
-            let scene_reader =
-                fs::read(entry?.path())?;
+        for path in vfs::list(&config.scenes_path)? {
+            let scene_reader = vfs::read(path)?;
             let scene: Scene = ron::de::from_bytes(&scene_reader)?;
             self.world.create_entity().with(scene.clone()).build();
             scenes.push(scene.clone());
@@ -170,6 +211,10 @@ impl AppBuilder {
             name: String::from("main"),
             loaded: false,
         });
+        self.world.insert(RegisteredSystems {
+            systems: self.system_names,
+            plugins: self.plugins.iter().map(|plugin| plugin.name()).collect(),
+        });
 
         self.plugins.iter_mut().for_each(|plugin| {
             plugin.prepare(&mut self.dispatcher_builder, &mut self.world).unwrap_or_else(|e| {
@@ -190,4 +235,22 @@ impl AppBuilder {
             plugins: self.plugins,
         })
     }
+
+    /// Mount the directories of the config, the user directory and then the backends given to
+    /// [`AppBuilder::mount`] on the global VFS
+    fn mount_config(&mut self, config: &Config) -> anyhow::Result<()> {
+        let mut filesystem = vfs::global().write().unwrap_or_else(PoisonError::into_inner);
+        for (scheme, dir) in &config.mounts {
+            debug!("mounting {dir} on {scheme}://");
+            filesystem.mount_dir_or_pack(scheme, dir)?;
+        }
+        if let Some(dir) = config.name.as_deref().and_then(vfs::user_dir) {
+            debug!("mounting {} on user://", dir.display());
+            filesystem.mount("user", DirBackend::writable(dir));
+        }
+        for (scheme, backend) in self.mounts.drain(..) {
+            filesystem.mount_boxed(&scheme, backend);
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+//! Runtime statistics for the debug overlay of `fennel-graphics`, enabled by the
+//! `debug-overlay` feature
+
+use specs::{Entities, Join, ReadExpect, System, WriteExpect};
+use fennel_graphics::plugin::debug_overlay::DebugOverlay;
+use fennel_graphics::plugin::system::Assets;
+use crate::app::RegisteredSystems;
+use crate::scenes::ActiveScene;
+use crate::time::Tick;
+
+/// Title of the overlay window filled by [`RuntimeDebugSystem`]
+pub const RUNTIME_WINDOW: &str = "runtime";
+
+/// System submitting the TPS, the entity count, the active scene, the registered systems and
+/// plugins and the memory used by assets to the [`DebugOverlay`], does nothing if the graphics
+/// plugin has no debug overlay
+pub struct RuntimeDebugSystem;
+
+impl<'a> System<'a> for RuntimeDebugSystem {
+    type SystemData = (
+        Option<WriteExpect<'a, DebugOverlay>>,
+        ReadExpect<'a, Tick>,
+        ReadExpect<'a, ActiveScene>,
+        ReadExpect<'a, RegisteredSystems>,
+        Option<ReadExpect<'a, Assets>>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (overlay, tick, active_scene, registered, assets, entities): Self::SystemData) {
+        let Some(mut overlay) = overlay else { return };
+        let window = overlay.window(RUNTIME_WINDOW);
+        window
+            .line("tps", format!("{:.1}", tick.tps()))
+            .line("entities", entities.join().count())
+            .line("scene", &active_scene.name)
+            .line("plugins", registered.plugins.join(", "))
+            .text("systems:");
+        for system in &registered.systems {
+            window.text(format!("  {system}"));
+        }
+        if let Some(assets) = assets {
+            window.text("assets:");
+            for usage in assets.memory() {
+                let type_name = usage.type_name.rsplit("::").next().unwrap_or(usage.type_name);
+                window.text(format!("  {type_name}: {} ({:.1} MiB)", usage.count, usage.bytes as f64 / (1024.0 * 1024.0)));
+            }
+        }
+    }
+}
@@ -8,6 +8,9 @@
 
 /// Application layer module
 pub mod app;
+/// Module submitting runtime statistics to the debug overlay
+#[cfg(feature = "debug-overlay")]
+pub mod debug;
 /// Module providing advanced rendering functionality
 pub mod renderer;
 /// Module providing functionality of scenes
@@ -1,13 +1,19 @@
 //! Scenes are compositions of entities/components and scripts the user sees on their screen.
 //! A scene has a name, a list of scripts it uses (doesn't actually own them, retrieves from
 //! resource manager) and a list of entities (same as with scripts)
+//!
+//! Switching to another scene with [`ActiveScene::switch`] deletes the entities of the previous
+//! one, along with the entities [`SpawnedBy`] them. A scene can list the manifest asset groups it
+//! needs, they're kept loaded while it's active so a level's textures are freed when the game
+//! moves on to the next one.
 
+use std::collections::HashSet;
 use log::debug;
 use ron::Value;
 use serde::Deserialize;
-use specs::{Component, DenseVecStorage, WriteExpect};
+use specs::{Component, DenseVecStorage, Entity, WriteExpect};
 use specs::{Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System};
-use fennel_registry::ComponentRegistry;
+use fennel_registry::{AssetGroups, ComponentRegistry, SpawnedBy};
 
 /// Scene struct
 #[derive(Deserialize, Debug, Clone, Component)]
@@ -16,6 +22,9 @@ pub struct Scene {
     pub name: String,
     /// List of entities in a scene
     pub entities: Vec<EntityDescriptor>,
+    /// Manifest asset groups the scene depends on
+    #[serde(default)]
+    pub assets: Vec<String>,
 }
 
 /// Descriptor of an entity in scene config
@@ -44,32 +53,54 @@ pub struct ActiveScene {
     pub loaded: bool,
 }
 
+impl ActiveScene {
+    /// Replace the active scene on the next tick, deleting the entities of the current one
+    pub fn switch(&mut self, name: &str) {
+        self.name = name.to_string();
+        self.loaded = false;
+    }
+}
+
 /// Scene loading system
-pub struct SceneSystem;
+#[derive(Default)]
+pub struct SceneSystem {
+    /// Entities created for the active scene
+    spawned: Vec<Entity>,
+}
 
 impl<'a> System<'a> for SceneSystem {
     type SystemData = (
         ReadStorage<'a, Scene>,
+        ReadStorage<'a, SpawnedBy>,
         Entities<'a>,
         WriteExpect<'a, ActiveScene>,
         ReadExpect<'a, ComponentRegistry>,
         Read<'a, LazyUpdate>,
     );
 
-    fn run(&mut self, (scenes, entities, mut active_scene, component_registry, lazy): Self::SystemData) {
+    fn run(&mut self, (scenes, spawned_by, entities, mut active_scene, component_registry, lazy): Self::SystemData) {
         let active_name = active_scene.name.clone();
         for scene in scenes
             .join()
             .filter(|s| s.name == active_name)
         {
             if !active_scene.loaded {
+                for entity in with_spawned(self.spawned.drain(..).collect(), &entities, &spawned_by) {
+                    entities.delete(entity).ok();
+                }
+                if !scene.assets.is_empty() {
+                    let entity = entities.create();
+                    lazy.insert(entity, AssetGroups { groups: scene.assets.clone() });
+                    self.spawned.push(entity);
+                }
                 for ent_def in &scene.entities {
+                    let entity = entities.create();
+                    self.spawned.push(entity);
                     for component in &ent_def.components {
                         debug!(
                             "loading component {} with parameters {:?}",
                             component.id, component.config
                         );
-                        let entity = entities.create();
 
                         // iirc no way to return an error from a system, and it's really a fatal error if a factory doesn't
                         // exist (means either the scene is defined wrongly or the developer didn't register a factory) so
@@ -86,3 +117,18 @@ impl<'a> System<'a> for SceneSystem {
         }
     }
 }
+
+/// Entities along with the entities [`SpawnedBy`] them, recursively
+fn with_spawned(mut owners: HashSet<Entity>, entities: &Entities, spawned_by: &ReadStorage<SpawnedBy>) -> HashSet<Entity> {
+    loop {
+        let spawned: Vec<Entity> = (entities, spawned_by)
+            .join()
+            .filter(|(entity, spawned)| owners.contains(&spawned.owner) && !owners.contains(entity))
+            .map(|(entity, _)| entity)
+            .collect();
+        if spawned.is_empty() {
+            return owners;
+        }
+        owners.extend(spawned);
+    }
+}
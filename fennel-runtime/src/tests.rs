@@ -1,3 +1,6 @@
+use specs::{Builder, Join, RunNow, World, WorldExt};
+use fennel_registry::{AssetGroups, ComponentRegistry, SpawnedBy};
+use crate::scenes::{ActiveScene, Scene, SceneSystem};
 use crate::time::Tick;
 
 #[test]
@@ -9,4 +12,48 @@ fn tps_calculation() {
     };
 
     assert_eq!(tick.tps(), 100.0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn scene_asset_dependencies() {
+    let scene: Scene = ron::from_str(r#"(name: "forest", entities: [], assets: ["forest", "boss"])"#).unwrap();
+    assert_eq!(scene.assets, ["forest", "boss"]);
+    let scene: Scene = ron::from_str(r#"(name: "menu", entities: [])"#).unwrap();
+    assert!(scene.assets.is_empty());
+}
+
+#[test]
+fn switching_scenes_deletes_spawned_entities() {
+    let mut world = World::new();
+    world.register::<Scene>();
+    world.register::<SpawnedBy>();
+    world.register::<AssetGroups>();
+    world.insert(ComponentRegistry::new());
+    world.insert(ActiveScene { name: "forest".to_string(), loaded: false });
+    let forest: Scene = ron::from_str(r#"(name: "forest", entities: [(id: "map", components: [])], assets: ["forest"])"#).unwrap();
+    let menu: Scene = ron::from_str(r#"(name: "menu", entities: [])"#).unwrap();
+    world.create_entity().with(forest).build();
+    world.create_entity().with(menu).build();
+
+    let mut system = SceneSystem::default();
+    system.run_now(&world);
+    world.maintain();
+    let groups: Vec<Vec<String>> = world.read_storage::<AssetGroups>().join().map(|groups| groups.groups.clone()).collect();
+    assert_eq!(groups, [["forest"]]);
+
+    // entities spawned by the scene's entities, and by those, go along with them
+    let map = world.entities().join().find(|&entity| {
+        world.read_storage::<Scene>().get(entity).is_none() && world.read_storage::<AssetGroups>().get(entity).is_none()
+    }).unwrap();
+    let object = world.create_entity().with(SpawnedBy { owner: map }).build();
+    let nested = world.create_entity().with(SpawnedBy { owner: object }).build();
+
+    world.write_resource::<ActiveScene>().switch("menu");
+    system.run_now(&world);
+    world.maintain();
+    assert!(!world.is_alive(map));
+    assert!(!world.is_alive(object));
+    assert!(!world.is_alive(nested));
+    assert_eq!(world.read_storage::<AssetGroups>().join().count(), 0);
+    assert_eq!(world.read_storage::<Scene>().join().count(), 2);
+}